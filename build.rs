@@ -9,4 +9,17 @@ fn main() {
         .build_client(true)
         .compile(&["proto/test.proto"], &["proto"])
         .unwrap();
+
+    // google.rpc error model, carried in the `grpc-status-details-bin` trailer
+    tonic_build::configure()
+        .build_server(false)
+        .build_client(false)
+        .compile(
+            &[
+                "proto/google/rpc/status.proto",
+                "proto/google/rpc/error_details.proto",
+            ],
+            &["proto"],
+        )
+        .unwrap();
 }
@@ -0,0 +1,227 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Attribute, Data, DeriveInput, Error, Ident, LitInt, LitStr, Path, Result};
+
+/// Attributes set with `#[error_model(...)]`
+#[derive(Clone, Default)]
+struct Attrs {
+    reasons: Option<Path>,
+    service: Option<LitStr>,
+    status: Option<Ident>,
+    code: Option<LitInt>,
+    reason: Option<Ident>,
+    domain: Option<LitStr>,
+}
+
+/// Model settings of an enum variant, or of a struct
+struct Model {
+    pattern: TokenStream,
+    status: Ident,
+    code: LitInt,
+    reason: Option<(Ident, LitStr)>,
+}
+
+pub fn expand(input: DeriveInput) -> Result<TokenStream> {
+    let container = Attrs::parse(&input.attrs)?;
+
+    let models = match &input.data {
+        Data::Enum(data) => {
+            if let Some(status) = &container.status {
+                return Err(Error::new_spanned(
+                    status,
+                    "set the status on each variant of an enum",
+                ));
+            }
+
+            data.variants
+                .iter()
+                .map(|variant| {
+                    let attrs = Attrs::parse(&variant.attrs)?;
+                    if let Some(reasons) = &attrs.reasons {
+                        return Err(Error::new_spanned(
+                            reasons,
+                            "set the reasons type on the enum",
+                        ));
+                    }
+
+                    let ident = &variant.ident;
+                    Model::new(
+                        quote!(Self::#ident { .. }),
+                        &variant.ident,
+                        attrs,
+                        &container,
+                    )
+                })
+                .collect::<Result<Vec<_>>>()?
+        }
+        Data::Struct(_) => vec![Model::new(
+            quote!(Self { .. }),
+            &input.ident,
+            container.clone(),
+            &container,
+        )?],
+        Data::Union(_) => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "ToErrorModel cannot be derived for unions",
+            ))
+        }
+    };
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let reasons = match &container.reasons {
+        Some(reasons) => quote!(#reasons),
+        None => quote!(::nats_transport::error::ErrorReason),
+    };
+
+    let status_arms = models.iter().map(|model| {
+        let (pattern, status) = (&model.pattern, &model.status);
+        quote!(#pattern => ::nats_transport::error::Status::#status)
+    });
+    let code_arms = models.iter().map(|model| {
+        let (pattern, code) = (&model.pattern, &model.code);
+        quote!(#pattern => #code)
+    });
+    let details_arms = models.iter().map(|model| {
+        let pattern = &model.pattern;
+        match &model.reason {
+            Some((reason, domain)) => quote!(#pattern => Some((#reasons::#reason, #domain))),
+            None => quote!(#pattern => None),
+        }
+    });
+    let service = container.service.as_ref().map(|service| {
+        quote! {
+            model = model.append_metadata(
+                ::nats_transport::error::MetaKeys::Service,
+                #service.to_string(),
+            );
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::nats_transport::error::ToErrorModel<#reasons> for #ident #ty_generics #where_clause {
+            fn to_error_model(
+                &self,
+                requestor: Option<i64>,
+                request: Option<String>,
+            ) -> ::nats_transport::error::ErrorModel<#reasons> {
+                let model = ::nats_transport::error::ErrorModel::new(
+                    self.status(),
+                    self.error_code(),
+                    self.msg(),
+                );
+
+                let details: Option<(#reasons, &str)> = match self {
+                    #(#details_arms,)*
+                };
+                let (reason, domain) = match details {
+                    Some(details) => details,
+                    None => return model,
+                };
+
+                let mut model = model.with_details(reason, domain.to_string());
+                #service
+
+                if let Some(request) = request {
+                    model = model.append_metadata(::nats_transport::error::MetaKeys::Request, request);
+                }
+
+                if let Some(requestor) = requestor {
+                    model = model.append_metadata(
+                        ::nats_transport::error::MetaKeys::Requestor,
+                        requestor.to_string(),
+                    );
+                }
+
+                model
+            }
+
+            fn msg(&self) -> String {
+                self.to_string()
+            }
+
+            fn error_code(&self) -> i32 {
+                match self {
+                    #(#code_arms,)*
+                }
+            }
+
+            fn status(&self) -> ::nats_transport::error::Status {
+                match self {
+                    #(#status_arms,)*
+                }
+            }
+        }
+    })
+}
+
+impl Attrs {
+    fn parse(attrs: &[Attribute]) -> Result<Self> {
+        let mut parsed = Attrs::default();
+
+        for attr in attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("error_model"))
+        {
+            attr.parse_nested_meta(|meta| {
+                let value = meta.value()?;
+
+                if meta.path.is_ident("reasons") {
+                    parsed.reasons = Some(value.parse()?);
+                } else if meta.path.is_ident("service") {
+                    parsed.service = Some(value.parse()?);
+                } else if meta.path.is_ident("status") {
+                    parsed.status = Some(value.parse()?);
+                } else if meta.path.is_ident("code") {
+                    parsed.code = Some(value.parse()?);
+                } else if meta.path.is_ident("reason") {
+                    parsed.reason = Some(value.parse()?);
+                } else if meta.path.is_ident("domain") {
+                    parsed.domain = Some(value.parse()?);
+                } else {
+                    return Err(meta.error(
+                        "unsupported error_model attribute, expected one of \
+                         `reasons`, `service`, `status`, `code`, `reason` or `domain`",
+                    ));
+                }
+
+                Ok(())
+            })?;
+        }
+
+        Ok(parsed)
+    }
+}
+
+impl Model {
+    fn new(pattern: TokenStream, ident: &Ident, attrs: Attrs, container: &Attrs) -> Result<Self> {
+        let missing = |attr: &str| {
+            Error::new(
+                ident.span(),
+                format!("missing `#[error_model({} = ...)]`", attr),
+            )
+        };
+
+        let status = attrs.status.ok_or_else(|| missing("status"))?;
+        let code = attrs.code.ok_or_else(|| missing("code"))?;
+
+        let reason = match attrs.reason {
+            Some(reason) => {
+                let domain = attrs
+                    .domain
+                    .or_else(|| container.domain.clone())
+                    .ok_or_else(|| missing("domain"))?;
+                Some((reason, domain))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            pattern,
+            status,
+            code,
+            reason,
+        })
+    }
+}
@@ -0,0 +1,46 @@
+//! Derive macros generating the error boilerplate of the nats-transport crate.
+//! They are re-exported from `nats_transport::error`, and the generated code refers to the
+//! `nats_transport` crate by name.
+
+#![deny(unsafe_code, unused_qualifications, trivial_casts)]
+#![deny(clippy::all)]
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+mod error_model;
+mod reason;
+
+/// Derives `ToErrorModel<R>` from `#[error_model(...)]` attributes.
+///
+/// On an enum, the container attribute sets the reason type (`reasons`, `ErrorReason` by default),
+/// the default error `domain` and the `service` added to the metadata. Each variant sets its
+/// `status`, `code` and optionally `reason` and `domain`. On a struct, all the attributes go on
+/// the container. The message of the model is the `Display` of the error.
+///
+/// Variants with a `reason` get one `ErrorDetails` with the service, request and requestor
+/// metadata; variants without a reason get no details. See `nats_transport::error::ToErrorModel`
+/// for examples.
+#[proc_macro_derive(ToErrorModel, attributes(error_model))]
+pub fn derive_to_error_model(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    error_model::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derives `Display`, `FromStr` and `ErrorReasons` for an enum of error reasons.
+///
+/// Each variant is displayed as its name in UPPER_SNAKE_CASE (`ChatTitleEmpty` is
+/// `CHAT_TITLE_EMPTY`), or as set with `#[error_reason(rename = "...")]`. Reasons are checked
+/// at compile time against `[A-Z][A-Z0-9_]+[A-Z0-9]`, and may be at most 63 characters long.
+/// See `nats_transport::error::ErrorReason` for examples.
+#[proc_macro_derive(ErrorReason, attributes(error_reason))]
+pub fn derive_error_reason(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    reason::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,167 @@
+use std::collections::HashSet;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Fields, LitStr, Result};
+
+/// Longest reason allowed by the Google error model
+const MAX_REASON_LEN: usize = 63;
+
+pub fn expand(input: DeriveInput) -> Result<TokenStream> {
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "ErrorReason can only be derived for enums",
+            ))
+        }
+    };
+
+    let mut variants = Vec::with_capacity(data.variants.len());
+    let mut reasons = HashSet::new();
+
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new_spanned(
+                variant,
+                "error reasons must be unit variants",
+            ));
+        }
+
+        let reason = match rename(variant)? {
+            Some(rename) => {
+                validate(&rename.value()).map_err(|msg| Error::new_spanned(&rename, msg))?;
+                rename.value()
+            }
+            None => {
+                let reason = upper_snake_case(&variant.ident.to_string());
+                validate(&reason).map_err(|msg| Error::new_spanned(&variant.ident, msg))?;
+                reason
+            }
+        };
+
+        if !reasons.insert(reason.clone()) {
+            return Err(Error::new_spanned(
+                variant,
+                format!("duplicate error reason: {}", reason),
+            ));
+        }
+
+        variants.push((&variant.ident, reason));
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let display_arms = variants
+        .iter()
+        .map(|(variant, reason)| quote!(Self::#variant => #reason));
+    let from_str_arms = variants
+        .iter()
+        .map(|(variant, reason)| quote!(#reason => Ok(Self::#variant)));
+
+    Ok(quote! {
+        impl #impl_generics ::std::fmt::Display for #ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.write_str(match self {
+                    #(#display_arms,)*
+                })
+            }
+        }
+
+        impl #impl_generics ::std::str::FromStr for #ident #ty_generics #where_clause {
+            type Err = ::nats_transport::error::ConversionError;
+
+            fn from_str(reason: &str) -> ::std::result::Result<Self, Self::Err> {
+                match reason {
+                    #(#from_str_arms,)*
+                    reason => Err(::nats_transport::error::ConversionError::UnknownReason(
+                        reason.to_string(),
+                    )),
+                }
+            }
+        }
+
+        impl #impl_generics ::nats_transport::error::ErrorReasons for #ident #ty_generics #where_clause {}
+    })
+}
+
+/// Reason set with `#[error_reason(rename = "...")]`
+fn rename(variant: &syn::Variant) -> Result<Option<LitStr>> {
+    let mut rename = None;
+
+    for attr in variant
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("error_reason"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                rename = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported error_reason attribute, expected `rename`"))
+            }
+        })?;
+    }
+
+    Ok(rename)
+}
+
+/// Converts a variant name to UPPER_SNAKE_CASE, e.g. `ApiKeyInvalid` to `API_KEY_INVALID`
+/// and `HTTPTimeout` to `HTTP_TIMEOUT`
+pub(crate) fn upper_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut reason = String::with_capacity(name.len() + 4);
+
+    for (i, c) in chars.iter().enumerate() {
+        if i > 0 && c.is_uppercase() {
+            let previous = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+
+            if previous.is_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_uppercase() && next_is_lower)
+            {
+                reason.push('_');
+            }
+        }
+        reason.extend(c.to_uppercase());
+    }
+
+    reason
+}
+
+/// Checks the reason against `[A-Z][A-Z0-9_]+[A-Z0-9]`, at most 63 characters long
+pub(crate) fn validate(reason: &str) -> std::result::Result<(), String> {
+    let bytes = reason.as_bytes();
+    let upper_or_digit = |b: &u8| b.is_ascii_uppercase() || b.is_ascii_digit();
+
+    let valid = bytes.len() >= 3
+        && bytes[0].is_ascii_uppercase()
+        && bytes[1..bytes.len() - 1]
+            .iter()
+            .all(|b| upper_or_digit(b) || *b == b'_')
+        && upper_or_digit(&bytes[bytes.len() - 1]);
+
+    if !valid {
+        return Err(format!(
+            "invalid error reason `{}`, expected UPPER_SNAKE_CASE matching [A-Z][A-Z0-9_]+[A-Z0-9]",
+            reason
+        ));
+    }
+
+    if reason.len() > MAX_REASON_LEN {
+        return Err(format!(
+            "error reason `{}` is longer than {} characters",
+            reason, MAX_REASON_LEN
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "./reason_tests.rs"]
+mod reason_tests;
@@ -0,0 +1,26 @@
+#[cfg(test)]
+mod reason_tests {
+    use crate::reason::{upper_snake_case, validate};
+
+    #[test]
+    fn test_upper_snake_case() {
+        assert_eq!(upper_snake_case("ChatTitleEmpty"), "CHAT_TITLE_EMPTY");
+        assert_eq!(upper_snake_case("ApiKeyInvalid"), "API_KEY_INVALID");
+        assert_eq!(upper_snake_case("HTTPTimeout"), "HTTP_TIMEOUT");
+        assert_eq!(upper_snake_case("Limit10Exceeded"), "LIMIT10_EXCEEDED");
+        assert_eq!(upper_snake_case("Oauth2"), "OAUTH2");
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate("CHAT_TITLE_EMPTY").is_ok());
+        assert!(validate("HTTP2").is_ok());
+
+        assert!(validate("AB").is_err());
+        assert!(validate("chat_title_empty").is_err());
+        assert!(validate("_CHAT_TITLE").is_err());
+        assert!(validate("CHAT_TITLE_").is_err());
+        assert!(validate("CHAT-TITLE").is_err());
+        assert!(validate(&"A".repeat(64)).is_err());
+    }
+}
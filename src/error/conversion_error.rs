@@ -0,0 +1,16 @@
+/// Error returned when an error received from a remote service cannot be converted back
+/// into an [ErrorModel](super::ErrorModel)
+#[derive(Debug, thiserror::Error)]
+pub enum ConversionError {
+    #[error("invalid error details: {0}")]
+    InvalidDetails(#[from] prost::DecodeError),
+
+    #[error("invalid error details header: {0}")]
+    InvalidDetailsHeader(#[from] base64::DecodeError),
+
+    #[error("unknown error status: {0}")]
+    UnknownStatus(i32),
+
+    #[error("unknown error reason: {0}")]
+    UnknownReason(String),
+}
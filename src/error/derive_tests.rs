@@ -0,0 +1,141 @@
+#[cfg(test)]
+mod derive_tests {
+    use crate::error::{ConversionError, ErrorReason, MetaKeys, Status, ToErrorModel};
+
+    use super::{ChatGroupError, ChatGroupErrorReason, QuotaExceeded};
+
+    #[test]
+    fn test_derived_reason_display_and_from_str() {
+        assert_eq!(
+            ChatGroupErrorReason::ChatTitleEmpty.to_string(),
+            "CHAT_TITLE_EMPTY"
+        );
+        assert_eq!(
+            ChatGroupErrorReason::AboutTooLong.to_string(),
+            "CHAT_ABOUT_TOO_LONG"
+        );
+        assert_eq!(
+            "CHAT_ABOUT_TOO_LONG"
+                .parse::<ChatGroupErrorReason>()
+                .unwrap(),
+            ChatGroupErrorReason::AboutTooLong
+        );
+        assert!(matches!(
+            "ABOUT_TOO_LONG".parse::<ChatGroupErrorReason>(),
+            Err(ConversionError::UnknownReason(_))
+        ));
+
+        assert_eq!(ErrorReason::ApiKeyInvalid.to_string(), "API_KEY_INVALID");
+        assert_eq!(
+            "UNSUPPORTED_REQUEST".parse::<ErrorReason>().unwrap(),
+            ErrorReason::UnsupportedRequest
+        );
+    }
+
+    #[test]
+    fn test_derived_error_model_with_reason() {
+        let model = ChatGroupError::TitleEmpty.to_error_model(
+            Some(1234567890),
+            Some("chat.chatgroup.command.create".to_string()),
+        );
+
+        assert_eq!(model.status, Status::InvalidArgument);
+        assert_eq!(model.code, 400);
+        assert_eq!(model.message, "No chat title provided.");
+
+        let details = model.details.first().unwrap();
+        assert_eq!(details.reason, ChatGroupErrorReason::ChatTitleEmpty);
+        assert_eq!(details.domain, "runtiva.com");
+        assert_eq!(
+            details.metadata.get(&MetaKeys::Service).unwrap(),
+            "chat-persist.runtiva.com"
+        );
+        assert_eq!(
+            details.metadata.get(&MetaKeys::Request).unwrap(),
+            "chat.chatgroup.command.create"
+        );
+        assert_eq!(
+            details.metadata.get(&MetaKeys::Requestor).unwrap(),
+            "1234567890"
+        );
+    }
+
+    #[test]
+    fn test_derived_error_model_domain_override() {
+        let model = ChatGroupError::AboutTooLong(300).to_error_model(None, None);
+
+        assert_eq!(model.message, "Chat about is too long: 300 characters");
+        assert_eq!(model.details[0].domain, "chat.runtiva.com");
+        assert!(!model.details[0].metadata.contains_key(&MetaKeys::Request));
+    }
+
+    #[test]
+    fn test_derived_error_model_without_reason() {
+        let model = ChatGroupError::Database {
+            msg: "connection lost".to_string(),
+        }
+        .to_error_model(Some(1), None);
+
+        assert_eq!(model.status, Status::DatabaseError);
+        assert_eq!(model.code, 500);
+        assert!(model.details.is_empty());
+    }
+
+    #[test]
+    fn test_derived_error_model_for_struct() {
+        let model = QuotaExceeded { limit: 10 }.to_error_model(None, None);
+
+        assert_eq!(model.status, Status::ResourceExhausted);
+        assert_eq!(model.code, 429);
+        assert_eq!(model.details[0].reason, ErrorReason::ApiKeyInvalid);
+        assert_eq!(model.details[0].domain, "runtiva.com");
+    }
+}
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{ErrorReason, ToErrorModel};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ErrorReason)]
+pub enum ChatGroupErrorReason {
+    ChatTitleEmpty,
+    #[error_reason(rename = "CHAT_ABOUT_TOO_LONG")]
+    AboutTooLong,
+}
+
+#[derive(Debug, thiserror::Error, ToErrorModel)]
+#[error_model(
+    reasons = ChatGroupErrorReason,
+    domain = "runtiva.com",
+    service = "chat-persist.runtiva.com"
+)]
+pub enum ChatGroupError {
+    #[error("No chat title provided.")]
+    #[error_model(status = InvalidArgument, code = 400, reason = ChatTitleEmpty)]
+    TitleEmpty,
+
+    #[error("Chat about is too long: {0} characters")]
+    #[error_model(
+        status = InvalidArgument,
+        code = 400,
+        reason = AboutTooLong,
+        domain = "chat.runtiva.com"
+    )]
+    AboutTooLong(usize),
+
+    #[error("Database error: {msg}")]
+    #[error_model(status = DatabaseError, code = 500)]
+    Database { msg: String },
+}
+
+#[derive(Debug, thiserror::Error, ToErrorModel)]
+#[error("Quota of {limit} chat groups exceeded")]
+#[error_model(
+    status = ResourceExhausted,
+    code = 429,
+    reason = ApiKeyInvalid,
+    domain = "runtiva.com"
+)]
+pub struct QuotaExceeded {
+    limit: u32,
+}
@@ -1,10 +1,11 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt::Display, str::FromStr, time::Duration};
 
+use num_traits::FromPrimitive;
 use serde::{Deserialize, Serialize};
 
 use chat_proto::runtiva::nats::v1 as proto_nats;
 
-use super::{MetaKeys, Status};
+use super::{ConversionError, MetaKeys, MetadataError, Status, TypedDetail};
 
 pub trait ToErrorModel<R> {
     fn to_error_model(&self, requestor: Option<i64>, request: Option<String>) -> ErrorModel<R>;
@@ -23,6 +24,23 @@ pub struct ErrorModel<R> {
     pub status: Status,
 
     pub details: Vec<ErrorDetails<R>>,
+
+    /// Details received from a remote service with a reason that does not parse into `R`,
+    /// e.g. a reason added by a newer version of the service
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unknown_details: Vec<ErrorDetails<String>>,
+
+    /// Other standard details of the Google error model, e.g. the field violations of a
+    /// [BadRequest](super::BadRequest). They are carried in JSON and in a gRPC status, and
+    /// next to a `proto_nats::ErrorReply` in the
+    /// [ERROR_DETAILS_HEADER](crate::response::ERROR_DETAILS_HEADER) header.
+    /// Details of unknown types are skipped when deserialized.
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        deserialize_with = "super::typed_detail::deserialize_known"
+    )]
+    pub typed_details: Vec<TypedDetail>,
 }
 
 impl<R> ErrorModel<R> {
@@ -33,6 +51,8 @@ impl<R> ErrorModel<R> {
             code,
             message,
             details: vec![],
+            unknown_details: vec![],
+            typed_details: vec![],
         }
     }
 
@@ -42,13 +62,85 @@ impl<R> ErrorModel<R> {
         self
     }
 
-    pub fn append_metadata(mut self, key: MetaKeys, value: String) -> Self {
+    /// Adds a standard detail, e.g.
+    /// `model.with_typed_detail(BadRequest::new().field_violation("title", "must not be empty"))`
+    pub fn with_typed_detail(mut self, detail: impl Into<TypedDetail>) -> Self {
+        self.typed_details.push(detail.into());
+        self
+    }
+
+    /// Adds the metadata entry to the last details. The key is a [MetaKeys], or an application
+    /// key type converting into one (see [MetaKeys::custom]).
+    pub fn append_metadata(mut self, key: impl Into<MetaKeys>, value: String) -> Self {
         let details = self.details.last_mut().unwrap();
-        details.metadata.insert(key, value);
+        details.metadata.insert(key.into(), value);
         self
     }
+
+    /// Adds an entity id under the `<name>Id` key, e.g. `chatId`, to the last details.
+    /// Fails for an invalid [MetaKeys::custom] key or a model without details.
+    pub fn append_id(self, name: &str, id: impl Display) -> Result<Self, MetadataError> {
+        self.try_append_metadata(MetaKeys::id(name)?, id.to_string())
+    }
+
+    /// Adds an integer value under the `<name><Unit>` key, e.g. `activeChatGroupsCount`
+    pub fn append_int(self, name: &str, value: i64, unit: &str) -> Result<Self, MetadataError> {
+        self.try_append_metadata(MetaKeys::unit(name, unit)?, value.to_string())
+    }
+
+    /// Adds a value per unit under the `<name>Per<Unit>` key, e.g. `limitPerRequest`
+    pub fn append_per(self, name: &str, value: i64, unit: &str) -> Result<Self, MetadataError> {
+        self.try_append_metadata(MetaKeys::per(name, unit)?, value.to_string())
+    }
+
+    /// Adds a duration in whole milliseconds under the `<name>Millis` key, e.g. `retryDelayMillis`
+    pub fn append_duration(self, name: &str, value: Duration) -> Result<Self, MetadataError> {
+        self.try_append_metadata(MetaKeys::millis(name)?, value.as_millis().to_string())
+    }
+
+    /// Adds the metadata entry to the last details, failing when there are none
+    fn try_append_metadata(mut self, key: MetaKeys, value: String) -> Result<Self, MetadataError> {
+        match self.details.last_mut() {
+            Some(details) => {
+                details.metadata.insert(key, value);
+                Ok(self)
+            }
+            None => Err(MetadataError::NoDetails(key)),
+        }
+    }
+
+    /// Adds details received from a remote service, keeping them in
+    /// [unknown_details](ErrorModel::unknown_details) when the reason does not parse into `R`
+    pub(crate) fn push_remote_details(
+        &mut self,
+        reason: String,
+        domain: String,
+        metadata: impl IntoIterator<Item = (String, String)>,
+    ) where
+        R: FromStr,
+    {
+        let metadata = metadata
+            .into_iter()
+            .map(|(key, value)| {
+                let key = key.parse().unwrap_or_else(|never| match never {});
+                (key, value)
+            })
+            .collect();
+
+        match reason.parse() {
+            Ok(reason) => self
+                .details
+                .push(ErrorDetails::new(reason, domain, metadata)),
+            Err(_) => self
+                .unknown_details
+                .push(ErrorDetails::new(reason, domain, metadata)),
+        }
+    }
 }
 
+/// Converts the model into a protobuf error reply. The reply only carries `ErrorInfo` details,
+/// the [typed details](ErrorModel::typed_details) are sent in a header, see
+/// [ErrorModel::typed_details_header].
 impl<R> From<ErrorModel<R>> for proto_nats::ErrorReply
 where
     R: ToString,
@@ -58,6 +150,9 @@ where
         for detail in val.details {
             details.push(detail.into());
         }
+        for detail in val.unknown_details {
+            details.push(detail.into());
+        }
 
         proto_nats::ErrorReply {
             code: val.code,
@@ -68,6 +163,33 @@ where
     }
 }
 
+/// Converts an error reply received from a remote service back into a model. Details with a
+/// reason that does not parse into `R` are kept in [ErrorModel::unknown_details], and unknown
+/// metadata keys as [MetaKeys::Custom]. Fails only for a status that is not a [Status].
+/// The typed details are restored from the reply header with [ErrorModel::with_typed_details_header].
+impl<R> TryFrom<proto_nats::ErrorReply> for ErrorModel<R>
+where
+    R: FromStr,
+{
+    type Error = ConversionError;
+
+    fn try_from(reply: proto_nats::ErrorReply) -> Result<Self, Self::Error> {
+        let status =
+            Status::from_i32(reply.status).ok_or(ConversionError::UnknownStatus(reply.status))?;
+
+        let mut model = ErrorModel::new(status, reply.code, reply.message);
+        for detail in reply.details {
+            let metadata = detail
+                .metadata
+                .into_iter()
+                .map(|entry| (entry.key, entry.value));
+            model.push_remote_details(detail.reason, detail.domain, metadata);
+        }
+
+        Ok(model)
+    }
+}
+
 /// Describes the cause of the error with structured details.
 /// This is based on GCP Cloud API Error best practices:
 /// https://cloud.google.com/apis/design/errors#error_model
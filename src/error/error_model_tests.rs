@@ -1,7 +1,8 @@
 #[cfg(test)]
 mod error_model_tests {
 
-    pub use crate::error::{ErrorModel, MetaKeys, Status, ToErrorModel};
+    pub use crate::error::{ConversionError, ErrorModel, MetaKeys, Status, ToErrorModel};
+    use chat_proto::runtiva::nats::v1 as proto_nats;
 
     use super::MySampleError;
 
@@ -65,9 +66,98 @@ mod error_model_tests {
             &"chat-persist.runtiva.com".to_string()
         );
     }
+
+    fn sample_model() -> ErrorModel<super::SampleErrorReason> {
+        let err = MySampleError {
+            msg: "No chat title provided.".to_string(),
+            reason: super::SampleErrorReason::ChatTitleEmpty,
+            source: super::SampleError::InvalidArgument(
+                "Missing Argument: No chat title provided.".to_string(),
+            ),
+        };
+
+        err.to_error_model(Some(1234567890), None)
+    }
+
+    #[test]
+    fn test_error_reply_and_back() {
+        let reply: proto_nats::ErrorReply = sample_model().into();
+
+        let model: ErrorModel<super::SampleErrorReason> = reply.try_into().unwrap();
+
+        assert_eq!(model.code, 400);
+        assert_eq!(model.status, Status::InvalidArgument);
+        assert_eq!(model.message, "No chat title provided.".to_string());
+        assert!(model.unknown_details.is_empty());
+
+        let details = model.details.first().unwrap();
+        assert_eq!(details.reason, super::SampleErrorReason::ChatTitleEmpty);
+        assert_eq!(details.domain, "runtiva.com".to_string());
+        assert_eq!(
+            details.metadata.get(&MetaKeys::Requestor).unwrap(),
+            &1234567890.to_string()
+        );
+        assert_eq!(
+            details.metadata.get(&MetaKeys::Service).unwrap(),
+            &"chat-persist.runtiva.com".to_string()
+        );
+    }
+
+    #[test]
+    fn test_error_reply_keeps_unknown_reasons_and_keys() {
+        let reply = proto_nats::ErrorReply {
+            code: 429,
+            message: "Too many chat groups".to_string(),
+            status: Status::ResourceExhausted as i32,
+            details: vec![proto_nats::ErrorDetails {
+                reason: "CHAT_GROUP_LIMIT".to_string(),
+                domain: "runtiva.com".to_string(),
+                metadata: vec![proto_nats::MetaData {
+                    key: "limitPerRequest".to_string(),
+                    value: "10".to_string(),
+                }],
+            }],
+        };
+
+        let model: ErrorModel<super::SampleErrorReason> = reply.try_into().unwrap();
+
+        assert!(model.details.is_empty());
+        let details = model.unknown_details.first().unwrap();
+        assert_eq!(details.reason, "CHAT_GROUP_LIMIT".to_string());
+        assert_eq!(
+            details
+                .metadata
+                .get(&MetaKeys::custom("limitPerRequest").unwrap())
+                .unwrap(),
+            "10"
+        );
+
+        // unknown details are sent on when the model is serialized again
+        let json = serde_json::to_value(&model).unwrap();
+        assert_eq!(
+            json["unknown_details"][0]["metadata"]["limitPerRequest"],
+            "10"
+        );
+        let reply: proto_nats::ErrorReply = model.into();
+        assert_eq!(reply.details[0].reason, "CHAT_GROUP_LIMIT".to_string());
+    }
+
+    #[test]
+    fn test_error_reply_with_unknown_status() {
+        let reply = proto_nats::ErrorReply {
+            code: 500,
+            message: "".to_string(),
+            status: 99,
+            details: vec![],
+        };
+
+        let result: Result<ErrorModel<super::SampleErrorReason>, _> = reply.try_into();
+
+        assert!(matches!(result, Err(ConversionError::UnknownStatus(99))));
+    }
 }
 
-use std::{fmt, fmt::Display};
+use std::{fmt, fmt::Display, str::FromStr};
 
 use serde::{Deserialize, Serialize};
 
@@ -157,3 +247,15 @@ impl Display for SampleErrorReason {
         }
     }
 }
+
+impl FromStr for SampleErrorReason {
+    type Err = String;
+
+    fn from_str(reason: &str) -> Result<Self, Self::Err> {
+        match reason {
+            "CHAT_TITLE_EMPTY" => Ok(SampleErrorReason::ChatTitleEmpty),
+            "CHAT_ABOUT_TOO_LONG" => Ok(SampleErrorReason::ChatAboutTooLong),
+            reason => Err(reason.to_string()),
+        }
+    }
+}
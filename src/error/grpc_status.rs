@@ -0,0 +1,185 @@
+use std::str::FromStr;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use bytes::Bytes;
+use prost::Message;
+
+use super::{google_rpc, ConversionError, ErrorDetails, ErrorModel, TypedDetail};
+
+/// Type url of the [google_rpc::ErrorInfo] details packed into a [google_rpc::Status]
+pub const ERROR_INFO_TYPE_URL: &str = "type.googleapis.com/google.rpc.ErrorInfo";
+
+// Type urls of the [TypedDetail]s, also used as their `@type` tag in JSON
+pub const BAD_REQUEST_TYPE_URL: &str = "type.googleapis.com/google.rpc.BadRequest";
+pub const RETRY_INFO_TYPE_URL: &str = "type.googleapis.com/google.rpc.RetryInfo";
+pub const QUOTA_FAILURE_TYPE_URL: &str = "type.googleapis.com/google.rpc.QuotaFailure";
+pub const PRECONDITION_FAILURE_TYPE_URL: &str =
+    "type.googleapis.com/google.rpc.PreconditionFailure";
+pub const RESOURCE_INFO_TYPE_URL: &str = "type.googleapis.com/google.rpc.ResourceInfo";
+pub const DEBUG_INFO_TYPE_URL: &str = "type.googleapis.com/google.rpc.DebugInfo";
+pub const HELP_TYPE_URL: &str = "type.googleapis.com/google.rpc.Help";
+pub const LOCALIZED_MESSAGE_TYPE_URL: &str = "type.googleapis.com/google.rpc.LocalizedMessage";
+
+/// Converts the model into a gRPC status. The details are encoded as a [google_rpc::Status]
+/// with one [google_rpc::ErrorInfo] per [ErrorDetails], followed by the [TypedDetail]s, which
+/// tonic sends in the `grpc-status-details-bin` trailer.
+///
+/// Only the gRPC code is carried: statuses without a gRPC equivalent (e.g. `DatabaseError`)
+/// become `Internal`, and the model `code` is not sent.
+impl<R> From<ErrorModel<R>> for tonic::Status
+where
+    R: ToString,
+{
+    fn from(model: ErrorModel<R>) -> tonic::Status {
+        let code: tonic::Code = model.status.into();
+
+        let details = model
+            .details
+            .into_iter()
+            .map(google_rpc::ErrorInfo::from)
+            .chain(
+                model
+                    .unknown_details
+                    .into_iter()
+                    .map(google_rpc::ErrorInfo::from),
+            )
+            .map(|error_info| prost_types::Any {
+                type_url: ERROR_INFO_TYPE_URL.to_string(),
+                value: error_info.encode_to_vec(),
+            })
+            .chain(model.typed_details.iter().map(TypedDetail::to_any))
+            .collect();
+
+        let status = google_rpc::Status {
+            code: code as i32,
+            message: model.message.clone(),
+            details,
+        };
+
+        tonic::Status::with_details(code, model.message, Bytes::from(status.encode_to_vec()))
+    }
+}
+
+/// Converts a gRPC status received from a backend into a model. The [google_rpc::ErrorInfo]
+/// details become [ErrorDetails], kept in [ErrorModel::unknown_details] when their reason does
+/// not parse into `R`, and the other standard details become [TypedDetail]s (unknown detail
+/// types are skipped). The model `code`
+/// is the HTTP code matching the gRPC code.
+impl<R> TryFrom<tonic::Status> for ErrorModel<R>
+where
+    R: FromStr,
+{
+    type Error = ConversionError;
+
+    fn try_from(status: tonic::Status) -> Result<Self, Self::Error> {
+        let mut model = ErrorModel::new(
+            status.code().into(),
+            http_code(status.code()),
+            status.message().to_string(),
+        );
+
+        if status.details().is_empty() {
+            return Ok(model);
+        }
+
+        let details = google_rpc::Status::decode(status.details())?;
+        for any in details.details.iter() {
+            if any.type_url == ERROR_INFO_TYPE_URL {
+                let error_info = google_rpc::ErrorInfo::decode(any.value.as_slice())?;
+                model.push_remote_details(
+                    error_info.reason,
+                    error_info.domain,
+                    error_info.metadata,
+                );
+            } else if let Some(detail) = TypedDetail::from_any(any)? {
+                model.typed_details.push(detail);
+            }
+        }
+
+        Ok(model)
+    }
+}
+
+impl<R> ErrorModel<R> {
+    /// Value of the [ERROR_DETAILS_HEADER](crate::response::ERROR_DETAILS_HEADER) header carrying
+    /// the [typed details](ErrorModel::typed_details) next to a `proto_nats::ErrorReply`, which
+    /// has no room for them: a base64 encoded [google_rpc::Status] with the details packed as
+    /// `google.protobuf.Any`. None when the model has no typed details.
+    pub fn typed_details_header(&self) -> Option<String> {
+        if self.typed_details.is_empty() {
+            return None;
+        }
+
+        let code: tonic::Code = self.status.into();
+        let status = google_rpc::Status {
+            code: code as i32,
+            message: self.message.clone(),
+            details: self.typed_details.iter().map(TypedDetail::to_any).collect(),
+        };
+
+        Some(BASE64.encode(status.encode_to_vec()))
+    }
+
+    /// Restores the typed details from the value of the
+    /// [ERROR_DETAILS_HEADER](crate::response::ERROR_DETAILS_HEADER) header of an error reply
+    /// (unknown detail types are skipped)
+    pub fn with_typed_details_header(mut self, value: &str) -> Result<Self, ConversionError> {
+        self.typed_details.extend(typed_details_from_header(value)?);
+        Ok(self)
+    }
+}
+
+/// Decodes the typed details of an [ERROR_DETAILS_HEADER](crate::response::ERROR_DETAILS_HEADER)
+/// header value, skipping unknown detail types
+pub(crate) fn typed_details_from_header(value: &str) -> Result<Vec<TypedDetail>, ConversionError> {
+    let status = google_rpc::Status::decode(BASE64.decode(value)?.as_slice())?;
+
+    let mut details = Vec::with_capacity(status.details.len());
+    for any in status.details.iter() {
+        details.extend(TypedDetail::from_any(any)?);
+    }
+
+    Ok(details)
+}
+
+impl<R> From<ErrorDetails<R>> for google_rpc::ErrorInfo
+where
+    R: ToString,
+{
+    fn from(details: ErrorDetails<R>) -> google_rpc::ErrorInfo {
+        google_rpc::ErrorInfo {
+            reason: details.reason.to_string(),
+            domain: details.domain,
+            metadata: details
+                .metadata
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        }
+    }
+}
+
+/// HTTP code matching the gRPC code, as mapped by the Google API design guide:
+/// https://cloud.google.com/apis/design/errors#handling_errors
+fn http_code(code: tonic::Code) -> i32 {
+    match code {
+        tonic::Code::Ok => 200,
+        tonic::Code::Cancelled => 499,
+        tonic::Code::InvalidArgument
+        | tonic::Code::FailedPrecondition
+        | tonic::Code::OutOfRange => 400,
+        tonic::Code::Unauthenticated => 401,
+        tonic::Code::PermissionDenied => 403,
+        tonic::Code::NotFound => 404,
+        tonic::Code::AlreadyExists | tonic::Code::Aborted => 409,
+        tonic::Code::ResourceExhausted => 429,
+        tonic::Code::Unimplemented => 501,
+        tonic::Code::Unavailable => 503,
+        tonic::Code::DeadlineExceeded => 504,
+        tonic::Code::Unknown | tonic::Code::Internal | tonic::Code::DataLoss => 500,
+    }
+}
+
+#[cfg(test)]
+#[path = "./grpc_status_tests.rs"]
+mod grpc_status_tests;
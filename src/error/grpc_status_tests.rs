@@ -0,0 +1,168 @@
+#[cfg(test)]
+mod grpc_status_tests {
+    use chat_proto::runtiva::nats::v1 as proto_nats;
+    use prost::Message;
+
+    use crate::error::{
+        google_rpc, BadRequest, ConversionError, ErrorModel, ErrorReason, MetaKeys, Status,
+        ERROR_INFO_TYPE_URL,
+    };
+
+    fn model() -> ErrorModel<ErrorReason> {
+        ErrorModel::new(
+            Status::Unimplemented,
+            501,
+            "No handler for subject: chat.channel.command.create".to_string(),
+        )
+        .with_details(ErrorReason::UnsupportedRequest, "runtiva.com".to_string())
+        .append_metadata(MetaKeys::Requestor, "42".to_string())
+    }
+
+    #[test]
+    fn test_error_model_to_grpc_status() {
+        let status: tonic::Status = model().into();
+
+        assert_eq!(status.code(), tonic::Code::Unimplemented);
+        assert_eq!(
+            status.message(),
+            "No handler for subject: chat.channel.command.create"
+        );
+
+        let details = google_rpc::Status::decode(status.details()).unwrap();
+        assert_eq!(details.code, tonic::Code::Unimplemented as i32);
+        assert_eq!(details.details.len(), 1);
+        assert_eq!(details.details[0].type_url, ERROR_INFO_TYPE_URL);
+
+        let error_info =
+            google_rpc::ErrorInfo::decode(details.details[0].value.as_slice()).unwrap();
+        assert_eq!(error_info.reason, "UNSUPPORTED_REQUEST");
+        assert_eq!(error_info.domain, "runtiva.com");
+        assert_eq!(error_info.metadata.get("requestor").unwrap(), "42");
+    }
+
+    #[test]
+    fn test_error_model_to_grpc_status_and_back() {
+        let status: tonic::Status = model().into();
+
+        let model: ErrorModel<ErrorReason> = status.try_into().unwrap();
+
+        assert_eq!(model.status, Status::Unimplemented);
+        assert_eq!(model.code, 501);
+        assert_eq!(model.details.len(), 1);
+        assert_eq!(model.details[0].reason, ErrorReason::UnsupportedRequest);
+        assert_eq!(model.details[0].domain, "runtiva.com");
+        assert_eq!(
+            model.details[0].metadata.get(&MetaKeys::Requestor).unwrap(),
+            "42"
+        );
+    }
+
+    #[test]
+    fn test_grpc_status_without_details() {
+        let status = tonic::Status::not_found("chat group not found");
+
+        let model: ErrorModel<ErrorReason> = status.try_into().unwrap();
+
+        assert_eq!(model.status, Status::NotFound);
+        assert_eq!(model.code, 404);
+        assert_eq!(model.message, "chat group not found");
+        assert!(model.details.is_empty());
+    }
+
+    #[test]
+    fn test_status_without_grpc_code_is_internal() {
+        let model: ErrorModel<ErrorReason> =
+            ErrorModel::new(Status::DatabaseError, 500, "Connection lost".to_string());
+
+        let status: tonic::Status = model.into();
+
+        assert_eq!(status.code(), tonic::Code::Internal);
+    }
+
+    #[test]
+    fn test_unknown_reason_is_kept() {
+        let status: tonic::Status = ErrorModel::new(Status::InvalidArgument, 400, "".to_string())
+            .with_details("CHAT_TITLE_EMPTY", "runtiva.com".to_string())
+            .append_metadata(MetaKeys::custom("chatId").unwrap(), "7".to_string())
+            .into();
+
+        let model: ErrorModel<ErrorReason> = status.try_into().unwrap();
+
+        assert!(model.details.is_empty());
+        assert_eq!(model.unknown_details[0].reason, "CHAT_TITLE_EMPTY");
+        assert_eq!(
+            model.unknown_details[0]
+                .metadata
+                .get(&MetaKeys::custom("chatId").unwrap())
+                .unwrap(),
+            "7"
+        );
+    }
+
+    #[test]
+    fn test_invalid_details_are_rejected() {
+        let status = tonic::Status::with_details(
+            tonic::Code::Internal,
+            "boom",
+            bytes::Bytes::from_static(b"\xff\xff"),
+        );
+
+        let result: Result<ErrorModel<ErrorReason>, _> = status.try_into();
+
+        assert!(matches!(result, Err(ConversionError::InvalidDetails(_))));
+    }
+
+    #[test]
+    fn test_typed_details_to_grpc_status_and_back() {
+        let status: tonic::Status = model()
+            .with_typed_detail(BadRequest::new().field_violation("title", "must not be empty"))
+            .into();
+
+        let details = google_rpc::Status::decode(status.details()).unwrap();
+        assert_eq!(details.details.len(), 2);
+        assert_eq!(
+            details.details[1].type_url,
+            "type.googleapis.com/google.rpc.BadRequest"
+        );
+
+        let model: ErrorModel<ErrorReason> = status.try_into().unwrap();
+        assert_eq!(model.details.len(), 1);
+        assert_eq!(
+            model.typed_details,
+            vec![BadRequest::new()
+                .field_violation("title", "must not be empty")
+                .into()]
+        );
+    }
+
+    #[test]
+    fn test_typed_details_header_and_back() {
+        let sent = model()
+            .with_typed_detail(BadRequest::new().field_violation("title", "must not be empty"));
+        let header = sent.typed_details_header().unwrap();
+
+        let reply: proto_nats::ErrorReply = sent.clone().into();
+        let received: ErrorModel<ErrorReason> = ErrorModel::try_from(reply)
+            .unwrap()
+            .with_typed_details_header(&header)
+            .unwrap();
+
+        assert_eq!(received.details.len(), 1);
+        assert_eq!(received.typed_details, sent.typed_details);
+    }
+
+    #[test]
+    fn test_no_typed_details_header_without_typed_details() {
+        assert!(model().typed_details_header().is_none());
+    }
+
+    #[test]
+    fn test_invalid_typed_details_header() {
+        let result = model().with_typed_details_header("not base64!");
+
+        assert!(matches!(
+            result,
+            Err(ConversionError::InvalidDetailsHeader(_))
+        ));
+    }
+}
@@ -1,23 +1,162 @@
-use std::{fmt, hash::Hash};
+use std::{convert::Infallible, fmt, hash::Hash, str::FromStr};
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 pub trait ErrorMetaKeys {}
 
+/// Longest metadata key allowed by the Google error model
+const MAX_KEY_LEN: usize = 64;
+
+/// Names of the [MetaKeys] variants, in [Display](fmt::Display) and in JSON
+const RESERVED_KEYS: [&str; 5] = [
+    "Requestor",
+    "Request",
+    "Service",
+    "DatabaseError",
+    "OtherError",
+];
+
+/// Error returned for a custom metadata key that is not valid, see [MetaKeys::custom]
+#[derive(Debug, thiserror::Error)]
+pub enum InvalidMetaKey {
+    #[error("invalid metadata key '{0}', expected [a-zA-Z0-9-_]{{1,64}}")]
+    Format(String),
+
+    #[error("metadata key '{0}' is reserved for a MetaKeys variant")]
+    Reserved(String),
+}
+
+/// Error returned by the typed metadata builders of [ErrorModel](super::ErrorModel),
+/// e.g. [append_id](super::ErrorModel::append_id)
+#[derive(Debug, thiserror::Error)]
+pub enum MetadataError {
+    #[error(transparent)]
+    InvalidKey(#[from] InvalidMetaKey),
+
+    #[error("no error details to add the metadata key '{0}' to")]
+    NoDetails(MetaKeys),
+}
+
 /// Manages a default static keys for metadata
 /// This can be overriden to a custom list of metakeys
 /// when using [`crate::ErrorInfo`](crate::ErrorInfo)
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
+///
+/// Other keys are added as [MetaKeys::Custom], built with [MetaKeys::custom] (or the
+/// unit-suffixed [MetaKeys::id], [MetaKeys::unit], [MetaKeys::millis] and [MetaKeys::per]),
+/// which validate them.
+/// Applications can define their own key enums and convert them with `From<MyKeys> for MetaKeys`,
+/// as [ErrorModel::append_metadata](super::ErrorModel::append_metadata) accepts any `Into<MetaKeys>`.
+///
+/// ```
+/// use nats_transport::error::MetaKeys;
+///
+/// assert_eq!(MetaKeys::id("chat")?.to_string(), "chatId");
+/// assert_eq!(MetaKeys::per("limit", "request")?.to_string(), "limitPerRequest");
+/// assert!(MetaKeys::custom("chat id").is_err());
+/// assert!(MetaKeys::custom("requestor").is_err());
+/// # Ok::<(), nats_transport::error::InvalidMetaKey>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum MetaKeys {
     Requestor,
     Request,
     Service,
     DatabaseError,
     OtherError,
+
+    /// Key without a variant: an application key (see [MetaKeys::custom]), or a key set by a
+    /// remote service using other metadata keys
+    Custom(CustomKey),
+}
+
+/// Name of a [MetaKeys::Custom] key. Only built by [MetaKeys::custom], or from the keys
+/// received from a remote service, which are kept as is.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CustomKey(String);
+
+impl CustomKey {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for CustomKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
 }
 
 impl ErrorMetaKeys for MetaKeys {}
 
+impl MetaKeys {
+    /// Custom key, validated against `[a-zA-Z0-9-_]{1,64}`. The names of the other variants
+    /// (e.g. `Requestor` or `request`, in any case) are rejected, as they would not parse back
+    /// into a custom key.
+    pub fn custom(key: impl Into<String>) -> Result<Self, InvalidMetaKey> {
+        let key = key.into();
+
+        let valid = !key.is_empty()
+            && key.len() <= MAX_KEY_LEN
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+        if !valid {
+            return Err(InvalidMetaKey::Format(key));
+        }
+
+        if RESERVED_KEYS
+            .iter()
+            .any(|reserved| reserved.eq_ignore_ascii_case(&key))
+        {
+            return Err(InvalidMetaKey::Reserved(key));
+        }
+
+        Ok(MetaKeys::Custom(CustomKey(key)))
+    }
+
+    /// Key of an entity id: `<name>Id`, e.g. `chatId`
+    pub fn id(name: &str) -> Result<Self, InvalidMetaKey> {
+        Self::custom(format!("{}Id", name))
+    }
+
+    /// Key of a value with a unit: `<name><Unit>`, e.g. `activeChatGroupsCount`
+    pub fn unit(name: &str, unit: &str) -> Result<Self, InvalidMetaKey> {
+        Self::custom(format!("{}{}", name, capitalized(unit)))
+    }
+
+    /// Key of a duration in milliseconds: `<name>Millis`, e.g. `retryDelayMillis`
+    pub fn millis(name: &str) -> Result<Self, InvalidMetaKey> {
+        Self::custom(format!("{}Millis", name))
+    }
+
+    /// Key of a value per unit: `<name>Per<Unit>`, e.g. `limitPerRequest`
+    pub fn per(name: &str, unit: &str) -> Result<Self, InvalidMetaKey> {
+        Self::custom(format!("{}Per{}", name, capitalized(unit)))
+    }
+
+    /// Name of the key in JSON, where the keys without a variant are kept as is
+    fn json_name(&self) -> &str {
+        match self {
+            MetaKeys::Requestor => "Requestor",
+            MetaKeys::Request => "Request",
+            MetaKeys::Service => "Service",
+            MetaKeys::DatabaseError => "DatabaseError",
+            MetaKeys::OtherError => "OtherError",
+            MetaKeys::Custom(key) => key.as_str(),
+        }
+    }
+}
+
+/// Unit with its first letter in uppercase, e.g. `Request`
+fn capitalized(unit: &str) -> String {
+    let mut chars = unit.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
 impl fmt::Display for MetaKeys {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -26,6 +165,56 @@ impl fmt::Display for MetaKeys {
             MetaKeys::Service => write!(f, "service"),
             MetaKeys::DatabaseError => write!(f, "DatabaseError"),
             MetaKeys::OtherError => write!(f, "OtherError"),
+            MetaKeys::Custom(key) => write!(f, "{}", key),
         }
     }
 }
+
+/// Parses the keys written by [Display](fmt::Display), e.g. in a `proto_nats::ErrorReply`.
+/// Unknown keys are kept as [MetaKeys::Custom].
+impl FromStr for MetaKeys {
+    type Err = Infallible;
+
+    fn from_str(key: &str) -> Result<Self, Self::Err> {
+        Ok(match key {
+            "request" => MetaKeys::Request,
+            "requestor" => MetaKeys::Requestor,
+            "service" => MetaKeys::Service,
+            "DatabaseError" => MetaKeys::DatabaseError,
+            "OtherError" => MetaKeys::OtherError,
+            key => MetaKeys::Custom(CustomKey(key.to_string())),
+        })
+    }
+}
+
+// Serialized as plain strings (rather than derived), so the custom keys can be used as JSON object keys
+impl Serialize for MetaKeys {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.json_name())
+    }
+}
+
+impl<'de> Deserialize<'de> for MetaKeys {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let key = String::deserialize(deserializer)?;
+
+        Ok(match key.as_str() {
+            "Requestor" => MetaKeys::Requestor,
+            "Request" => MetaKeys::Request,
+            "Service" => MetaKeys::Service,
+            "DatabaseError" => MetaKeys::DatabaseError,
+            "OtherError" => MetaKeys::OtherError,
+            _ => MetaKeys::Custom(CustomKey(key)),
+        })
+    }
+}
+
+#[cfg(test)]
+#[path = "./meta_keys_tests.rs"]
+mod meta_keys_tests;
@@ -0,0 +1,154 @@
+#[cfg(test)]
+mod meta_keys_tests {
+    use std::time::Duration;
+
+    use crate::error::{ErrorModel, ErrorReason, InvalidMetaKey, MetaKeys, MetadataError, Status};
+
+    /// Metadata keys of an application, e.g. the chat service
+    enum ChatMetaKeys {
+        ChatGroupOwner,
+    }
+
+    impl From<ChatMetaKeys> for MetaKeys {
+        fn from(key: ChatMetaKeys) -> Self {
+            match key {
+                ChatMetaKeys::ChatGroupOwner => MetaKeys::custom("chatGroupOwner").unwrap(),
+            }
+        }
+    }
+
+    fn model() -> ErrorModel<ErrorReason> {
+        ErrorModel::new(
+            Status::ResourceExhausted,
+            429,
+            "Too many chat groups".to_string(),
+        )
+        .with_details(ErrorReason::ApiKeyInvalid, "runtiva.com".to_string())
+    }
+
+    #[test]
+    fn test_custom_key_validation() {
+        match MetaKeys::custom("chat-group_Id9").unwrap() {
+            MetaKeys::Custom(key) => assert_eq!(key.as_str(), "chat-group_Id9"),
+            key => panic!("unexpected key {key:?}"),
+        }
+
+        assert!(MetaKeys::custom("").is_err());
+        assert!(MetaKeys::custom("chat id").is_err());
+        assert!(MetaKeys::custom("chat.id").is_err());
+        assert!(MetaKeys::custom("chätId").is_err());
+        assert!(MetaKeys::custom("a".repeat(64)).is_ok());
+        assert!(MetaKeys::custom("a".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn test_reserved_custom_keys() {
+        for key in [
+            "Requestor",
+            "requestor",
+            "Request",
+            "request",
+            "service",
+            "REQUEST",
+            "OtherError",
+        ] {
+            assert!(
+                matches!(MetaKeys::custom(key), Err(InvalidMetaKey::Reserved(_))),
+                "{key}"
+            );
+        }
+
+        assert!(MetaKeys::custom("requestorId").is_ok());
+    }
+
+    #[test]
+    fn test_unit_suffixed_keys() {
+        assert_eq!(MetaKeys::id("chat").unwrap().to_string(), "chatId");
+        assert_eq!(
+            MetaKeys::millis("retryDelay").unwrap().to_string(),
+            "retryDelayMillis"
+        );
+        assert_eq!(
+            MetaKeys::per("limit", "request").unwrap().to_string(),
+            "limitPerRequest"
+        );
+        assert_eq!(
+            MetaKeys::unit("activeChatGroups", "count")
+                .unwrap()
+                .to_string(),
+            "activeChatGroupsCount"
+        );
+        assert!(MetaKeys::per("limit", "chat group").is_err());
+    }
+
+    #[test]
+    fn test_typed_metadata_builders() {
+        let model = model()
+            .append_id("chat", 7037539637825798_i64)
+            .and_then(|model| model.append_per("limit", 10, "request"))
+            .and_then(|model| model.append_int("activeChatGroups", 25, "count"))
+            .and_then(|model| model.append_duration("retryDelay", Duration::from_secs_f64(1.5)))
+            .unwrap();
+
+        let metadata = &model.details[0].metadata;
+        let get = |key: &str| metadata.get(&MetaKeys::custom(key).unwrap()).unwrap();
+
+        assert_eq!(get("chatId"), "7037539637825798");
+        assert_eq!(get("limitPerRequest"), "10");
+        assert_eq!(get("activeChatGroupsCount"), "25");
+        assert_eq!(get("retryDelayMillis"), "1500");
+    }
+
+    #[test]
+    fn test_typed_metadata_builder_with_invalid_key() {
+        let result = model().append_int("active chat groups", 25, "count");
+        assert!(matches!(
+            result,
+            Err(MetadataError::InvalidKey(InvalidMetaKey::Format(_)))
+        ));
+
+        let result = model().append_int("Other", 1, "error");
+        assert!(matches!(
+            result,
+            Err(MetadataError::InvalidKey(InvalidMetaKey::Reserved(_)))
+        ));
+    }
+
+    #[test]
+    fn test_typed_metadata_builder_without_details() {
+        let model = ErrorModel::<ErrorReason>::new(Status::Internal, 500, "".to_string());
+
+        assert!(matches!(
+            model.append_id("chat", 7),
+            Err(MetadataError::NoDetails(_))
+        ));
+    }
+
+    #[test]
+    fn test_application_key_type() {
+        let model = model().append_metadata(ChatMetaKeys::ChatGroupOwner, "42".to_string());
+
+        assert_eq!(
+            model.details[0]
+                .metadata
+                .get(&MetaKeys::custom("chatGroupOwner").unwrap())
+                .unwrap(),
+            "42"
+        );
+    }
+
+    #[test]
+    fn test_custom_keys_json() {
+        let model = model()
+            .append_metadata(MetaKeys::Requestor, "42".to_string())
+            .append_id("chat", 7)
+            .unwrap();
+
+        let json = serde_json::to_value(&model).unwrap();
+        assert_eq!(json["details"][0]["metadata"]["Requestor"], "42");
+        assert_eq!(json["details"][0]["metadata"]["chatId"], "7");
+
+        let decoded: ErrorModel<ErrorReason> = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.details[0].metadata, model.details[0].metadata);
+    }
+}
@@ -1,9 +1,130 @@
+mod conversion_error;
 mod error_model;
+mod grpc_status;
 mod meta_keys;
 mod reason;
 mod status;
+mod typed_detail;
 
+pub use conversion_error::ConversionError;
 pub use error_model::{ErrorDetails, ErrorModel, ToErrorModel};
-pub use meta_keys::{ErrorMetaKeys, MetaKeys};
+pub(crate) use grpc_status::typed_details_from_header;
+pub use grpc_status::{
+    BAD_REQUEST_TYPE_URL, DEBUG_INFO_TYPE_URL, ERROR_INFO_TYPE_URL, HELP_TYPE_URL,
+    LOCALIZED_MESSAGE_TYPE_URL, PRECONDITION_FAILURE_TYPE_URL, QUOTA_FAILURE_TYPE_URL,
+    RESOURCE_INFO_TYPE_URL, RETRY_INFO_TYPE_URL,
+};
+pub use meta_keys::{CustomKey, ErrorMetaKeys, InvalidMetaKey, MetaKeys, MetadataError};
 pub use reason::{ErrorReason, ErrorReasons};
 pub use status::Status;
+pub use typed_detail::{
+    BadRequest, DebugInfo, FieldViolation, Help, HelpLink, LocalizedMessage, PreconditionFailure,
+    PreconditionViolation, QuotaFailure, QuotaViolation, ResourceInfo, RetryInfo, TypedDetail,
+};
+
+/// Derive macro for error reason enums, see [ErrorReasons]
+///
+/// ```
+/// use nats_transport::error::ErrorReason;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ErrorReason)]
+/// pub enum ChatGroupErrorReason {
+///     ChatTitleEmpty,
+///     #[error_reason(rename = "CHAT_ABOUT_TOO_LONG")]
+///     AboutTooLong,
+/// }
+///
+/// assert_eq!(ChatGroupErrorReason::ChatTitleEmpty.to_string(), "CHAT_TITLE_EMPTY");
+/// assert_eq!(
+///     "CHAT_ABOUT_TOO_LONG".parse::<ChatGroupErrorReason>().unwrap(),
+///     ChatGroupErrorReason::AboutTooLong
+/// );
+/// ```
+///
+/// Reasons that are not UPPER_SNAKE_CASE are rejected at compile time:
+///
+/// ```compile_fail
+/// use nats_transport::error::ErrorReason;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ErrorReason)]
+/// pub enum ChatGroupErrorReason {
+///     #[error_reason(rename = "chat-title-empty")]
+///     ChatTitleEmpty,
+/// }
+/// ```
+pub use nats_transport_derive::ErrorReason;
+
+/// Derive macro for [ToErrorModel]
+///
+/// ```
+/// use nats_transport::error::{ErrorReason, MetaKeys, Status, ToErrorModel};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ErrorReason)]
+/// pub enum ChatGroupErrorReason {
+///     ChatTitleEmpty,
+/// }
+///
+/// #[derive(Debug, thiserror::Error, ToErrorModel)]
+/// #[error_model(reasons = ChatGroupErrorReason, domain = "runtiva.com", service = "chat-persist.runtiva.com")]
+/// pub enum ChatGroupError {
+///     #[error("No chat title provided.")]
+///     #[error_model(status = InvalidArgument, code = 400, reason = ChatTitleEmpty)]
+///     TitleEmpty,
+///
+///     #[error("Database error: {0}")]
+///     #[error_model(status = DatabaseError, code = 500)]
+///     Database(String),
+/// }
+///
+/// let model = ChatGroupError::TitleEmpty.to_error_model(Some(42), None);
+/// assert_eq!(model.status, Status::InvalidArgument);
+/// assert_eq!(model.code, 400);
+/// assert_eq!(model.details[0].reason, ChatGroupErrorReason::ChatTitleEmpty);
+/// assert_eq!(model.details[0].metadata.get(&MetaKeys::Requestor).unwrap(), "42");
+///
+/// let model = ChatGroupError::Database("connection lost".to_string()).to_error_model(None, None);
+/// assert_eq!(model.message, "Database error: connection lost");
+/// assert!(model.details.is_empty());
+/// ```
+///
+/// Every variant must set its `status` and `code`:
+///
+/// ```compile_fail
+/// use nats_transport::error::ToErrorModel;
+///
+/// #[derive(Debug, thiserror::Error, ToErrorModel)]
+/// pub enum ChatGroupError {
+///     #[error("No chat title provided.")]
+///     #[error_model(reason = ApiKeyInvalid)]
+///     TitleEmpty,
+/// }
+/// ```
+///
+/// and a `reason` must be a variant of the reason type:
+///
+/// ```compile_fail
+/// use nats_transport::error::ToErrorModel;
+///
+/// #[derive(Debug, thiserror::Error, ToErrorModel)]
+/// pub enum ChatGroupError {
+///     #[error("No chat title provided.")]
+///     #[error_model(status = InvalidArgument, code = 400, reason = NoSuchReason, domain = "runtiva.com")]
+///     TitleEmpty,
+/// }
+/// ```
+pub use nats_transport_derive::ToErrorModel;
+
+/// Protobuf messages of the google.rpc error model, used to carry the [ErrorModel] details
+/// over gRPC (see `From<ErrorModel<R>> for tonic::Status`)
+#[allow(unused_qualifications)]
+#[allow(clippy::all)]
+pub mod google_rpc {
+    tonic::include_proto!("google.rpc");
+}
+
+#[cfg(test)]
+#[path = "./derive_tests.rs"]
+mod derive_tests;
@@ -1,4 +1,4 @@
-use std::fmt::{self, Debug};
+use std::fmt::Debug;
 
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
@@ -7,19 +7,8 @@ pub trait ErrorReasons: Debug + Clone + Serialize + DeserializeOwned {}
 /// Manages a default static list of error reasons
 /// This can be overriden to a custom list of error reasons
 /// when using [`crate::ErrorInfo`](crate::ErrorInfo)
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, nats_transport_derive::ErrorReason)]
 pub enum ErrorReason {
     ApiKeyInvalid,
     UnsupportedRequest,
 }
-
-impl ErrorReasons for ErrorReason {}
-
-impl fmt::Display for ErrorReason {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            ErrorReason::ApiKeyInvalid => write!(f, "API_KEY_INVALID"),
-            ErrorReason::UnsupportedRequest => write!(f, "UNSUPPORTED_REQUEST"),
-        }
-    }
-}
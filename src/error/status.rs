@@ -116,3 +116,27 @@ impl From<Status> for tonic::Code {
         }
     }
 }
+
+impl From<tonic::Code> for Status {
+    fn from(code: tonic::Code) -> Self {
+        match code {
+            tonic::Code::Ok => Status::Ok,
+            tonic::Code::Cancelled => Status::Cancelled,
+            tonic::Code::Unknown => Status::Unknown,
+            tonic::Code::InvalidArgument => Status::InvalidArgument,
+            tonic::Code::DeadlineExceeded => Status::DeadlineExceeded,
+            tonic::Code::NotFound => Status::NotFound,
+            tonic::Code::AlreadyExists => Status::AlreadyExists,
+            tonic::Code::PermissionDenied => Status::PermissionDenied,
+            tonic::Code::ResourceExhausted => Status::ResourceExhausted,
+            tonic::Code::FailedPrecondition => Status::FailedPrecondition,
+            tonic::Code::Aborted => Status::Aborted,
+            tonic::Code::OutOfRange => Status::OutOfRange,
+            tonic::Code::Unimplemented => Status::Unimplemented,
+            tonic::Code::Internal => Status::Internal,
+            tonic::Code::Unavailable => Status::Unavailable,
+            tonic::Code::DataLoss => Status::DataLoss,
+            tonic::Code::Unauthenticated => Status::Unauthenticated,
+        }
+    }
+}
@@ -0,0 +1,719 @@
+use std::time::Duration;
+
+use prost::Message;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{
+    google_rpc, BAD_REQUEST_TYPE_URL, DEBUG_INFO_TYPE_URL, HELP_TYPE_URL,
+    LOCALIZED_MESSAGE_TYPE_URL, PRECONDITION_FAILURE_TYPE_URL, QUOTA_FAILURE_TYPE_URL,
+    RESOURCE_INFO_TYPE_URL, RETRY_INFO_TYPE_URL,
+};
+
+/// Standard error detail of the Google error model, carried by [ErrorModel](super::ErrorModel)
+/// along the [ErrorDetails](super::ErrorDetails) (`google.rpc.ErrorInfo`):
+/// https://cloud.google.com/apis/design/errors#error_details
+///
+/// In JSON, each detail is tagged with its `@type` url and its fields use the proto3 JSON
+/// (camelCase) names, e.g.
+///
+/// ```json
+///     { "@type": "type.googleapis.com/google.rpc.BadRequest",
+///       "fieldViolations": [
+///         { "field": "title", "description": "The chat title must not be empty" }
+///       ]
+///     }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypedDetail {
+    BadRequest(BadRequest),
+    RetryInfo(RetryInfo),
+    QuotaFailure(QuotaFailure),
+    PreconditionFailure(PreconditionFailure),
+    ResourceInfo(ResourceInfo),
+    DebugInfo(DebugInfo),
+    Help(Help),
+    LocalizedMessage(LocalizedMessage),
+}
+
+/// Fields of the request that failed validation
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BadRequest {
+    #[serde(alias = "field_violations")]
+    pub field_violations: Vec<FieldViolation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldViolation {
+    /// Dot-separated path to the field in the request, e.g. `title` or `settings.ttl_period`
+    pub field: String,
+
+    pub description: String,
+}
+
+/// Delay after which the request may be retried. In JSON, the delay is a proto3 duration
+/// string, e.g. `"1.500s"`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryInfo {
+    #[serde(with = "proto_duration", alias = "retry_delay")]
+    pub retry_delay: Duration,
+}
+
+/// Quota checks that failed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuotaFailure {
+    pub violations: Vec<QuotaViolation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuotaViolation {
+    /// Subject of the quota, e.g. `user:<user_id>`
+    pub subject: String,
+
+    pub description: String,
+}
+
+/// Preconditions that failed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PreconditionFailure {
+    pub violations: Vec<PreconditionViolation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PreconditionViolation {
+    /// Service specific type of the precondition, e.g. `TOS`
+    #[serde(rename = "type")]
+    pub violation_type: String,
+
+    /// Subject, relative to the type, that failed
+    pub subject: String,
+
+    pub description: String,
+}
+
+/// Resource being accessed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceInfo {
+    #[serde(alias = "resource_type")]
+    pub resource_type: String,
+
+    #[serde(alias = "resource_name")]
+    pub resource_name: String,
+
+    pub owner: String,
+    pub description: String,
+}
+
+/// Debugging information, which should not be sent to end users
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DebugInfo {
+    #[serde(alias = "stack_entries")]
+    pub stack_entries: Vec<String>,
+    pub detail: String,
+}
+
+/// Links to documentation or to an out of band action
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Help {
+    pub links: Vec<HelpLink>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HelpLink {
+    pub description: String,
+    pub url: String,
+}
+
+/// Error message localized for the end user
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LocalizedMessage {
+    /// BCP 47 locale, e.g. `en-US`
+    pub locale: String,
+
+    pub message: String,
+}
+
+impl TypedDetail {
+    /// Type url of the detail when packed into a `google.protobuf.Any`
+    pub fn type_url(&self) -> &'static str {
+        match self {
+            TypedDetail::BadRequest(_) => BAD_REQUEST_TYPE_URL,
+            TypedDetail::RetryInfo(_) => RETRY_INFO_TYPE_URL,
+            TypedDetail::QuotaFailure(_) => QUOTA_FAILURE_TYPE_URL,
+            TypedDetail::PreconditionFailure(_) => PRECONDITION_FAILURE_TYPE_URL,
+            TypedDetail::ResourceInfo(_) => RESOURCE_INFO_TYPE_URL,
+            TypedDetail::DebugInfo(_) => DEBUG_INFO_TYPE_URL,
+            TypedDetail::Help(_) => HELP_TYPE_URL,
+            TypedDetail::LocalizedMessage(_) => LOCALIZED_MESSAGE_TYPE_URL,
+        }
+    }
+
+    /// Packs the detail into a `google.protobuf.Any`, e.g. for the details of a [google_rpc::Status]
+    pub fn to_any(&self) -> prost_types::Any {
+        let value = match self.clone() {
+            TypedDetail::BadRequest(detail) => google_rpc::BadRequest::from(detail).encode_to_vec(),
+            TypedDetail::RetryInfo(detail) => google_rpc::RetryInfo::from(detail).encode_to_vec(),
+            TypedDetail::QuotaFailure(detail) => {
+                google_rpc::QuotaFailure::from(detail).encode_to_vec()
+            }
+            TypedDetail::PreconditionFailure(detail) => {
+                google_rpc::PreconditionFailure::from(detail).encode_to_vec()
+            }
+            TypedDetail::ResourceInfo(detail) => {
+                google_rpc::ResourceInfo::from(detail).encode_to_vec()
+            }
+            TypedDetail::DebugInfo(detail) => google_rpc::DebugInfo::from(detail).encode_to_vec(),
+            TypedDetail::Help(detail) => google_rpc::Help::from(detail).encode_to_vec(),
+            TypedDetail::LocalizedMessage(detail) => {
+                google_rpc::LocalizedMessage::from(detail).encode_to_vec()
+            }
+        };
+
+        prost_types::Any {
+            type_url: self.type_url().to_string(),
+            value,
+        }
+    }
+
+    /// Unpacks the detail from a `google.protobuf.Any`, None for other types (e.g. `ErrorInfo`)
+    pub fn from_any(any: &prost_types::Any) -> Result<Option<Self>, prost::DecodeError> {
+        let value = any.value.as_slice();
+
+        let detail = match any.type_url.as_str() {
+            BAD_REQUEST_TYPE_URL => {
+                TypedDetail::BadRequest(google_rpc::BadRequest::decode(value)?.into())
+            }
+            RETRY_INFO_TYPE_URL => {
+                TypedDetail::RetryInfo(google_rpc::RetryInfo::decode(value)?.into())
+            }
+            QUOTA_FAILURE_TYPE_URL => {
+                TypedDetail::QuotaFailure(google_rpc::QuotaFailure::decode(value)?.into())
+            }
+            PRECONDITION_FAILURE_TYPE_URL => TypedDetail::PreconditionFailure(
+                google_rpc::PreconditionFailure::decode(value)?.into(),
+            ),
+            RESOURCE_INFO_TYPE_URL => {
+                TypedDetail::ResourceInfo(google_rpc::ResourceInfo::decode(value)?.into())
+            }
+            DEBUG_INFO_TYPE_URL => {
+                TypedDetail::DebugInfo(google_rpc::DebugInfo::decode(value)?.into())
+            }
+            HELP_TYPE_URL => TypedDetail::Help(google_rpc::Help::decode(value)?.into()),
+            LOCALIZED_MESSAGE_TYPE_URL => {
+                TypedDetail::LocalizedMessage(google_rpc::LocalizedMessage::decode(value)?.into())
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(detail))
+    }
+}
+
+/// Serializes the detail fields tagged with the `@type` url
+impl Serialize for TypedDetail {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Tagged<'a, T> {
+            #[serde(rename = "@type")]
+            type_url: &'a str,
+
+            #[serde(flatten)]
+            detail: &'a T,
+        }
+
+        let type_url = self.type_url();
+        match self {
+            TypedDetail::BadRequest(detail) => Tagged { type_url, detail }.serialize(serializer),
+            TypedDetail::RetryInfo(detail) => Tagged { type_url, detail }.serialize(serializer),
+            TypedDetail::QuotaFailure(detail) => Tagged { type_url, detail }.serialize(serializer),
+            TypedDetail::PreconditionFailure(detail) => {
+                Tagged { type_url, detail }.serialize(serializer)
+            }
+            TypedDetail::ResourceInfo(detail) => Tagged { type_url, detail }.serialize(serializer),
+            TypedDetail::DebugInfo(detail) => Tagged { type_url, detail }.serialize(serializer),
+            TypedDetail::Help(detail) => Tagged { type_url, detail }.serialize(serializer),
+            TypedDetail::LocalizedMessage(detail) => {
+                Tagged { type_url, detail }.serialize(serializer)
+            }
+        }
+    }
+}
+
+/// Deserializes the detail matching the `@type` url, failing for other types
+impl<'de> Deserialize<'de> for TypedDetail {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fields = serde_json::Map::deserialize(deserializer)?;
+
+        TypedDetail::from_json(fields)?.ok_or_else(|| D::Error::custom("unknown detail type"))
+    }
+}
+
+/// Deserializes a list of details, skipping the details of unknown types (e.g. a detail type
+/// of the Google error model added by another service) as [TypedDetail::from_any] does
+pub(crate) fn deserialize_known<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<TypedDetail>, D::Error> {
+    let details = Vec::<serde_json::Map<String, serde_json::Value>>::deserialize(deserializer)?;
+
+    let mut known = Vec::with_capacity(details.len());
+    for fields in details {
+        known.extend(TypedDetail::from_json(fields)?);
+    }
+
+    Ok(known)
+}
+
+impl TypedDetail {
+    /// Decodes the fields of a JSON detail tagged with its `@type` url, None for other types
+    fn from_json<E: serde::de::Error>(
+        mut fields: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<Option<Self>, E> {
+        let type_url = match fields.remove("@type") {
+            Some(serde_json::Value::String(type_url)) => type_url,
+            _ => return Err(E::missing_field("@type")),
+        };
+        let fields = serde_json::Value::Object(fields);
+
+        let detail = match type_url.as_str() {
+            BAD_REQUEST_TYPE_URL => serde_json::from_value(fields).map(TypedDetail::BadRequest),
+            RETRY_INFO_TYPE_URL => serde_json::from_value(fields).map(TypedDetail::RetryInfo),
+            QUOTA_FAILURE_TYPE_URL => serde_json::from_value(fields).map(TypedDetail::QuotaFailure),
+            PRECONDITION_FAILURE_TYPE_URL => {
+                serde_json::from_value(fields).map(TypedDetail::PreconditionFailure)
+            }
+            RESOURCE_INFO_TYPE_URL => serde_json::from_value(fields).map(TypedDetail::ResourceInfo),
+            DEBUG_INFO_TYPE_URL => serde_json::from_value(fields).map(TypedDetail::DebugInfo),
+            HELP_TYPE_URL => serde_json::from_value(fields).map(TypedDetail::Help),
+            LOCALIZED_MESSAGE_TYPE_URL => {
+                serde_json::from_value(fields).map(TypedDetail::LocalizedMessage)
+            }
+            _ => return Ok(None),
+        };
+
+        detail.map(Some).map_err(E::custom)
+    }
+}
+
+/// Serde of a [Duration] as a proto3 JSON duration string: seconds with 0, 3, 6 or 9
+/// fractional digits and an `s` suffix, e.g. `"1.500s"`
+mod proto_duration {
+    use std::time::Duration;
+
+    use serde::{de::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        let secs = duration.as_secs();
+        let mut fraction = format!("{:09}", duration.subsec_nanos());
+        while fraction.ends_with("000") {
+            fraction.truncate(fraction.len() - 3);
+        }
+
+        let value = if fraction.is_empty() {
+            format!("{secs}s")
+        } else {
+            format!("{secs}.{fraction}s")
+        };
+
+        serializer.serialize_str(&value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        parse(&value).ok_or_else(|| D::Error::custom(format!("invalid duration: {value}")))
+    }
+
+    /// Parses a non-negative duration, with up to 9 fractional digits
+    fn parse(value: &str) -> Option<Duration> {
+        let value = value.strip_suffix('s')?;
+        let (secs, fraction) = match value.split_once('.') {
+            Some((secs, fraction)) if !fraction.is_empty() => (secs, fraction),
+            Some(_) => return None,
+            None => (value, ""),
+        };
+
+        let all_digits = |part: &str| part.bytes().all(|byte| byte.is_ascii_digit());
+        if secs.is_empty() || !all_digits(secs) || fraction.len() > 9 || !all_digits(fraction) {
+            return None;
+        }
+
+        let secs = secs.parse().ok()?;
+        let nanos = format!("{fraction:0<9}").parse().ok()?;
+
+        Some(Duration::new(secs, nanos))
+    }
+}
+
+impl BadRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn field_violation(
+        mut self,
+        field: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        self.field_violations.push(FieldViolation {
+            field: field.into(),
+            description: description.into(),
+        });
+        self
+    }
+}
+
+impl RetryInfo {
+    pub fn new(retry_delay: Duration) -> Self {
+        Self { retry_delay }
+    }
+}
+
+impl QuotaFailure {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn violation(mut self, subject: impl Into<String>, description: impl Into<String>) -> Self {
+        self.violations.push(QuotaViolation {
+            subject: subject.into(),
+            description: description.into(),
+        });
+        self
+    }
+}
+
+impl PreconditionFailure {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn violation(
+        mut self,
+        violation_type: impl Into<String>,
+        subject: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        self.violations.push(PreconditionViolation {
+            violation_type: violation_type.into(),
+            subject: subject.into(),
+            description: description.into(),
+        });
+        self
+    }
+}
+
+impl ResourceInfo {
+    pub fn new(resource_type: impl Into<String>, resource_name: impl Into<String>) -> Self {
+        Self {
+            resource_type: resource_type.into(),
+            resource_name: resource_name.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn owner(mut self, owner: impl Into<String>) -> Self {
+        self.owner = owner.into();
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+}
+
+impl DebugInfo {
+    pub fn new(detail: impl Into<String>) -> Self {
+        Self {
+            stack_entries: vec![],
+            detail: detail.into(),
+        }
+    }
+
+    pub fn stack_entry(mut self, entry: impl Into<String>) -> Self {
+        self.stack_entries.push(entry.into());
+        self
+    }
+}
+
+impl Help {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn link(mut self, description: impl Into<String>, url: impl Into<String>) -> Self {
+        self.links.push(HelpLink {
+            description: description.into(),
+            url: url.into(),
+        });
+        self
+    }
+}
+
+impl LocalizedMessage {
+    pub fn new(locale: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            locale: locale.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl From<BadRequest> for TypedDetail {
+    fn from(detail: BadRequest) -> Self {
+        TypedDetail::BadRequest(detail)
+    }
+}
+
+impl From<RetryInfo> for TypedDetail {
+    fn from(detail: RetryInfo) -> Self {
+        TypedDetail::RetryInfo(detail)
+    }
+}
+
+impl From<QuotaFailure> for TypedDetail {
+    fn from(detail: QuotaFailure) -> Self {
+        TypedDetail::QuotaFailure(detail)
+    }
+}
+
+impl From<PreconditionFailure> for TypedDetail {
+    fn from(detail: PreconditionFailure) -> Self {
+        TypedDetail::PreconditionFailure(detail)
+    }
+}
+
+impl From<ResourceInfo> for TypedDetail {
+    fn from(detail: ResourceInfo) -> Self {
+        TypedDetail::ResourceInfo(detail)
+    }
+}
+
+impl From<DebugInfo> for TypedDetail {
+    fn from(detail: DebugInfo) -> Self {
+        TypedDetail::DebugInfo(detail)
+    }
+}
+
+impl From<Help> for TypedDetail {
+    fn from(detail: Help) -> Self {
+        TypedDetail::Help(detail)
+    }
+}
+
+impl From<LocalizedMessage> for TypedDetail {
+    fn from(detail: LocalizedMessage) -> Self {
+        TypedDetail::LocalizedMessage(detail)
+    }
+}
+
+// ******************* google.rpc conversions ******************
+
+impl From<BadRequest> for google_rpc::BadRequest {
+    fn from(detail: BadRequest) -> Self {
+        google_rpc::BadRequest {
+            field_violations: detail
+                .field_violations
+                .into_iter()
+                .map(|violation| google_rpc::bad_request::FieldViolation {
+                    field: violation.field,
+                    description: violation.description,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<google_rpc::BadRequest> for BadRequest {
+    fn from(detail: google_rpc::BadRequest) -> Self {
+        BadRequest {
+            field_violations: detail
+                .field_violations
+                .into_iter()
+                .map(|violation| FieldViolation {
+                    field: violation.field,
+                    description: violation.description,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<RetryInfo> for google_rpc::RetryInfo {
+    fn from(detail: RetryInfo) -> Self {
+        google_rpc::RetryInfo {
+            retry_delay: Some(prost_types::Duration {
+                seconds: detail.retry_delay.as_secs() as i64,
+                nanos: detail.retry_delay.subsec_nanos() as i32,
+            }),
+        }
+    }
+}
+
+impl From<google_rpc::RetryInfo> for RetryInfo {
+    fn from(detail: google_rpc::RetryInfo) -> Self {
+        // a missing or negative delay means the request may be retried right away
+        let retry_delay = detail
+            .retry_delay
+            .map(|delay| Duration::new(delay.seconds.max(0) as u64, delay.nanos.max(0) as u32))
+            .unwrap_or_default();
+
+        RetryInfo { retry_delay }
+    }
+}
+
+impl From<QuotaFailure> for google_rpc::QuotaFailure {
+    fn from(detail: QuotaFailure) -> Self {
+        google_rpc::QuotaFailure {
+            violations: detail
+                .violations
+                .into_iter()
+                .map(|violation| google_rpc::quota_failure::Violation {
+                    subject: violation.subject,
+                    description: violation.description,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<google_rpc::QuotaFailure> for QuotaFailure {
+    fn from(detail: google_rpc::QuotaFailure) -> Self {
+        QuotaFailure {
+            violations: detail
+                .violations
+                .into_iter()
+                .map(|violation| QuotaViolation {
+                    subject: violation.subject,
+                    description: violation.description,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<PreconditionFailure> for google_rpc::PreconditionFailure {
+    fn from(detail: PreconditionFailure) -> Self {
+        google_rpc::PreconditionFailure {
+            violations: detail
+                .violations
+                .into_iter()
+                .map(|violation| google_rpc::precondition_failure::Violation {
+                    r#type: violation.violation_type,
+                    subject: violation.subject,
+                    description: violation.description,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<google_rpc::PreconditionFailure> for PreconditionFailure {
+    fn from(detail: google_rpc::PreconditionFailure) -> Self {
+        PreconditionFailure {
+            violations: detail
+                .violations
+                .into_iter()
+                .map(|violation| PreconditionViolation {
+                    violation_type: violation.r#type,
+                    subject: violation.subject,
+                    description: violation.description,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<ResourceInfo> for google_rpc::ResourceInfo {
+    fn from(detail: ResourceInfo) -> Self {
+        google_rpc::ResourceInfo {
+            resource_type: detail.resource_type,
+            resource_name: detail.resource_name,
+            owner: detail.owner,
+            description: detail.description,
+        }
+    }
+}
+
+impl From<google_rpc::ResourceInfo> for ResourceInfo {
+    fn from(detail: google_rpc::ResourceInfo) -> Self {
+        ResourceInfo {
+            resource_type: detail.resource_type,
+            resource_name: detail.resource_name,
+            owner: detail.owner,
+            description: detail.description,
+        }
+    }
+}
+
+impl From<DebugInfo> for google_rpc::DebugInfo {
+    fn from(detail: DebugInfo) -> Self {
+        google_rpc::DebugInfo {
+            stack_entries: detail.stack_entries,
+            detail: detail.detail,
+        }
+    }
+}
+
+impl From<google_rpc::DebugInfo> for DebugInfo {
+    fn from(detail: google_rpc::DebugInfo) -> Self {
+        DebugInfo {
+            stack_entries: detail.stack_entries,
+            detail: detail.detail,
+        }
+    }
+}
+
+impl From<Help> for google_rpc::Help {
+    fn from(detail: Help) -> Self {
+        google_rpc::Help {
+            links: detail
+                .links
+                .into_iter()
+                .map(|link| google_rpc::help::Link {
+                    description: link.description,
+                    url: link.url,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<google_rpc::Help> for Help {
+    fn from(detail: google_rpc::Help) -> Self {
+        Help {
+            links: detail
+                .links
+                .into_iter()
+                .map(|link| HelpLink {
+                    description: link.description,
+                    url: link.url,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<LocalizedMessage> for google_rpc::LocalizedMessage {
+    fn from(detail: LocalizedMessage) -> Self {
+        google_rpc::LocalizedMessage {
+            locale: detail.locale,
+            message: detail.message,
+        }
+    }
+}
+
+impl From<google_rpc::LocalizedMessage> for LocalizedMessage {
+    fn from(detail: google_rpc::LocalizedMessage) -> Self {
+        LocalizedMessage {
+            locale: detail.locale,
+            message: detail.message,
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./typed_detail_tests.rs"]
+mod typed_detail_tests;
@@ -0,0 +1,196 @@
+#[cfg(test)]
+mod typed_detail_tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use crate::error::{
+        BadRequest, DebugInfo, ErrorModel, ErrorReason, Help, LocalizedMessage,
+        PreconditionFailure, QuotaFailure, ResourceInfo, RetryInfo, Status, TypedDetail,
+        RETRY_INFO_TYPE_URL,
+    };
+
+    fn chat_group_validation_error() -> ErrorModel<ErrorReason> {
+        ErrorModel::new(
+            Status::InvalidArgument,
+            400,
+            "Invalid chat group".to_string(),
+        )
+        .with_typed_detail(
+            BadRequest::new()
+                .field_violation("title", "The chat title must not be empty")
+                .field_violation("about", "The chat about must be at most 255 characters"),
+        )
+    }
+
+    #[test]
+    fn test_typed_details_to_json() {
+        let json = serde_json::to_value(chat_group_validation_error()).unwrap();
+
+        assert_eq!(
+            json["typed_details"],
+            json!([{
+                "@type": "type.googleapis.com/google.rpc.BadRequest",
+                "fieldViolations": [
+                    { "field": "title", "description": "The chat title must not be empty" },
+                    { "field": "about", "description": "The chat about must be at most 255 characters" }
+                ]
+            }])
+        );
+    }
+
+    #[test]
+    fn test_typed_details_json_and_back() {
+        let model = chat_group_validation_error()
+            .with_typed_detail(RetryInfo::new(Duration::from_millis(1500)))
+            .with_typed_detail(LocalizedMessage::new("en-US", "Please add a title"));
+
+        let json = serde_json::to_string(&model).unwrap();
+        let decoded: ErrorModel<ErrorReason> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(decoded.typed_details, model.typed_details);
+    }
+
+    #[test]
+    fn test_model_without_typed_details_json() {
+        let model: ErrorModel<ErrorReason> =
+            serde_json::from_str(r#"{"code":500,"message":"","status":"Internal","details":[]}"#)
+                .unwrap();
+
+        assert!(model.typed_details.is_empty());
+        assert!(serde_json::to_value(model)
+            .unwrap()
+            .get("typed_details")
+            .is_none());
+    }
+
+    #[test]
+    fn test_typed_details_any_and_back() {
+        let details: Vec<TypedDetail> = vec![
+            BadRequest::new().field_violation("title", "empty").into(),
+            RetryInfo::new(Duration::from_millis(1500)).into(),
+            QuotaFailure::new()
+                .violation("user:42", "Daily limit of chat groups exceeded")
+                .into(),
+            PreconditionFailure::new()
+                .violation("TOS", "runtiva.com/chat", "Terms of service not accepted")
+                .into(),
+            ResourceInfo::new("chat group", "chatgroup-123")
+                .owner("user:42")
+                .description("The chat group is archived")
+                .into(),
+            DebugInfo::new("connection reset")
+                .stack_entry("persist::save")
+                .into(),
+            Help::new()
+                .link("Chat group limits", "https://runtiva.com/docs/limits")
+                .into(),
+            LocalizedMessage::new("fr-CH", "Le titre est vide").into(),
+        ];
+
+        for detail in details {
+            let any = detail.to_any();
+            assert_eq!(any.type_url, detail.type_url());
+
+            assert_eq!(TypedDetail::from_any(&any).unwrap(), Some(detail));
+        }
+    }
+
+    #[test]
+    fn test_unknown_any_is_skipped() {
+        let any = prost_types::Any {
+            type_url: "type.googleapis.com/google.rpc.RequestInfo".to_string(),
+            value: vec![],
+        };
+
+        assert_eq!(TypedDetail::from_any(&any).unwrap(), None);
+    }
+
+    #[test]
+    fn test_retry_info_delay_as_proto3_duration() {
+        let detail: TypedDetail = RetryInfo::new(Duration::from_millis(1500)).into();
+
+        assert_eq!(
+            serde_json::to_value(&detail).unwrap(),
+            json!({ "@type": RETRY_INFO_TYPE_URL, "retryDelay": "1.500s" })
+        );
+
+        for (delay, expected) in [
+            ("1.5s", Duration::from_millis(1500)),
+            ("30s", Duration::from_secs(30)),
+            ("0.000000001s", Duration::from_nanos(1)),
+        ] {
+            let detail: TypedDetail = serde_json::from_value(
+                json!({ "@type": RETRY_INFO_TYPE_URL, "retryDelay": delay }),
+            )
+            .unwrap();
+            assert_eq!(detail, RetryInfo::new(expected).into());
+        }
+    }
+
+    #[test]
+    fn test_snake_case_field_names_are_accepted() {
+        let detail: TypedDetail = serde_json::from_value(json!({
+            "@type": "type.googleapis.com/google.rpc.ResourceInfo",
+            "resource_type": "chat group",
+            "resource_name": "chatgroup-123",
+            "owner": "",
+            "description": ""
+        }))
+        .unwrap();
+
+        assert_eq!(
+            detail,
+            ResourceInfo::new("chat group", "chatgroup-123").into()
+        );
+    }
+
+    #[test]
+    fn test_retry_info_invalid_delay() {
+        for delay in [
+            json!("-1s"),
+            json!("1.s"),
+            json!("1.5"),
+            json!("1.0000000001s"),
+            json!({ "secs": 1, "nanos": 0 }),
+        ] {
+            let result = serde_json::from_value::<TypedDetail>(
+                json!({ "@type": RETRY_INFO_TYPE_URL, "retry_delay": delay }),
+            );
+            assert!(result.is_err(), "{delay}");
+        }
+    }
+
+    #[test]
+    fn test_unknown_typed_detail_json() {
+        let result = serde_json::from_value::<TypedDetail>(
+            json!({ "@type": "type.googleapis.com/google.rpc.ErrorInfo", "reason": "UNKNOWN" }),
+        );
+
+        assert!(result.is_err());
+        assert!(serde_json::from_value::<TypedDetail>(json!({ "detail": "no type" })).is_err());
+    }
+
+    #[test]
+    fn test_unknown_typed_details_are_skipped_in_model() {
+        let model: ErrorModel<ErrorReason> = serde_json::from_value(json!({
+            "code": 400,
+            "message": "Invalid chat group",
+            "status": "InvalidArgument",
+            "details": [],
+            "typed_details": [
+                { "@type": "type.googleapis.com/google.rpc.RequestInfo", "requestId": "42" },
+                {
+                    "@type": "type.googleapis.com/google.rpc.BadRequest",
+                    "fieldViolations": [{ "field": "title", "description": "empty" }]
+                }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(
+            model.typed_details,
+            vec![BadRequest::new().field_violation("title", "empty").into()]
+        );
+    }
+}
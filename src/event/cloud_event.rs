@@ -0,0 +1,397 @@
+use std::collections::BTreeMap;
+
+use async_nats::HeaderMap;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::server::NatsTransportError;
+
+use super::{EventEnvelope, INITIAL_SCHEMA_VERSION};
+
+pub const CLOUD_EVENTS_SPEC_VERSION: &str = "1.0";
+
+/// Content type of a structured mode CloudEvent
+pub const CLOUD_EVENTS_JSON_CONTENT_TYPE: &str = "application/cloudevents+json";
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+pub const PROTOBUF_CONTENT_TYPE: &str = "application/protobuf";
+
+pub const CONTENT_TYPE_HEADER: &str = "content-type";
+const CE_HEADER_PREFIX: &str = "ce-";
+
+// CloudEvents extension attributes carrying the rest of the EventEnvelope metadata
+const AGGREGATE_VERSION_EXTENSION: &str = "aggregateversion";
+const SCHEMA_VERSION_EXTENSION: &str = "schemaversion";
+const CAUSATION_ID_EXTENSION: &str = "causationid";
+const CORRELATION_ID_EXTENSION: &str = "correlationid";
+
+/// CloudEvents 1.0 content mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloudEventMode {
+    /// Attributes in `ce-*` NATS headers, the payload is the encoded event data
+    Binary,
+
+    /// Attributes and data in a single `application/cloudevents+json` payload.
+    /// Protobuf data is carried base64 encoded in `data_base64`.
+    Structured,
+}
+
+/// Publishes events in the CloudEvents 1.0 format, see [EventPublisher::cloud_events](super::EventPublisher::cloud_events).
+///
+/// The event metadata is mapped to the CloudEvents attributes:
+/// - `id`: event id
+/// - `source`: the configured source, e.g. `/runtiva/chat`
+/// - `type`: event subject, e.g. `chat.chatgroup.event.created`
+/// - `subject`: aggregate id
+/// - `time`: occurred at, when known
+/// - `datacontenttype`: `application/json` or `application/protobuf`
+/// - `dataschema`: the configured schema (if any)
+///
+/// The aggregate version, schema version, causation id and correlation id are sent as the
+/// `aggregateversion`, `schemaversion`, `causationid` and `correlationid` extension attributes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloudEventOptions {
+    pub(crate) mode: CloudEventMode,
+    pub(crate) source: String,
+    pub(crate) dataschema: Option<String>,
+}
+
+/// Structured mode CloudEvent
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct StructuredCloudEvent<T> {
+    pub specversion: String,
+    pub id: String,
+    pub source: String,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub datacontenttype: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dataschema: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_base64: Option<String>,
+    #[serde(flatten)]
+    pub extensions: BTreeMap<String, serde_json::Value>,
+}
+
+/// Event data of a decoded CloudEvent, still encoded with the `datacontenttype` codec
+#[derive(Debug)]
+pub(crate) struct CloudEventData {
+    pub envelope: EventEnvelope<()>,
+    pub data: Bytes,
+}
+
+impl CloudEventOptions {
+    pub fn new(mode: CloudEventMode, source: impl Into<String>) -> Self {
+        Self {
+            mode,
+            source: source.into(),
+            dataschema: None,
+        }
+    }
+
+    pub fn binary(source: impl Into<String>) -> Self {
+        Self::new(CloudEventMode::Binary, source)
+    }
+
+    pub fn structured(source: impl Into<String>) -> Self {
+        Self::new(CloudEventMode::Structured, source)
+    }
+
+    /// URI of the schema the event data adheres to
+    pub fn dataschema(mut self, dataschema: impl Into<String>) -> Self {
+        self.dataschema = Some(dataschema.into());
+        self
+    }
+
+    /// Attributes of the event, as (name, value) pairs without the `ce-` prefix
+    fn attributes<T>(
+        &self,
+        subject: &str,
+        event: &EventEnvelope<T>,
+        content_type: &str,
+    ) -> Vec<(&'static str, String)> {
+        let mut attributes = vec![
+            ("specversion", CLOUD_EVENTS_SPEC_VERSION.to_string()),
+            ("id", event.event_id.clone()),
+            ("source", self.source.clone()),
+            ("type", subject.to_string()),
+            ("subject", event.aggregate_id.clone()),
+        ];
+
+        if let Some(occurred_at) = event.occurred_at {
+            attributes.push(("time", occurred_at.to_rfc3339()));
+        }
+        attributes.push(("datacontenttype", content_type.to_string()));
+        if let Some(dataschema) = &self.dataschema {
+            attributes.push(("dataschema", dataschema.clone()));
+        }
+        attributes.push((
+            AGGREGATE_VERSION_EXTENSION,
+            event.aggregate_version.to_string(),
+        ));
+        attributes.push((SCHEMA_VERSION_EXTENSION, event.schema_version.to_string()));
+        if let Some(causation_id) = &event.causation_id {
+            attributes.push((CAUSATION_ID_EXTENSION, causation_id.clone()));
+        }
+        if let Some(correlation_id) = &event.correlation_id {
+            attributes.push((CORRELATION_ID_EXTENSION, correlation_id.clone()));
+        }
+
+        attributes
+    }
+
+    /// Headers of a binary mode event. The `datacontenttype` is sent as the content type.
+    pub(crate) fn binary_headers<T>(
+        &self,
+        subject: &str,
+        event: &EventEnvelope<T>,
+        content_type: &str,
+    ) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+
+        for (name, value) in self.attributes(subject, event, content_type) {
+            if name == "datacontenttype" {
+                headers.insert(CONTENT_TYPE_HEADER, value.as_str());
+            } else {
+                headers.insert(
+                    format!("{}{}", CE_HEADER_PREFIX, name).as_str(),
+                    value.as_str(),
+                );
+            }
+        }
+
+        headers
+    }
+
+    /// Structured mode event, with JSON data embedded as is and other data base64 encoded
+    pub(crate) fn structured_event<T, D>(
+        &self,
+        subject: &str,
+        event: &EventEnvelope<T>,
+        content_type: &str,
+        data: Option<D>,
+        data_base64: Option<Bytes>,
+    ) -> StructuredCloudEvent<D> {
+        let mut attributes: BTreeMap<&str, String> = self
+            .attributes(subject, event, content_type)
+            .into_iter()
+            .collect();
+
+        StructuredCloudEvent {
+            specversion: attributes.remove("specversion").unwrap_or_default(),
+            id: attributes.remove("id").unwrap_or_default(),
+            source: attributes.remove("source").unwrap_or_default(),
+            event_type: attributes.remove("type").unwrap_or_default(),
+            subject: attributes.remove("subject"),
+            time: attributes.remove("time"),
+            datacontenttype: attributes.remove("datacontenttype"),
+            dataschema: attributes.remove("dataschema"),
+            data,
+            data_base64: data_base64.map(|data| BASE64.encode(data)),
+            // the remaining attributes are the extensions
+            extensions: attributes
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), serde_json::Value::String(value)))
+                .collect(),
+        }
+    }
+}
+
+/// Headers of a structured mode event
+pub(crate) fn structured_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE_HEADER, CLOUD_EVENTS_JSON_CONTENT_TYPE);
+    headers
+}
+
+/// Returns the content mode of a CloudEvent message, or None for other messages
+pub(crate) fn cloud_event_mode(headers: Option<&HeaderMap>) -> Option<CloudEventMode> {
+    let headers = headers?;
+
+    let content_type = headers
+        .get(CONTENT_TYPE_HEADER)
+        .map(|value| value.to_string());
+    if content_type
+        .as_deref()
+        .is_some_and(|content_type| content_type.starts_with(CLOUD_EVENTS_JSON_CONTENT_TYPE))
+    {
+        Some(CloudEventMode::Structured)
+    } else if headers.get("ce-specversion").is_some() {
+        Some(CloudEventMode::Binary)
+    } else {
+        None
+    }
+}
+
+/// Decodes the attributes of a binary mode event, whose data is expected in the `content_type`
+pub(crate) fn decode_binary(
+    headers: &HeaderMap,
+    payload: Bytes,
+    content_type: &str,
+) -> Result<CloudEventData, NatsTransportError> {
+    let attribute = |name: &str| {
+        headers
+            .get(format!("{}{}", CE_HEADER_PREFIX, name).as_str())
+            .map(|value| value.to_string())
+    };
+
+    let envelope = envelope(attribute)?;
+    check_content_type(
+        headers
+            .get(CONTENT_TYPE_HEADER)
+            .map(|value| value.to_string())
+            .as_deref(),
+        content_type,
+    )?;
+
+    Ok(CloudEventData {
+        envelope,
+        data: payload,
+    })
+}
+
+/// Decodes a structured mode event, whose data is expected in the `content_type`, re-encoding
+/// JSON data so it can be decoded by the data codec
+pub(crate) fn decode_structured(
+    payload: Bytes,
+    content_type: &str,
+) -> Result<CloudEventData, NatsTransportError> {
+    let event: StructuredCloudEvent<serde_json::Value> = serde_json::from_slice(&payload)
+        .map_err(|err| NatsTransportError::DeserializeEvent(Box::new(err)))?;
+
+    let attribute = |name: &str| match name {
+        "specversion" => Some(event.specversion.clone()),
+        "id" => Some(event.id.clone()),
+        "source" => Some(event.source.clone()),
+        "type" => Some(event.event_type.clone()),
+        "subject" => event.subject.clone(),
+        "time" => event.time.clone(),
+        extension => event.extensions.get(extension).map(|value| match value {
+            serde_json::Value::String(value) => value.clone(),
+            value => value.to_string(),
+        }),
+    };
+
+    let envelope = envelope(attribute)?;
+    check_content_type(event.datacontenttype.as_deref(), content_type)?;
+
+    let data = match (event.data, event.data_base64) {
+        (_, Some(data_base64)) => BASE64
+            .decode(data_base64)
+            .map_err(|err| NatsTransportError::DeserializeEvent(Box::new(err)))?
+            .into(),
+        (Some(data), None) => serde_json::to_vec(&data)
+            .map_err(|err| NatsTransportError::DeserializeEvent(Box::new(err)))?
+            .into(),
+        (None, None) => Bytes::new(),
+    };
+
+    Ok(CloudEventData { envelope, data })
+}
+
+/// Maps the CloudEvents attributes back to the event metadata, in either mode. The required
+/// `specversion`, `id`, `source` and `type` attributes are validated. Events published by other
+/// producers without the extension attributes have a zero aggregate version and the initial
+/// schema version.
+fn envelope(
+    attribute: impl Fn(&str) -> Option<String>,
+) -> Result<EventEnvelope<()>, NatsTransportError> {
+    let invalid =
+        |name: &str| NatsTransportError::InvalidEventMetadata(format!("invalid {}", name));
+    let missing =
+        |name: &str| NatsTransportError::InvalidEventMetadata(format!("missing {}", name));
+
+    if attribute("specversion").as_deref() != Some(CLOUD_EVENTS_SPEC_VERSION) {
+        return Err(invalid("specversion"));
+    }
+
+    let event_id = attribute("id").unwrap_or_default();
+    if event_id.is_empty() {
+        return Err(missing("id"));
+    }
+
+    if attribute("source").unwrap_or_default().is_empty() {
+        return Err(missing("source"));
+    }
+
+    // the type is the event subject, whose last token is the event type
+    let event_type = attribute("type").ok_or_else(|| missing("type"))?;
+    let event_type = event_type
+        .rsplit('.')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+
+    let occurred_at = attribute("time")
+        .map(|time| DateTime::parse_from_rfc3339(&time).map_err(|_| invalid("time")))
+        .transpose()?
+        .map(|time| time.with_timezone(&Utc));
+
+    Ok(EventEnvelope {
+        event_id,
+        event_type,
+        schema_version: parse_optional(&attribute, SCHEMA_VERSION_EXTENSION)?
+            .unwrap_or(INITIAL_SCHEMA_VERSION),
+        aggregate_id: attribute("subject").unwrap_or_default(),
+        aggregate_version: parse_optional(&attribute, AGGREGATE_VERSION_EXTENSION)?
+            .unwrap_or_default(),
+        occurred_at,
+        causation_id: attribute(CAUSATION_ID_EXTENSION),
+        correlation_id: attribute(CORRELATION_ID_EXTENSION),
+        data: (),
+    })
+}
+
+/// Rejects a `datacontenttype` other than the content type of the data codec. Events without
+/// one are decoded with the codec.
+fn check_content_type(
+    datacontenttype: Option<&str>,
+    content_type: &str,
+) -> Result<(), NatsTransportError> {
+    let media_type = |value: &str| {
+        value
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase()
+    };
+
+    match datacontenttype {
+        Some(datacontenttype) if media_type(datacontenttype) != content_type => {
+            Err(NatsTransportError::InvalidEventMetadata(format!(
+                "datacontenttype {} does not match {}",
+                datacontenttype, content_type
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn parse_optional<V>(
+    attribute: &impl Fn(&str) -> Option<String>,
+    name: &str,
+) -> Result<Option<V>, NatsTransportError>
+where
+    V: std::str::FromStr,
+{
+    attribute(name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| NatsTransportError::InvalidEventMetadata(format!("invalid {}", name)))
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+#[path = "./cloud_event_tests.rs"]
+mod cloud_event_tests;
@@ -0,0 +1,257 @@
+#[cfg(test)]
+mod cloud_event_tests {
+    use async_nats::HeaderMap;
+    use bytes::Bytes;
+    use prost::Message as _;
+    use uuid::Uuid;
+
+    use crate::{
+        event::{
+            cloud_event::{cloud_event_mode, decode_binary, decode_structured, structured_headers},
+            CloudEventMode, CloudEventOptions, EventEnvelope, CONTENT_TYPE_HEADER,
+            JSON_CONTENT_TYPE, PROTOBUF_CONTENT_TYPE,
+        },
+        proto_test as proto,
+        server::NatsTransportError,
+    };
+
+    use super::ChatGroupCreated;
+
+    fn event() -> EventEnvelope<()> {
+        EventEnvelope::new("created", "chatgroup-123", 4, ())
+            .with_causation_id(Uuid::new_v4())
+            .with_correlation_id(Uuid::new_v4())
+    }
+
+    #[test]
+    fn test_binary_headers() {
+        let event = event();
+        let options = CloudEventOptions::binary("/runtiva/chat").dataschema("chatgroup.v1");
+
+        let headers =
+            options.binary_headers("chat.chatgroup.event.created", &event, JSON_CONTENT_TYPE);
+
+        let header = |name: &str| headers.get(name).map(|value| value.to_string());
+        assert_eq!(header("ce-specversion").unwrap(), "1.0");
+        assert_eq!(header("ce-id").unwrap(), event.event_id.to_string());
+        assert_eq!(header("ce-source").unwrap(), "/runtiva/chat");
+        assert_eq!(header("ce-type").unwrap(), "chat.chatgroup.event.created");
+        assert_eq!(header("ce-subject").unwrap(), "chatgroup-123");
+        assert_eq!(header("ce-dataschema").unwrap(), "chatgroup.v1");
+        assert_eq!(header("ce-aggregateversion").unwrap(), "4");
+        assert_eq!(header(CONTENT_TYPE_HEADER).unwrap(), JSON_CONTENT_TYPE);
+        assert_eq!(
+            cloud_event_mode(Some(&headers)),
+            Some(CloudEventMode::Binary)
+        );
+    }
+
+    #[test]
+    fn test_binary_and_back() {
+        let event = event();
+        let options = CloudEventOptions::binary("/runtiva/chat");
+        let headers = options.binary_headers(
+            "chat.chatgroup.event.created",
+            &event,
+            PROTOBUF_CONTENT_TYPE,
+        );
+        let payload: Bytes = proto::UserData {
+            id: "1234".into(),
+            attr: "test".into(),
+            credit: 23.23,
+        }
+        .encode_to_vec()
+        .into();
+
+        let decoded = decode_binary(&headers, payload.clone(), PROTOBUF_CONTENT_TYPE).unwrap();
+
+        assert_eq!(decoded.envelope, event);
+        assert_eq!(decoded.data, payload);
+    }
+
+    #[test]
+    fn test_structured_json_and_back() {
+        let event = event();
+        let options = CloudEventOptions::structured("/runtiva/chat");
+        let data = ChatGroupCreated {
+            title: "Hello".to_string(),
+        };
+
+        let structured = options.structured_event(
+            "chat.chatgroup.event.created",
+            &event,
+            JSON_CONTENT_TYPE,
+            Some(data),
+            None,
+        );
+        let payload = serde_json::to_vec(&structured).unwrap();
+
+        let json: serde_json::Value = serde_json::from_slice(&payload).unwrap();
+        assert_eq!(json["type"], "chat.chatgroup.event.created");
+        assert_eq!(json["data"]["title"], "Hello");
+        assert_eq!(json["aggregateversion"], "4");
+        assert!(json.get("data_base64").is_none());
+
+        let decoded = decode_structured(payload.into(), JSON_CONTENT_TYPE).unwrap();
+        let data: ChatGroupCreated = serde_json::from_slice(&decoded.data).unwrap();
+
+        assert_eq!(decoded.envelope, event);
+        assert_eq!(data.title, "Hello".to_string());
+    }
+
+    #[test]
+    fn test_structured_prost_and_back() {
+        let event = event();
+        let options = CloudEventOptions::structured("/runtiva/chat");
+        let data: Bytes = proto::UserData {
+            id: "1234".into(),
+            attr: "test".into(),
+            credit: 23.23,
+        }
+        .encode_to_vec()
+        .into();
+
+        let structured = options.structured_event::<(), ()>(
+            "chat.chatgroup.event.created",
+            &event,
+            PROTOBUF_CONTENT_TYPE,
+            None,
+            Some(data.clone()),
+        );
+        let payload = serde_json::to_vec(&structured).unwrap();
+
+        let decoded = decode_structured(payload.into(), PROTOBUF_CONTENT_TYPE).unwrap();
+
+        assert_eq!(decoded.envelope, event);
+        assert_eq!(decoded.data, data);
+    }
+
+    #[test]
+    fn test_cloud_event_mode() {
+        let mut native = HeaderMap::new();
+        native.insert("x-event-id", Uuid::new_v4().to_string().as_str());
+
+        assert_eq!(
+            cloud_event_mode(Some(&structured_headers())),
+            Some(CloudEventMode::Structured)
+        );
+        assert_eq!(cloud_event_mode(Some(&native)), None);
+        assert_eq!(cloud_event_mode(None), None);
+    }
+
+    #[test]
+    fn test_foreign_event_without_extensions() {
+        let payload = Bytes::from_static(
+            br#"{
+                "specversion": "1.0",
+                "id": "invoice-42-paid",
+                "source": "/billing",
+                "type": "com.example.invoice.paid",
+                "data": {"title": "Hello"}
+            }"#,
+        );
+
+        let decoded = decode_structured(payload, JSON_CONTENT_TYPE).unwrap();
+
+        // any non-empty id is accepted, and the time is optional
+        assert_eq!(decoded.envelope.event_id, "invoice-42-paid");
+        assert_eq!(decoded.envelope.occurred_at, None);
+        assert_eq!(decoded.envelope.event_type, "paid".to_string());
+        assert_eq!(decoded.envelope.aggregate_version, 0);
+        assert_eq!(decoded.envelope.causation_id, None);
+    }
+
+    #[test]
+    fn test_binary_requires_id() {
+        let mut headers = CloudEventOptions::binary("/runtiva/chat").binary_headers(
+            "chat.chatgroup.event.created",
+            &event(),
+            JSON_CONTENT_TYPE,
+        );
+        headers.insert("ce-id", "");
+
+        assert!(matches!(
+            decode_binary(&headers, Bytes::new(), JSON_CONTENT_TYPE),
+            Err(NatsTransportError::InvalidEventMetadata(message)) if message == "missing id"
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_spec_version() {
+        let mut headers = CloudEventOptions::binary("/runtiva/chat").binary_headers(
+            "chat.chatgroup.event.created",
+            &event(),
+            JSON_CONTENT_TYPE,
+        );
+        headers.insert("ce-specversion", "0.3");
+
+        assert!(matches!(
+            decode_binary(&headers, Bytes::new(), JSON_CONTENT_TYPE),
+            Err(NatsTransportError::InvalidEventMetadata(_))
+        ));
+    }
+
+    #[test]
+    fn test_binary_requires_source() {
+        let mut headers = CloudEventOptions::binary("/runtiva/chat").binary_headers(
+            "chat.chatgroup.event.created",
+            &event(),
+            JSON_CONTENT_TYPE,
+        );
+        headers.insert("ce-source", "");
+
+        assert!(matches!(
+            decode_binary(&headers, Bytes::new(), JSON_CONTENT_TYPE),
+            Err(NatsTransportError::InvalidEventMetadata(message)) if message == "missing source"
+        ));
+    }
+
+    #[test]
+    fn test_content_type_must_match_codec() {
+        let headers = CloudEventOptions::binary("/runtiva/chat").binary_headers(
+            "chat.chatgroup.event.created",
+            &event(),
+            JSON_CONTENT_TYPE,
+        );
+
+        assert!(matches!(
+            decode_binary(&headers, Bytes::new(), PROTOBUF_CONTENT_TYPE),
+            Err(NatsTransportError::InvalidEventMetadata(_))
+        ));
+
+        let payload = serde_json::to_vec(
+            &CloudEventOptions::structured("/runtiva/chat").structured_event::<(), ()>(
+                "chat.chatgroup.event.created",
+                &event(),
+                PROTOBUF_CONTENT_TYPE,
+                None,
+                Some(Bytes::from_static(b"data")),
+            ),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            decode_structured(payload.into(), JSON_CONTENT_TYPE),
+            Err(NatsTransportError::InvalidEventMetadata(_))
+        ));
+    }
+
+    #[test]
+    fn test_content_type_parameters_are_ignored() {
+        let mut headers = CloudEventOptions::binary("/runtiva/chat").binary_headers(
+            "chat.chatgroup.event.created",
+            &event(),
+            JSON_CONTENT_TYPE,
+        );
+        headers.insert(CONTENT_TYPE_HEADER, "Application/JSON; charset=utf-8");
+
+        assert!(decode_binary(&headers, Bytes::new(), JSON_CONTENT_TYPE).is_ok());
+    }
+}
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ChatGroupCreated {
+    pub title: String,
+}
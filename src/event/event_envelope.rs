@@ -0,0 +1,254 @@
+use async_nats::HeaderMap;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::server::NatsTransportError;
+
+/// Headers carrying the [EventEnvelope] metadata of an event message
+pub const EVENT_ID_HEADER: &str = "x-event-id";
+pub const EVENT_TYPE_HEADER: &str = "x-event-type";
+pub const AGGREGATE_ID_HEADER: &str = "x-aggregate-id";
+pub const AGGREGATE_VERSION_HEADER: &str = "x-aggregate-version";
+pub const OCCURRED_AT_HEADER: &str = "x-occurred-at";
+pub const CAUSATION_ID_HEADER: &str = "x-causation-id";
+pub const CORRELATION_ID_HEADER: &str = "x-correlation-id";
+pub const SCHEMA_VERSION_HEADER: &str = "x-schema-version";
+
+/// Schema version of events published without one
+pub const INITIAL_SCHEMA_VERSION: u32 = 1;
+
+/// Event data type declaring the version of its schema, to be upcast from older
+/// versions by an [UpcasterRegistry](super::UpcasterRegistry).
+///
+/// Required to receive events with an [EventSubscriber](super::EventSubscriber), which rejects
+/// events of a newer version. Types whose schema never changed declare the
+/// [INITIAL_SCHEMA_VERSION].
+pub trait VersionedEvent {
+    const SCHEMA_VERSION: u32;
+}
+
+/// Domain event with the metadata identifying it within its aggregate's history.
+///
+/// On the wire the metadata is carried by the NATS message headers, and the payload
+/// is the event data encoded in JSON or protobuf.
+///
+/// ```
+/// use nats_transport::event::EventEnvelope;
+///
+/// let created = EventEnvelope::new("created", "chatgroup-123", 1, "Hello".to_string());
+///
+/// // events raised while handling `created` share its correlation id
+/// let renamed = EventEnvelope::new("renamed", "chatgroup-123", 2, "Hi".to_string())
+///     .caused_by(&created);
+///
+/// assert_eq!(renamed.causation_id, Some(created.event_id.clone()));
+/// assert_eq!(renamed.correlation_id, Some(created.event_id));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventEnvelope<T> {
+    /// Unique id of the event, a UUID for the events created by [EventEnvelope::new]. Events
+    /// received from other producers (e.g. as CloudEvents) may use any non-empty id.
+    pub event_id: String,
+
+    /// Name of the event within its domain, used as the last token of the event subject
+    pub event_type: String,
+
+    /// Version of the schema the event data was published with
+    pub schema_version: u32,
+
+    pub aggregate_id: String,
+
+    /// Version of the aggregate after the event was applied
+    pub aggregate_version: u64,
+
+    /// None for events received without a time, e.g. CloudEvents whose `time` is optional
+    pub occurred_at: Option<DateTime<Utc>>,
+
+    /// Id of the event (or command) that caused this event
+    pub causation_id: Option<String>,
+
+    /// Id shared by all events resulting from the same original request
+    pub correlation_id: Option<String>,
+
+    pub data: T,
+}
+
+impl<T> EventEnvelope<T> {
+    /// Creates an envelope with a new event id, occurring now
+    pub fn new(
+        event_type: impl Into<String>,
+        aggregate_id: impl Into<String>,
+        aggregate_version: u64,
+        data: T,
+    ) -> Self {
+        Self {
+            event_id: Uuid::new_v4().to_string(),
+            event_type: event_type.into(),
+            schema_version: INITIAL_SCHEMA_VERSION,
+            aggregate_id: aggregate_id.into(),
+            aggregate_version,
+            occurred_at: Some(Utc::now()),
+            causation_id: None,
+            correlation_id: None,
+            data,
+        }
+    }
+
+    /// Creates an envelope with the schema version declared by the event data type
+    pub fn versioned(
+        event_type: impl Into<String>,
+        aggregate_id: impl Into<String>,
+        aggregate_version: u64,
+        data: T,
+    ) -> Self
+    where
+        T: VersionedEvent,
+    {
+        Self::new(event_type, aggregate_id, aggregate_version, data)
+            .with_schema_version(T::SCHEMA_VERSION)
+    }
+
+    /// Sets the causation id to the given event, and inherits its correlation id
+    /// (the given event starts the correlation if it has none)
+    pub fn caused_by<C>(mut self, cause: &EventEnvelope<C>) -> Self {
+        self.causation_id = Some(cause.event_id.clone());
+        self.correlation_id = Some(
+            cause
+                .correlation_id
+                .clone()
+                .unwrap_or_else(|| cause.event_id.clone()),
+        );
+        self
+    }
+
+    pub fn with_causation_id(mut self, causation_id: impl Into<String>) -> Self {
+        self.causation_id = Some(causation_id.into());
+        self
+    }
+
+    pub fn with_correlation_id(mut self, correlation_id: impl Into<String>) -> Self {
+        self.correlation_id = Some(correlation_id.into());
+        self
+    }
+
+    pub fn with_schema_version(mut self, schema_version: u32) -> Self {
+        self.schema_version = schema_version;
+        self
+    }
+
+    /// Replaces the event data, keeping the metadata
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> EventEnvelope<U> {
+        EventEnvelope {
+            event_id: self.event_id,
+            event_type: self.event_type,
+            schema_version: self.schema_version,
+            aggregate_id: self.aggregate_id,
+            aggregate_version: self.aggregate_version,
+            occurred_at: self.occurred_at,
+            causation_id: self.causation_id,
+            correlation_id: self.correlation_id,
+            data: f(self.data),
+        }
+    }
+
+    /// Splits the envelope into its metadata and its data
+    pub fn into_parts(self) -> (EventEnvelope<()>, T) {
+        let metadata = EventEnvelope {
+            event_id: self.event_id,
+            event_type: self.event_type,
+            schema_version: self.schema_version,
+            aggregate_id: self.aggregate_id,
+            aggregate_version: self.aggregate_version,
+            occurred_at: self.occurred_at,
+            causation_id: self.causation_id,
+            correlation_id: self.correlation_id,
+            data: (),
+        };
+
+        (metadata, self.data)
+    }
+
+    /// Returns the metadata as NATS message headers
+    pub fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+
+        headers.insert(EVENT_ID_HEADER, self.event_id.as_str());
+        headers.insert(EVENT_TYPE_HEADER, self.event_type.as_str());
+        headers.insert(
+            SCHEMA_VERSION_HEADER,
+            self.schema_version.to_string().as_str(),
+        );
+        headers.insert(AGGREGATE_ID_HEADER, self.aggregate_id.as_str());
+        headers.insert(
+            AGGREGATE_VERSION_HEADER,
+            self.aggregate_version.to_string().as_str(),
+        );
+        if let Some(occurred_at) = self.occurred_at {
+            headers.insert(OCCURRED_AT_HEADER, occurred_at.to_rfc3339().as_str());
+        }
+        if let Some(causation_id) = &self.causation_id {
+            headers.insert(CAUSATION_ID_HEADER, causation_id.as_str());
+        }
+        if let Some(correlation_id) = &self.correlation_id {
+            headers.insert(CORRELATION_ID_HEADER, correlation_id.as_str());
+        }
+
+        headers
+    }
+
+    /// Rebuilds the envelope from the NATS message headers and the decoded event data.
+    /// Events published without a schema version have the [INITIAL_SCHEMA_VERSION].
+    pub fn from_headers(headers: &HeaderMap, data: T) -> Result<Self, NatsTransportError> {
+        let event_id = header(headers, EVENT_ID_HEADER)?;
+        if event_id.is_empty() {
+            return Err(NatsTransportError::InvalidEventMetadata(format!(
+                "invalid {}",
+                EVENT_ID_HEADER
+            )));
+        }
+
+        Ok(Self {
+            event_id,
+            event_type: header(headers, EVENT_TYPE_HEADER)?,
+            schema_version: parse_optional(headers, SCHEMA_VERSION_HEADER)?
+                .unwrap_or(INITIAL_SCHEMA_VERSION),
+            aggregate_id: header(headers, AGGREGATE_ID_HEADER)?,
+            aggregate_version: parse(headers, AGGREGATE_VERSION_HEADER)?,
+            occurred_at: parse_optional(headers, OCCURRED_AT_HEADER)?,
+            causation_id: optional_header(headers, CAUSATION_ID_HEADER),
+            correlation_id: optional_header(headers, CORRELATION_ID_HEADER),
+            data,
+        })
+    }
+}
+
+fn optional_header(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers.get(name).map(|value| value.to_string())
+}
+
+fn header(headers: &HeaderMap, name: &str) -> Result<String, NatsTransportError> {
+    optional_header(headers, name)
+        .ok_or_else(|| NatsTransportError::InvalidEventMetadata(format!("missing {}", name)))
+}
+
+fn parse<V>(headers: &HeaderMap, name: &str) -> Result<V, NatsTransportError>
+where
+    V: std::str::FromStr,
+{
+    header(headers, name)?
+        .parse()
+        .map_err(|_| NatsTransportError::InvalidEventMetadata(format!("invalid {}", name)))
+}
+
+fn parse_optional<V>(headers: &HeaderMap, name: &str) -> Result<Option<V>, NatsTransportError>
+where
+    V: std::str::FromStr,
+{
+    optional_header(headers, name)
+        .map(|_| parse(headers, name))
+        .transpose()
+}
+
+#[cfg(test)]
+#[path = "./event_envelope_tests.rs"]
+mod event_envelope_tests;
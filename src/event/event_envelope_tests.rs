@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod event_envelope_tests {
+    use async_nats::HeaderMap;
+    use uuid::Uuid;
+
+    use crate::{
+        event::{EventEnvelope, AGGREGATE_VERSION_HEADER, EVENT_ID_HEADER},
+        server::NatsTransportError,
+    };
+
+    #[test]
+    fn test_headers_and_back() {
+        let event = EventEnvelope::new("created", "chatgroup-123", 1, ())
+            .with_causation_id(Uuid::new_v4())
+            .with_correlation_id(Uuid::new_v4());
+
+        let and_back = EventEnvelope::from_headers(&event.headers(), ()).unwrap();
+
+        assert_eq!(and_back, event);
+    }
+
+    #[test]
+    fn test_optional_metadata_is_not_sent() {
+        let mut event = EventEnvelope::new("created", "chatgroup-123", 1, ());
+        event.occurred_at = None;
+
+        let and_back = EventEnvelope::from_headers(&event.headers(), ()).unwrap();
+
+        assert_eq!(and_back.occurred_at, None);
+        assert_eq!(and_back.causation_id, None);
+        assert_eq!(and_back.correlation_id, None);
+    }
+
+    #[test]
+    fn test_caused_by_keeps_correlation() {
+        let created = EventEnvelope::new("created", "chatgroup-123", 1, ())
+            .with_correlation_id(Uuid::new_v4());
+        let renamed = EventEnvelope::new("renamed", "chatgroup-123", 2, ()).caused_by(&created);
+
+        assert_eq!(renamed.causation_id, Some(created.event_id));
+        assert_eq!(renamed.correlation_id, created.correlation_id);
+    }
+
+    #[test]
+    fn test_invalid_metadata() {
+        let event = EventEnvelope::new("created", "chatgroup-123", 1, ());
+
+        let mut headers = event.headers();
+        headers.insert(AGGREGATE_VERSION_HEADER, "first");
+        assert!(matches!(
+            EventEnvelope::from_headers(&headers, ()),
+            Err(NatsTransportError::InvalidEventMetadata(_))
+        ));
+
+        let mut headers = event.headers();
+        headers.insert(EVENT_ID_HEADER, "");
+        assert!(matches!(
+            EventEnvelope::from_headers(&headers, ()),
+            Err(NatsTransportError::InvalidEventMetadata(_))
+        ));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(EVENT_ID_HEADER, event.event_id.as_str());
+        assert!(matches!(
+            EventEnvelope::from_headers(&headers, ()),
+            Err(NatsTransportError::InvalidEventMetadata(_))
+        ));
+    }
+}
@@ -0,0 +1,191 @@
+use std::sync::Arc;
+
+use async_nats::HeaderMap;
+use serde::Serialize;
+
+use crate::{
+    server::{
+        serde::{NatsMessageSerde, Serializer},
+        JetStreamPublishJson, JetStreamPublishOptions, JetStreamPublishProst, NatsServer,
+        NatsTransportError, PublishJson, PublishProst,
+    },
+    Subject,
+};
+
+use super::{
+    cloud_event::structured_headers, CloudEventMode, CloudEventOptions, EventEnvelope,
+    JSON_CONTENT_TYPE, PROTOBUF_CONTENT_TYPE,
+};
+
+/// Publishes [EventEnvelope]s to `chat.<domain>.event.<event_type>` (see [Subject::event]).
+///
+/// A durable publisher stores the events in the JetStream stream bound to the subject, using the
+/// event id as deduplication id, so retried publishes of the same event are only stored once.
+///
+/// The event metadata is sent in the `x-event-*` headers, unless the publisher is configured to
+/// publish CloudEvents with [EventPublisher::cloud_events].
+pub struct EventPublisher {
+    nats_server: Arc<NatsServer>,
+    durable: bool,
+    cloud_events: Option<CloudEventOptions>,
+}
+
+impl EventPublisher {
+    /// Publishes events with core NATS: events are lost if no subscriber is online
+    pub fn new(nats_server: Arc<NatsServer>) -> Self {
+        Self {
+            nats_server,
+            durable: false,
+            cloud_events: None,
+        }
+    }
+
+    /// Publishes events to JetStream, waiting for the stream to store them
+    pub fn durable(nats_server: Arc<NatsServer>) -> Self {
+        Self {
+            nats_server,
+            durable: true,
+            cloud_events: None,
+        }
+    }
+
+    /// Publishes the events in the CloudEvents 1.0 format, for consumers outside of this crate
+    pub fn cloud_events(mut self, options: CloudEventOptions) -> Self {
+        self.cloud_events = Some(options);
+        self
+    }
+
+    /// Publishes the event with its data serialized in JSON
+    pub async fn publish_json<T>(
+        &self,
+        domain: &str,
+        event: EventEnvelope<T>,
+    ) -> Result<(), NatsTransportError>
+    where
+        T: Serialize + Send + Sync + 'static,
+    {
+        let subject = Subject::event(domain, &event.event_type)?.to_string();
+        let options = self.publish_options(&event);
+
+        match &self.cloud_events {
+            None => {
+                let headers = event.headers();
+                self.send_json(subject, headers, event.data, options).await
+            }
+            Some(cloud_events) if cloud_events.mode == CloudEventMode::Binary => {
+                let headers = cloud_events.binary_headers(&subject, &event, JSON_CONTENT_TYPE);
+                self.send_json(subject, headers, event.data, options).await
+            }
+            Some(cloud_events) => {
+                let (metadata, data) = event.into_parts();
+                let event = cloud_events.structured_event(
+                    &subject,
+                    &metadata,
+                    JSON_CONTENT_TYPE,
+                    Some(data),
+                    None,
+                );
+                self.send_json(subject, structured_headers(), event, options)
+                    .await
+            }
+        }
+    }
+
+    /// Publishes the event with its data encoded in protobuf
+    pub async fn publish_prost<T>(
+        &self,
+        domain: &str,
+        event: EventEnvelope<T>,
+    ) -> Result<(), NatsTransportError>
+    where
+        T: prost::Message + Default + 'static,
+    {
+        let subject = Subject::event(domain, &event.event_type)?.to_string();
+        let options = self.publish_options(&event);
+
+        match &self.cloud_events {
+            None => {
+                let headers = event.headers();
+                self.send_prost(subject, headers, event.data, options).await
+            }
+            Some(cloud_events) if cloud_events.mode == CloudEventMode::Binary => {
+                let headers = cloud_events.binary_headers(&subject, &event, PROTOBUF_CONTENT_TYPE);
+                self.send_prost(subject, headers, event.data, options).await
+            }
+            Some(cloud_events) => {
+                // protobuf data is embedded base64 encoded in the JSON event
+                let (metadata, data) = event.into_parts();
+                let data = NatsMessageSerde::<T>::default()
+                    .serialize(data)
+                    .unwrap_or_else(|never| match never {});
+                let event = cloud_events.structured_event::<(), ()>(
+                    &subject,
+                    &metadata,
+                    PROTOBUF_CONTENT_TYPE,
+                    None,
+                    Some(data),
+                );
+                self.send_json(subject, structured_headers(), event, options)
+                    .await
+            }
+        }
+    }
+
+    /// JetStream options of a durable publisher, None for core NATS
+    fn publish_options<T>(&self, event: &EventEnvelope<T>) -> Option<JetStreamPublishOptions> {
+        self.durable
+            .then(|| JetStreamPublishOptions::new().message_id(event.event_id.clone()))
+    }
+
+    async fn send_json<T>(
+        &self,
+        subject: String,
+        headers: HeaderMap,
+        msg: T,
+        options: Option<JetStreamPublishOptions>,
+    ) -> Result<(), NatsTransportError>
+    where
+        T: Serialize + Send + Sync + 'static,
+    {
+        let nats_server = self.nats_server.as_ref();
+
+        match options {
+            Some(options) => JetStreamPublishJson::jetstream_publish(
+                nats_server,
+                subject,
+                Some(headers),
+                msg,
+                options,
+            )
+            .await
+            .map(|_| ()),
+            None => PublishJson::publish_with_headers(nats_server, subject, headers, msg).await,
+        }
+    }
+
+    async fn send_prost<T>(
+        &self,
+        subject: String,
+        headers: HeaderMap,
+        msg: T,
+        options: Option<JetStreamPublishOptions>,
+    ) -> Result<(), NatsTransportError>
+    where
+        T: prost::Message + Default + 'static,
+    {
+        let nats_server = self.nats_server.as_ref();
+
+        match options {
+            Some(options) => JetStreamPublishProst::jetstream_publish(
+                nats_server,
+                subject,
+                Some(headers),
+                msg,
+                options,
+            )
+            .await
+            .map(|_| ()),
+            None => PublishProst::publish_with_headers(nats_server, subject, headers, msg).await,
+        }
+    }
+}
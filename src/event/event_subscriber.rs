@@ -0,0 +1,250 @@
+use std::{sync::Arc, time::Duration};
+
+use async_nats::{HeaderMap, Message};
+use bytes::Bytes;
+use futures::{Future, FutureExt};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    server::{
+        receiver::{
+            outcome_handler, AckOutcome, OutcomeHandler, Subscribe, SubscribeOptions,
+            SubscriptionHandle,
+        },
+        serde::{Deserializer, NatsJson, NatsMessageSerde},
+        NatsTransportError,
+    },
+    SubjectKind, SubjectPattern,
+};
+
+use super::{
+    cloud_event::{cloud_event_mode, decode_binary, decode_structured},
+    CloudEventMode, EventEnvelope, JsonUpcasters, ProstUpcasters, VersionedEvent,
+    JSON_CONTENT_TYPE, PROTOBUF_CONTENT_TYPE,
+};
+
+/// Subscribes to the events of a domain, decoding each message into an [EventEnvelope]
+/// so the handlers have access to the event metadata.
+///
+/// Events published as CloudEvents, in binary or structured mode, are decoded as well.
+///
+/// Messages that cannot be decoded are logged and not passed to the handler. When received from
+/// a [JetStreamReceiver](crate::server::receiver::JetStreamReceiver), they are terminated, except
+/// for events of a schema version newer than the data type, which are redelivered after
+/// [UNSUPPORTED_VERSION_REDELIVERY_DELAY] (e.g. to an updated instance of the service).
+/// Decoded events are acknowledged once the handler completes.
+pub struct EventSubscriber {
+    receiver: Arc<dyn Subscribe>,
+    options: SubscribeOptions,
+    json_upcasters: Option<Arc<JsonUpcasters>>,
+    prost_upcasters: Option<Arc<ProstUpcasters>>,
+}
+
+/// Delay before an event of a newer schema version than its data type is redelivered
+pub const UNSUPPORTED_VERSION_REDELIVERY_DELAY: Duration = Duration::from_secs(30);
+
+impl EventSubscriber {
+    /// Subscribes through the given receiver, e.g. a [JetStreamReceiver](crate::server::receiver::JetStreamReceiver)
+    /// to receive the events stored while the service was offline
+    pub fn new(receiver: Arc<dyn Subscribe>) -> Self {
+        Self {
+            receiver,
+            options: SubscribeOptions::new(),
+            json_upcasters: None,
+            prost_upcasters: None,
+        }
+    }
+
+    /// Options of the subscriptions, e.g. a queue group to share the events between the
+    /// instances of the service, or the concurrency and ordering of the handlers.
+    /// Sequential by default.
+    pub fn subscribe_options(mut self, options: SubscribeOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Upcasts the JSON data of events published with an older schema version
+    pub fn json_upcasters(mut self, upcasters: JsonUpcasters) -> Self {
+        self.json_upcasters = Some(Arc::new(upcasters));
+        self
+    }
+
+    /// Upcasts the protobuf data of events published with an older schema version
+    pub fn prost_upcasters(mut self, upcasters: ProstUpcasters) -> Self {
+        self.prost_upcasters = Some(Arc::new(upcasters));
+        self
+    }
+
+    /// Subscribes to `chat.<domain>.event.<event_type>`, with the event data in JSON.
+    /// `event_type` may be a wildcard (`*` or `>`) to receive all events of the domain.
+    pub async fn subscribe_json<T, H, Fut>(
+        &self,
+        domain: &str,
+        event_type: &str,
+        handler: H,
+    ) -> Result<SubscriptionHandle, NatsTransportError>
+    where
+        T: DeserializeOwned + VersionedEvent + Send + 'static,
+        H: Fn(EventEnvelope<T>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let subject = SubjectPattern::new()
+            .domain(domain)
+            .kind(SubjectKind::Event)
+            .name(event_type)
+            .build()?;
+        let upcasters = self.json_upcasters.clone();
+        let decode = move |message| decode_json::<T>(message, upcasters.as_deref());
+
+        self.receiver
+            .subscribe_with_outcome(
+                subject,
+                self.options.clone(),
+                event_handler(decode, handler),
+            )
+            .await
+    }
+
+    /// Subscribes to `chat.<domain>.event.<event_type>`, with the event data in protobuf.
+    /// `event_type` may be a wildcard (`*` or `>`) to receive all events of the domain.
+    pub async fn subscribe_prost<T, H, Fut>(
+        &self,
+        domain: &str,
+        event_type: &str,
+        handler: H,
+    ) -> Result<SubscriptionHandle, NatsTransportError>
+    where
+        T: prost::Message + Default + VersionedEvent + Send + 'static,
+        H: Fn(EventEnvelope<T>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let subject = SubjectPattern::new()
+            .domain(domain)
+            .kind(SubjectKind::Event)
+            .name(event_type)
+            .build()?;
+        let upcasters = self.prost_upcasters.clone();
+        let decode = move |message| decode_prost::<T>(message, upcasters.as_deref());
+
+        self.receiver
+            .subscribe_with_outcome(
+                subject,
+                self.options.clone(),
+                event_handler(decode, handler),
+            )
+            .await
+    }
+}
+
+/// Decodes each message and passes the event to the handler, see [EventSubscriber] for how
+/// the messages are acknowledged
+pub(crate) fn event_handler<T, D, H, Fut>(decode: D, handler: H) -> OutcomeHandler
+where
+    T: Send + 'static,
+    D: Fn(Message) -> Result<EventEnvelope<T>, NatsTransportError> + Send + Sync + 'static,
+    H: Fn(EventEnvelope<T>) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    outcome_handler(move |message: Message| {
+        let subject = message.subject.to_string();
+
+        match decode(message) {
+            Ok(event) => handler(event).map(|_| AckOutcome::Ack).boxed(),
+            Err(err) => {
+                tracing::warn!(subject = %subject, error = %err, "failed to decode event");
+                futures::future::ready(decode_failure_outcome(&err)).boxed()
+            }
+        }
+    })
+}
+
+/// Events of a newer schema version may be decoded by an updated instance of the service,
+/// other decoding failures (including missing upcasters) are permanent
+fn decode_failure_outcome(err: &NatsTransportError) -> AckOutcome {
+    match err {
+        NatsTransportError::UnsupportedSchemaVersion { .. } => {
+            AckOutcome::Nak(Some(UNSUPPORTED_VERSION_REDELIVERY_DELAY))
+        }
+        _ => AckOutcome::Term,
+    }
+}
+
+/// Decodes an event message with its data in JSON, upcast to the current schema version
+pub(crate) fn decode_json<T>(
+    message: Message,
+    upcasters: Option<&JsonUpcasters>,
+) -> Result<EventEnvelope<T>, NatsTransportError>
+where
+    T: DeserializeOwned + VersionedEvent,
+{
+    let (envelope, data) = decode_metadata(message, JSON_CONTENT_TYPE)?;
+
+    // without upcasters, events of a newer schema version are still rejected
+    let no_upcasters = JsonUpcasters::new();
+    let upcasters = upcasters.unwrap_or(&no_upcasters);
+    let data = upcasters.upcast_payload(&envelope, T::SCHEMA_VERSION, data)?;
+
+    decode_data(envelope, data, NatsJson::<T>::default())
+}
+
+/// Decodes an event message with its data in protobuf, upcast to the current schema version
+pub(crate) fn decode_prost<T>(
+    message: Message,
+    upcasters: Option<&ProstUpcasters>,
+) -> Result<EventEnvelope<T>, NatsTransportError>
+where
+    T: prost::Message + Default + VersionedEvent,
+{
+    let (envelope, data) = decode_metadata(message, PROTOBUF_CONTENT_TYPE)?;
+
+    let no_upcasters = ProstUpcasters::new();
+    let upcasters = upcasters.unwrap_or(&no_upcasters);
+    let data = upcasters.upcast_payload(&envelope, T::SCHEMA_VERSION, data)?;
+
+    decode_data(envelope, data, NatsMessageSerde::<T>::default())
+}
+
+/// Decodes the metadata of an event published with the `x-event-*` headers, or as a CloudEvent
+/// in either mode, along with its still encoded data. The data of a CloudEvent must be in the
+/// `content_type` of the data codec.
+fn decode_metadata(
+    message: Message,
+    content_type: &str,
+) -> Result<(EventEnvelope<()>, Bytes), NatsTransportError> {
+    let event = match cloud_event_mode(message.headers.as_ref()) {
+        Some(CloudEventMode::Structured) => decode_structured(message.payload, content_type)?,
+        Some(CloudEventMode::Binary) => {
+            decode_binary(&headers(message.headers)?, message.payload, content_type)?
+        }
+        None => {
+            let envelope = EventEnvelope::from_headers(&headers(message.headers)?, ())?;
+            return Ok((envelope, message.payload));
+        }
+    };
+
+    Ok((event.envelope, event.data))
+}
+
+fn decode_data<T, S>(
+    envelope: EventEnvelope<()>,
+    data: Bytes,
+    serde: S,
+) -> Result<EventEnvelope<T>, NatsTransportError>
+where
+    S: Deserializer<T>,
+    S::Error: std::error::Error + Send + Sync + 'static,
+{
+    let data = serde
+        .deserialize(data)
+        .map_err(|err| NatsTransportError::DeserializeEvent(Box::new(err)))?;
+
+    Ok(envelope.map(|_| data))
+}
+
+fn headers(headers: Option<HeaderMap>) -> Result<HeaderMap, NatsTransportError> {
+    headers.ok_or_else(|| NatsTransportError::InvalidEventMetadata("missing headers".to_string()))
+}
+
+#[cfg(test)]
+#[path = "./event_subscriber_tests.rs"]
+mod event_subscriber_tests;
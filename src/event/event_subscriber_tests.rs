@@ -0,0 +1,259 @@
+#[cfg(test)]
+mod event_subscriber_tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    };
+
+    use async_nats::Message;
+    use async_trait::async_trait;
+    use bytes::Bytes;
+    use prost::Message as _;
+    use serde::{Deserialize, Serialize};
+
+    use crate::{
+        event::{
+            event_subscriber::{decode_json, decode_prost, event_handler},
+            CloudEventOptions, EventEnvelope, EventSubscriber, JsonUpcasters, VersionedEvent,
+            JSON_CONTENT_TYPE, UNSUPPORTED_VERSION_REDELIVERY_DELAY,
+        },
+        proto_test as proto,
+        server::{
+            receiver::{
+                AckOutcome, MessageHandler, Subscribe, SubscribeOptions, SubscriptionHandle,
+            },
+            NatsTransportError,
+        },
+    };
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct ChatGroupCreated {
+        title: String,
+    }
+
+    impl VersionedEvent for ChatGroupCreated {
+        const SCHEMA_VERSION: u32 = 2;
+    }
+
+    impl VersionedEvent for proto::UserData {
+        const SCHEMA_VERSION: u32 = 1;
+    }
+
+    /// Records the options of the subscriptions, without delivering messages
+    #[derive(Default)]
+    struct RecordingReceiver {
+        options: Mutex<Vec<SubscribeOptions>>,
+    }
+
+    #[async_trait]
+    impl Subscribe for RecordingReceiver {
+        async fn subscribe_with_options(
+            &self,
+            subject: String,
+            options: SubscribeOptions,
+            _handler: MessageHandler,
+        ) -> Result<SubscriptionHandle, NatsTransportError> {
+            self.options.lock().unwrap().push(options);
+            Ok(SubscriptionHandle::from_task(
+                subject,
+                tokio::spawn(async { Ok(()) }),
+            ))
+        }
+    }
+
+    fn message(event: &EventEnvelope<()>, payload: Bytes) -> Message {
+        Message {
+            subject: "chat.chatgroup.event.created".into(),
+            reply: None,
+            payload,
+            headers: Some(event.headers()),
+            status: None,
+            description: None,
+            length: 0,
+        }
+    }
+
+    #[test]
+    fn test_decode_json() {
+        let event = EventEnvelope::new("created", "chatgroup-123", 1, ());
+        let payload = serde_json::to_vec(&ChatGroupCreated {
+            title: "Hello".to_string(),
+        })
+        .unwrap();
+
+        let decoded =
+            decode_json::<ChatGroupCreated>(message(&event, payload.into()), None).unwrap();
+
+        assert_eq!(decoded.event_id, event.event_id);
+        assert_eq!(decoded.aggregate_version, 1);
+        assert_eq!(decoded.data.title, "Hello".to_string());
+    }
+
+    #[test]
+    fn test_decode_prost() {
+        let event = EventEnvelope::new("created", "user-1234", 3, ());
+        let payload = proto::UserData {
+            id: "1234".into(),
+            attr: "test".into(),
+            credit: 23.23,
+        }
+        .encode_to_vec();
+
+        let decoded =
+            decode_prost::<proto::UserData>(message(&event, payload.into()), None).unwrap();
+
+        assert_eq!(decoded.aggregate_id, "user-1234".to_string());
+        assert_eq!(decoded.data.id, "1234".to_string());
+    }
+
+    #[test]
+    fn test_decode_binary_cloud_event() {
+        let event = EventEnvelope::new("created", "chatgroup-123", 2, ());
+        let headers = CloudEventOptions::binary("/runtiva/chat").binary_headers(
+            "chat.chatgroup.event.created",
+            &event,
+            JSON_CONTENT_TYPE,
+        );
+        let mut message = message(&event, Bytes::from_static(b"{\"title\":\"Hello\"}"));
+        message.headers = Some(headers);
+
+        let decoded = decode_json::<ChatGroupCreated>(message, None).unwrap();
+
+        assert_eq!(decoded.event_id, event.event_id);
+        assert_eq!(decoded.event_type, "created".to_string());
+        assert_eq!(decoded.data.title, "Hello".to_string());
+    }
+
+    #[test]
+    fn test_decode_upcast_json() {
+        let upcasters = JsonUpcasters::new().upcaster("created", 1, |mut data| {
+            data["title"] = data["name"].take();
+            Ok(data)
+        });
+        let event = EventEnvelope::new("created", "chatgroup-123", 1, ());
+
+        let decoded = decode_json::<ChatGroupCreated>(
+            message(&event, Bytes::from_static(b"{\"name\":\"Hello\"}")),
+            Some(&upcasters),
+        )
+        .unwrap();
+
+        // the envelope keeps the version the event was published with
+        assert_eq!(decoded.schema_version, 1);
+        assert_eq!(decoded.data.title, "Hello".to_string());
+
+        let event = EventEnvelope::versioned("created", "chatgroup-123", 2, decoded.data);
+        let payload = serde_json::to_vec(&event.data).unwrap();
+        let decoded = decode_json::<ChatGroupCreated>(
+            message(&event.map(|_| ()), payload.into()),
+            Some(&upcasters),
+        )
+        .unwrap();
+
+        assert_eq!(decoded.schema_version, 2);
+        assert_eq!(decoded.data.title, "Hello".to_string());
+    }
+
+    #[test]
+    fn test_decode_newer_schema_version_without_upcasters() {
+        let event = EventEnvelope::new("created", "chatgroup-123", 1, ()).with_schema_version(99);
+
+        assert!(matches!(
+            decode_json::<ChatGroupCreated>(
+                message(&event, Bytes::from_static(b"{\"title\":\"Hello\"}")),
+                None
+            ),
+            Err(NatsTransportError::UnsupportedSchemaVersion {
+                version: 99,
+                current_version: 2,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_decode_without_metadata() {
+        let event = EventEnvelope::new("created", "chatgroup-123", 1, ());
+        let mut message = message(&event, Bytes::from_static(b"{\"title\":\"Hello\"}"));
+        message.headers = None;
+
+        assert!(matches!(
+            decode_json::<ChatGroupCreated>(message, None),
+            Err(NatsTransportError::InvalidEventMetadata(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_options() -> Result<(), NatsTransportError> {
+        let receiver = Arc::new(RecordingReceiver::default());
+        let options = SubscribeOptions::new()
+            .queue_group("chat-persist")
+            .concurrency(8);
+        let subscriber = EventSubscriber::new(receiver.clone()).subscribe_options(options.clone());
+
+        subscriber
+            .subscribe_json(
+                "chatgroup",
+                "created",
+                |_: EventEnvelope<ChatGroupCreated>| async {},
+            )
+            .await?
+            .join()
+            .await?;
+        subscriber
+            .subscribe_prost("user", "*", |_: EventEnvelope<proto::UserData>| async {})
+            .await?
+            .join()
+            .await?;
+
+        assert_eq!(
+            *receiver.options.lock().unwrap(),
+            [options.clone(), options]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_event_handler_outcomes() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let handler = event_handler(
+            |message| decode_json::<ChatGroupCreated>(message, None),
+            move |_event: EventEnvelope<ChatGroupCreated>| {
+                let counter = counter.clone();
+                async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                }
+            },
+        );
+
+        let event = EventEnvelope::new("created", "chatgroup-123", 1, ());
+        let decodable = message(&event, Bytes::from_static(b"{\"title\":\"Hello\"}"));
+        assert_eq!(handler(decodable).await, AckOutcome::Ack);
+
+        // undecodable events are terminated without reaching the handler
+        let undecodable = message(&event, Bytes::from_static(b"{\"name\":1}"));
+        assert_eq!(handler(undecodable).await, AckOutcome::Term);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_event_handler_redelivers_newer_schema_versions() {
+        let handler = event_handler(
+            |_message| -> Result<EventEnvelope<()>, NatsTransportError> {
+                Err(NatsTransportError::UnsupportedSchemaVersion {
+                    event_type: "created".to_string(),
+                    version: 3,
+                    current_version: 2,
+                })
+            },
+            |_event| async {},
+        );
+
+        let event = EventEnvelope::new("created", "chatgroup-123", 1, ());
+        assert_eq!(
+            handler(message(&event, Bytes::new())).await,
+            AckOutcome::Nak(Some(UNSUPPORTED_VERSION_REDELIVERY_DELAY))
+        );
+    }
+}
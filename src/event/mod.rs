@@ -0,0 +1,21 @@
+mod event_envelope;
+pub use event_envelope::{
+    EventEnvelope, VersionedEvent, AGGREGATE_ID_HEADER, AGGREGATE_VERSION_HEADER,
+    CAUSATION_ID_HEADER, CORRELATION_ID_HEADER, EVENT_ID_HEADER, EVENT_TYPE_HEADER,
+    INITIAL_SCHEMA_VERSION, OCCURRED_AT_HEADER, SCHEMA_VERSION_HEADER,
+};
+
+mod event_publisher;
+pub use event_publisher::EventPublisher;
+
+mod event_subscriber;
+pub use event_subscriber::{EventSubscriber, UNSUPPORTED_VERSION_REDELIVERY_DELAY};
+
+mod cloud_event;
+pub use cloud_event::{
+    CloudEventMode, CloudEventOptions, CLOUD_EVENTS_JSON_CONTENT_TYPE, CLOUD_EVENTS_SPEC_VERSION,
+    CONTENT_TYPE_HEADER, JSON_CONTENT_TYPE, PROTOBUF_CONTENT_TYPE,
+};
+
+mod upcaster;
+pub use upcaster::{JsonUpcasters, ProstUpcasters, Upcaster, UpcasterRegistry};
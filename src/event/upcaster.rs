@@ -0,0 +1,165 @@
+use std::collections::{BTreeMap, HashMap};
+
+use bytes::Bytes;
+
+use crate::server::NatsTransportError;
+
+use super::EventEnvelope;
+
+/// Transforms the data of an event from one schema version into the next one
+pub type Upcaster<P> =
+    Box<dyn Fn(P) -> Result<P, Box<dyn std::error::Error + Send + Sync>> + Send + Sync>;
+
+/// Upcasters of JSON event data, working on the untyped [serde_json::Value]
+pub type JsonUpcasters = UpcasterRegistry<serde_json::Value>;
+
+/// Upcasters of protobuf event data, working on the encoded message bytes
+pub type ProstUpcasters = UpcasterRegistry<Bytes>;
+
+/// Registry of the upcasters bringing old events up to the current schema version of their
+/// data type, the [VersionedEvent::SCHEMA_VERSION](super::VersionedEvent::SCHEMA_VERSION),
+/// before they are decoded into it.
+///
+/// An event of version `n` is passed through the upcasters registered for versions
+/// `n`, `n + 1`, ... up to the current version, and is rejected with
+/// [NatsTransportError::MissingUpcaster] when one of them is not registered. Older events of
+/// types without upcasters are decoded as is. Events of a version newer than the current one are
+/// rejected with [NatsTransportError::UnsupportedSchemaVersion], whether their type has
+/// upcasters or not.
+///
+/// ```
+/// use nats_transport::event::JsonUpcasters;
+///
+/// // v1 had a `name`, renamed to `title` in v2
+/// let upcasters = JsonUpcasters::new().upcaster("created", 1, |mut data| {
+///     let name = data["name"].take();
+///     data["title"] = name;
+///     Ok(data)
+/// });
+/// ```
+pub struct UpcasterRegistry<P> {
+    event_types: HashMap<String, BTreeMap<u32, Upcaster<P>>>,
+}
+
+impl<P> Default for UpcasterRegistry<P> {
+    fn default() -> Self {
+        Self {
+            event_types: HashMap::new(),
+        }
+    }
+}
+
+impl<P> UpcasterRegistry<P> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the upcaster transforming the data of `from_version` into `from_version + 1`
+    pub fn upcaster<F>(
+        mut self,
+        event_type: impl Into<String>,
+        from_version: u32,
+        upcaster: F,
+    ) -> Self
+    where
+        F: Fn(P) -> Result<P, Box<dyn std::error::Error + Send + Sync>> + Send + Sync + 'static,
+    {
+        self.event_types
+            .entry(event_type.into())
+            .or_default()
+            .insert(from_version, Box::new(upcaster));
+        self
+    }
+
+    /// Returns false for events already at the current version, or without upcasters
+    fn needs_upcast<T>(&self, event: &EventEnvelope<T>, current_version: u32) -> bool {
+        event.schema_version != current_version && self.event_types.contains_key(&event.event_type)
+    }
+
+    /// Brings the event data up to the current schema version of its data type
+    pub fn upcast<T>(
+        &self,
+        event: &EventEnvelope<T>,
+        current_version: u32,
+        data: P,
+    ) -> Result<P, NatsTransportError> {
+        supported(event, current_version)?;
+
+        let upcasters = match self.event_types.get(&event.event_type) {
+            Some(upcasters) => upcasters,
+            None => return Ok(data),
+        };
+
+        (event.schema_version..current_version).try_fold(data, |data, version| {
+            let upcaster =
+                upcasters
+                    .get(&version)
+                    .ok_or_else(|| NatsTransportError::MissingUpcaster {
+                        event_type: event.event_type.clone(),
+                        version,
+                    })?;
+
+            upcaster(data).map_err(|source| NatsTransportError::UpcastEvent {
+                event_type: event.event_type.clone(),
+                version,
+                source,
+            })
+        })
+    }
+}
+
+impl UpcasterRegistry<serde_json::Value> {
+    /// Upcasts JSON encoded event data, so it can be decoded with [NatsJson](crate::server::serde::NatsJson)
+    pub(crate) fn upcast_payload<T>(
+        &self,
+        event: &EventEnvelope<T>,
+        current_version: u32,
+        payload: Bytes,
+    ) -> Result<Bytes, NatsTransportError> {
+        supported(event, current_version)?;
+        if !self.needs_upcast(event, current_version) {
+            return Ok(payload);
+        }
+
+        let upcast_json = |source| NatsTransportError::UpcastJson {
+            event_type: event.event_type.clone(),
+            source,
+        };
+
+        let data = serde_json::from_slice(&payload).map_err(upcast_json)?;
+        let data = self.upcast(event, current_version, data)?;
+
+        serde_json::to_vec(&data)
+            .map(Bytes::from)
+            .map_err(upcast_json)
+    }
+}
+
+impl UpcasterRegistry<Bytes> {
+    /// Upcasts protobuf encoded event data, so it can be decoded with [NatsMessageSerde](crate::server::serde::NatsMessageSerde)
+    pub(crate) fn upcast_payload<T>(
+        &self,
+        event: &EventEnvelope<T>,
+        current_version: u32,
+        payload: Bytes,
+    ) -> Result<Bytes, NatsTransportError> {
+        self.upcast(event, current_version, payload)
+    }
+}
+
+/// Rejects events of a newer schema version than their data type
+fn supported<T>(event: &EventEnvelope<T>, current_version: u32) -> Result<(), NatsTransportError> {
+    if event.schema_version > current_version {
+        return Err(NatsTransportError::UnsupportedSchemaVersion {
+            event_type: event.event_type.clone(),
+            version: event.schema_version,
+            current_version,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "./upcaster_tests.rs"]
+mod upcaster_tests;
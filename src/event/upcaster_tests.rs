@@ -0,0 +1,128 @@
+#[cfg(test)]
+mod upcaster_tests {
+    use bytes::Bytes;
+    use serde_json::json;
+
+    use crate::{
+        event::{EventEnvelope, JsonUpcasters, ProstUpcasters},
+        server::NatsTransportError,
+    };
+
+    fn upcasters() -> JsonUpcasters {
+        JsonUpcasters::new()
+            // v1 had a `name`, renamed to `title` in v2
+            .upcaster("created", 1, |mut data| {
+                data["title"] = data["name"].take();
+                Ok(data)
+            })
+            // v3 added the `private` flag
+            .upcaster("created", 2, |mut data| {
+                data["private"] = json!(false);
+                Ok(data)
+            })
+    }
+
+    fn event(schema_version: u32) -> EventEnvelope<()> {
+        EventEnvelope::new("created", "chatgroup-123", 1, ()).with_schema_version(schema_version)
+    }
+
+    #[test]
+    fn test_upcast_through_all_versions() {
+        let data = upcasters()
+            .upcast(&event(1), 3, json!({ "name": "Hello" }))
+            .unwrap();
+
+        assert_eq!(data["title"], "Hello");
+        assert_eq!(data["private"], false);
+    }
+
+    #[test]
+    fn test_current_version_is_untouched() {
+        let data = json!({ "title": "Hello", "private": true });
+
+        assert_eq!(
+            upcasters().upcast(&event(3), 3, data.clone()).unwrap(),
+            data
+        );
+    }
+
+    #[test]
+    fn test_unknown_event_type_is_untouched() {
+        let event = EventEnvelope::new("renamed", "chatgroup-123", 2, ()).with_schema_version(7);
+        let data = json!({ "title": "Hi" });
+
+        assert_eq!(upcasters().upcast(&event, 7, data.clone()).unwrap(), data);
+    }
+
+    #[test]
+    fn test_future_version_of_unknown_event_type_is_rejected() {
+        let event = EventEnvelope::new("renamed", "chatgroup-123", 2, ()).with_schema_version(99);
+
+        assert!(matches!(
+            upcasters().upcast(&event, 1, json!({})),
+            Err(NatsTransportError::UnsupportedSchemaVersion {
+                version: 99,
+                current_version: 1,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_invalid_json_payload() {
+        assert!(matches!(
+            upcasters().upcast_payload(&event(1), 3, Bytes::from_static(b"{")),
+            Err(NatsTransportError::UpcastJson { .. })
+        ));
+    }
+
+    #[test]
+    fn test_future_version_is_rejected() {
+        let result = upcasters().upcast(&event(4), 3, json!({}));
+
+        assert!(matches!(
+            result,
+            Err(NatsTransportError::UnsupportedSchemaVersion {
+                version: 4,
+                current_version: 3,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_missing_upcaster_is_rejected() {
+        let upcasters = JsonUpcasters::new().upcaster("created", 2, Ok);
+
+        assert!(matches!(
+            upcasters.upcast(&event(1), 3, json!({})),
+            Err(NatsTransportError::MissingUpcaster { version: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_failed_upcast() {
+        let upcasters = ProstUpcasters::new().upcaster("created", 1, |data: Bytes| {
+            if data.is_empty() {
+                return Err("empty v1 payload".into());
+            }
+            Ok(data)
+        });
+
+        assert!(matches!(
+            upcasters.upcast(&event(1), 2, Bytes::new()),
+            Err(NatsTransportError::UpcastEvent { version: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_upcast_json_payload() {
+        let payload = Bytes::from_static(b"{\"name\":\"Hello\"}");
+
+        let payload = upcasters().upcast_payload(&event(2), 3, payload).unwrap();
+
+        // v2 payloads only go through the v2 upcaster
+        let data: serde_json::Value = serde_json::from_slice(&payload).unwrap();
+        assert_eq!(data, json!({ "name": "Hello", "private": false }));
+    }
+}
@@ -2,6 +2,9 @@
 #![deny(clippy::all)]
 // #![warn(clippy::pedantic)]
 
+// lets the code generated by nats-transport-derive refer to `::nats_transport` in this crate too
+extern crate self as nats_transport;
+
 extern crate num;
 #[macro_use]
 extern crate num_derive;
@@ -30,7 +33,9 @@ pub mod server;
 pub mod error;
 
 mod subject;
+#[allow(deprecated)]
 pub use subject::SubjectName;
+pub use subject::{Subject, SubjectKind, SubjectPattern, SUBJECT_PREFIX};
 
 #[allow(unused_qualifications)]
 #[allow(clippy::all)]
@@ -84,7 +84,7 @@ impl TryFromNatsRequest<proto_nats::NatsChatGroupCreateRequest> for CreateChatGr
         let id = 1000i64;
         let public_id = "random_val".to_string();
 
-        let headers: RequestHeaders = value.headers.into();
+        let headers: RequestHeaders = value.headers.try_into()?;
 
         let value = value.data.unwrap();
 
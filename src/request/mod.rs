@@ -5,7 +5,11 @@
 ///
 /// ```rust
 mod nats_request;
-pub use nats_request::{NatsEnvelope, RequestHeaders};
+pub(crate) use nats_request::deadline_header;
+pub use nats_request::{
+    InvalidHeader, NatsEnvelope, RequestHeaders, TryFromNatsRequest, DEADLINE_HEADER,
+    REQUESTOR_HEADER,
+};
 
 mod converter;
 pub use converter::Converter;
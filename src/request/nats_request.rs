@@ -1,3 +1,9 @@
+use std::{
+    collections::HashSet,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use async_nats::HeaderMap;
 use chat_proto::runtiva::nats::v1 as proto_nats;
 use tonic::metadata::{AsciiMetadataKey, KeyAndValueRef, MetadataMap};
 
@@ -20,10 +26,65 @@ impl Default for RequestHeaders {
     }
 }
 
+/// Header carrying the id of the user on whose behalf the request is made
+pub const REQUESTOR_HEADER: &str = "x-requestor";
+
+/// Header carrying the absolute deadline of the request, in milliseconds since the unix epoch.
+/// An absolute deadline survives queueing and forwarding, but assumes synchronized clocks.
+pub const DEADLINE_HEADER: &str = "x-deadline";
+
 impl RequestHeaders {
     pub fn new() -> Self {
         Self(MetadataMap::new())
     }
+
+    /// Returns the requestor id from the [REQUESTOR_HEADER] header, if present and valid
+    pub fn requestor(&self) -> Option<i64> {
+        self.0
+            .get(REQUESTOR_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+    }
+
+    /// Returns the deadline from the [DEADLINE_HEADER] header, if present and valid
+    pub fn deadline(&self) -> Option<SystemTime> {
+        self.0
+            .get(DEADLINE_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+            .and_then(|millis| UNIX_EPOCH.checked_add(Duration::from_millis(millis)))
+    }
+
+    /// Returns the time left until the deadline, zero once it has passed.
+    /// None when the request has no deadline.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline().map(|deadline| {
+            deadline
+                .duration_since(SystemTime::now())
+                .unwrap_or(Duration::ZERO)
+        })
+    }
+
+    /// Adds the headers of `other` whose keys are not already present, e.g. to combine
+    /// the headers embedded in a protobuf request with the native NATS message headers
+    pub fn merge(&mut self, other: RequestHeaders) {
+        let existing: HashSet<String> = self
+            .0
+            .iter()
+            .filter_map(|key_and_value| match key_and_value {
+                KeyAndValueRef::Ascii(key, _) => Some(key.to_string()),
+                KeyAndValueRef::Binary(..) => None,
+            })
+            .collect();
+
+        for key_and_value in other.0.iter() {
+            if let KeyAndValueRef::Ascii(key, value) = key_and_value {
+                if !existing.contains(key.as_str()) {
+                    self.0.append(key.clone(), value.clone());
+                }
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -36,36 +97,65 @@ impl<T> NatsEnvelope<T> {
     pub fn new(headers: RequestHeaders, data: T) -> Self {
         Self { headers, data }
     }
+
+    /// Deadline of the request, see [RequestHeaders::deadline]
+    pub fn deadline(&self) -> Option<SystemTime> {
+        self.headers.deadline()
+    }
+
+    /// Budget left to handle the request, see [RequestHeaders::remaining].
+    /// Pass it as the timeout of the requests made while handling this one to propagate the deadline.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.headers.remaining()
+    }
+}
+
+/// Value of the [DEADLINE_HEADER] header for a request timing out after `timeout`.
+/// None when the deadline is too far away to be represented, e.g. for `Duration::MAX`,
+/// in which case the request is sent without a deadline.
+pub(crate) fn deadline_header(timeout: Duration) -> Option<String> {
+    let deadline = SystemTime::now().checked_add(timeout)?;
+    let millis = deadline
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_millis();
+
+    u64::try_from(millis).ok().map(|millis| millis.to_string())
 }
 
 // ******************* Chat Proto conversions ******************
 
+/// Error returned for a header embedded in a protobuf request that is not valid gRPC metadata
+#[derive(Debug, thiserror::Error)]
+pub enum InvalidHeader {
+    #[error("invalid header name '{0}'")]
+    Name(String),
+
+    #[error("invalid value of header '{0}'")]
+    Value(String),
+}
+
 // Converter for proto_nats::MetadataMap to tonic::metadata::MetadataMap
 // This is used in the TryFromNatsRequest<T> implementations to extract out the headers
-impl From<Vec<proto_nats::MetadataMap>> for RequestHeaders {
-    fn from(values: Vec<proto_nats::MetadataMap>) -> Self {
+// The headers come from the network, so an invalid name or value is an error rather than a panic
+impl TryFrom<Vec<proto_nats::MetadataMap>> for RequestHeaders {
+    type Error = InvalidHeader;
+
+    fn try_from(values: Vec<proto_nats::MetadataMap>) -> Result<Self, Self::Error> {
         let mut map = MetadataMap::new();
         for header_val in values.iter() {
-            let key = header_val.key.as_bytes();
-            let values = header_val.value.iter();
-            let mut ctr = 0;
-            for value in values {
-                ctr += 1;
-                if ctr == 1 {
-                    map.insert(
-                        AsciiMetadataKey::from_bytes(key).unwrap(),
-                        value.parse().unwrap(),
-                    );
-                } else {
-                    map.append(
-                        AsciiMetadataKey::from_bytes(key).unwrap(),
-                        value.parse().unwrap(),
-                    );
-                }
+            let key = AsciiMetadataKey::from_bytes(header_val.key.as_bytes())
+                .map_err(|_| InvalidHeader::Name(header_val.key.clone()))?;
+
+            for value in header_val.value.iter() {
+                let value = value
+                    .parse()
+                    .map_err(|_| InvalidHeader::Value(header_val.key.clone()))?;
+                map.append(key.clone(), value);
             }
         }
 
-        RequestHeaders(map)
+        Ok(RequestHeaders(map))
     }
 }
 
@@ -91,9 +181,11 @@ impl From<RequestHeaders> for Vec<proto_nats::MetadataMap> {
 
                 // only add the key once...
                 if !headers.iter().any(|h| h.key == k) {
+                    // values that are not visible ASCII cannot be represented as strings
                     let v: Vec<String> = view
                         .iter()
-                        .map(|v| v.to_str().unwrap().to_string())
+                        .filter_map(|v| v.to_str().ok())
+                        .map(str::to_string)
                         .collect::<Vec<_>>();
 
                     headers.push(proto_nats::MetadataMap {
@@ -108,6 +200,57 @@ impl From<RequestHeaders> for Vec<proto_nats::MetadataMap> {
     }
 }
 
+// ******************* NATS header conversions ******************
+
+// Converter for tonic::metadata::MetadataMap to native NATS message headers
+// This is used to send RequestHeaders with JSON (and protobuf) messages
+// Binary (`-bin`) headers have no NATS representation and are skipped
+impl From<&RequestHeaders> for HeaderMap {
+    fn from(value: &RequestHeaders) -> Self {
+        let mut headers = HeaderMap::new();
+
+        for key_and_value in value.0.iter() {
+            if let KeyAndValueRef::Ascii(key, value) = key_and_value {
+                if let Ok(value) = value.to_str() {
+                    headers.append(key.as_str(), value);
+                }
+            }
+        }
+
+        headers
+    }
+}
+
+impl From<RequestHeaders> for HeaderMap {
+    fn from(value: RequestHeaders) -> Self {
+        HeaderMap::from(&value)
+    }
+}
+
+// Converter for native NATS message headers to tonic::metadata::MetadataMap
+// This is used on the subscribing side to lift the message headers into NatsEnvelope::headers
+// Header names are lowercased; headers that are not valid gRPC metadata are skipped
+impl From<&HeaderMap> for RequestHeaders {
+    fn from(value: &HeaderMap) -> Self {
+        let mut map = MetadataMap::new();
+
+        for (name, values) in value.iter() {
+            let key = name.to_string().to_lowercase();
+            let Ok(key) = AsciiMetadataKey::from_bytes(key.as_bytes()) else {
+                continue;
+            };
+
+            for value in values {
+                if let Ok(value) = value.to_string().parse() {
+                    map.append(key.clone(), value);
+                }
+            }
+        }
+
+        RequestHeaders(map)
+    }
+}
+
 #[cfg(test)]
 #[path = "./nats_request_tests.rs"]
 mod nats_request_tests;
@@ -1,9 +1,13 @@
 #[cfg(test)]
 mod nats_request_tests {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+    use async_nats::HeaderMap;
     use tonic::metadata::{MetadataMap, MetadataValue};
 
-    use crate::request::nats_request::RequestHeaders;
+    use crate::request::nats_request::{
+        deadline_header, InvalidHeader, RequestHeaders, DEADLINE_HEADER,
+    };
     use chat_proto::runtiva::nats::v1 as proto_nats;
 
     #[test]
@@ -17,7 +21,7 @@ mod nats_request_tests {
         let vec_headers = vec![headers];
 
         // Convert to RequestHeaders
-        let converted: RequestHeaders = RequestHeaders::from(vec_headers);
+        let converted = RequestHeaders::try_from(vec_headers).unwrap();
 
         let values = converted.0.get_all("key");
         let mut i = values.iter();
@@ -60,7 +64,7 @@ mod nats_request_tests {
         assert_eq!(converted[0].value[0], "127.0.0.1");
 
         // Convert back to tonic MetadataMap
-        let and_back: RequestHeaders = RequestHeaders::from(converted);
+        let and_back = RequestHeaders::try_from(converted).unwrap();
         assert_eq!(2, and_back.0.len());
         let values = and_back.0.get_all("x-host-ip");
         let mut i = values.iter();
@@ -68,4 +72,115 @@ mod nats_request_tests {
         assert_eq!(*i.next().unwrap(), MetadataValue::from_static("text/html"));
         assert_eq!(None, i.next());
     }
+
+    #[test]
+    fn test_invalid_proto_metadata_map() {
+        let invalid_name = vec![proto_nats::MetadataMap {
+            key: "x requestor".to_string(),
+            value: vec!["42".to_string()],
+        }];
+        assert!(matches!(
+            RequestHeaders::try_from(invalid_name),
+            Err(InvalidHeader::Name(_))
+        ));
+
+        let invalid_value = vec![proto_nats::MetadataMap {
+            key: "x-requestor".to_string(),
+            value: vec!["4\n2".to_string()],
+        }];
+        assert!(matches!(
+            RequestHeaders::try_from(invalid_value),
+            Err(InvalidHeader::Value(_))
+        ));
+    }
+
+    #[test]
+    fn test_to_nats_headers_and_back() {
+        let mut metadata = MetadataMap::new();
+        metadata.insert("x-requestor", MetadataValue::from_static("42"));
+        metadata.append("x-forwarded-for", MetadataValue::from_static("10.0.0.1"));
+        metadata.append("x-forwarded-for", MetadataValue::from_static("10.0.0.2"));
+
+        let headers = HeaderMap::from(RequestHeaders(metadata));
+        assert_eq!(headers.get("x-requestor").unwrap().to_string(), "42");
+
+        let and_back = RequestHeaders::from(&headers);
+        assert_eq!(and_back.requestor(), Some(42));
+
+        let forwarded: Vec<_> = and_back.0.get_all("x-forwarded-for").iter().collect();
+        assert_eq!(forwarded.len(), 2);
+    }
+
+    #[test]
+    fn test_from_nats_headers_lowercases_names() {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Requestor", "7");
+
+        let converted = RequestHeaders::from(&headers);
+
+        assert_eq!(converted.requestor(), Some(7));
+    }
+
+    #[test]
+    fn test_merge_keeps_existing_headers() {
+        let mut metadata = MetadataMap::new();
+        metadata.insert("x-requestor", MetadataValue::from_static("42"));
+        let mut headers = RequestHeaders(metadata);
+
+        let mut other = MetadataMap::new();
+        other.insert("x-requestor", MetadataValue::from_static("7"));
+        other.insert("traceparent", MetadataValue::from_static("00-abc-def-01"));
+        headers.merge(RequestHeaders(other));
+
+        assert_eq!(headers.requestor(), Some(42));
+        assert_eq!(
+            headers.0.get("traceparent").unwrap(),
+            MetadataValue::from_static("00-abc-def-01")
+        );
+    }
+
+    #[test]
+    fn test_deadline_header() {
+        let mut map = MetadataMap::new();
+        map.insert(DEADLINE_HEADER, "1700000000000".parse().unwrap());
+        let headers = RequestHeaders(map);
+
+        assert_eq!(
+            headers.deadline(),
+            Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+        );
+        // the deadline has long passed
+        assert_eq!(headers.remaining(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_unrepresentable_deadline() {
+        assert_eq!(deadline_header(Duration::MAX), None);
+    }
+
+    #[test]
+    fn test_remaining_until_deadline() {
+        let mut map = MetadataMap::new();
+        map.insert(
+            DEADLINE_HEADER,
+            deadline_header(Duration::from_secs(30))
+                .unwrap()
+                .parse()
+                .unwrap(),
+        );
+        let headers = RequestHeaders(map);
+
+        let remaining = headers.remaining().unwrap();
+        assert!(remaining > Duration::from_secs(29) && remaining <= Duration::from_secs(30));
+        assert!(headers.deadline().unwrap() > SystemTime::now());
+    }
+
+    #[test]
+    fn test_no_deadline() {
+        let mut map = MetadataMap::new();
+        map.insert(DEADLINE_HEADER, "tomorrow".parse().unwrap());
+
+        assert!(RequestHeaders::new().remaining().is_none());
+        assert!(RequestHeaders(map).deadline().is_none());
+    }
 }
@@ -1,12 +1,25 @@
 use serde::{Deserialize, Serialize};
 
-use crate::error::{ErrorModel, ErrorReason, ToErrorModel};
+use crate::{
+    error::{ErrorModel, ErrorReason, ToErrorModel},
+    server::{ClientError, NatsTransportError},
+};
+
+/// NATS header set on protobuf replies whose payload is a `proto_nats::ErrorReply`
+/// instead of the expected response message.
+pub const ERROR_REPLY_HEADER: &str = "x-error-reply";
+
+/// NATS header carrying the [typed details](crate::error::ErrorModel::typed_details) of an
+/// error reply, see [ErrorModel::typed_details_header]. Only set when the model has typed details.
+pub const ERROR_DETAILS_HEADER: &str = "x-error-details";
 
 /// `StandardNatsReply` is used for NATs Request/Reply responses with
 /// a standard set of ErrorReasons. Custom reasons can be implemented
 /// by using the `NatsReply` struct directly.
 ///
-/// Examples of creating success and error responses:
+/// Examples of creating success and error responses (the `ToErrorModel` and reason `Display`
+/// impls can also be generated with `#[derive(ToErrorModel)]` and `#[derive(ErrorReason)]`,
+/// see [error](crate::error)):
 /// ``` rust
 ///     use std::{fmt, fmt::Display};
 ///     use serde::{Deserialize, Serialize};
@@ -143,4 +156,17 @@ impl<T, R> NatsResponse<T, R> {
             data: None,
         }
     }
+
+    /// Converts the response into its data, or the [ErrorModel] returned by the remote service
+    pub fn into_result(self) -> Result<T, ClientError<R>> {
+        match (self.error, self.data) {
+            (Some(error), _) => Err(ClientError::Remote(error)),
+            (None, Some(data)) => Ok(data),
+            (None, None) => Err(ClientError::Transport(NatsTransportError::EmptyReply)),
+        }
+    }
 }
+
+#[cfg(test)]
+#[path = "./response_tests.rs"]
+mod response_tests;
@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod response_tests {
+    use crate::{
+        error::{ErrorModel, ErrorReason, Status},
+        response::{NatsResponse, StandardNatsResponse},
+        server::{ClientError, NatsTransportError},
+    };
+
+    #[test]
+    fn test_into_result_data() {
+        let response = StandardNatsResponse::new("chat created".to_string());
+
+        assert_eq!(response.into_result().unwrap(), "chat created".to_string());
+    }
+
+    #[test]
+    fn test_into_result_remote_error() {
+        let json = serde_json::to_vec(&StandardNatsResponse::<String> {
+            error: Some(
+                ErrorModel::new(Status::InvalidArgument, 400, "Unsupported".to_string())
+                    .with_details(ErrorReason::UnsupportedRequest, "runtiva.com".to_string()),
+            ),
+            data: None,
+        })
+        .unwrap();
+
+        // round trip as the requestor would receive it
+        let response: NatsResponse<String, ErrorReason> = serde_json::from_slice(&json).unwrap();
+
+        match response.into_result() {
+            Err(ClientError::Remote(model)) => {
+                assert_eq!(model.status, Status::InvalidArgument);
+                assert_eq!(model.code, 400);
+                assert_eq!(
+                    model.details.first().unwrap().reason,
+                    ErrorReason::UnsupportedRequest
+                );
+            }
+            other => panic!("expected a remote error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_into_result_empty_reply() {
+        let response = StandardNatsResponse::<String> {
+            error: None,
+            data: None,
+        };
+
+        assert!(matches!(
+            response.into_result(),
+            Err(ClientError::Transport(NatsTransportError::EmptyReply))
+        ));
+    }
+}
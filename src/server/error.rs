@@ -1,6 +1,9 @@
 use std::str::Utf8Error;
 
 use async_nats::{ConnectError, PublishError, RequestError};
+use chat_proto::runtiva::nats::v1 as proto_nats;
+
+use crate::error::ErrorModel;
 
 #[derive(Debug, thiserror::Error)]
 pub enum NatsTransportError {
@@ -8,21 +11,138 @@ pub enum NatsTransportError {
     ConvertEvent(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
     #[error("failed to deserialize event from database: {0}")]
     DeserializeEvent(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+    #[error("failed to deserialize reply: {0}")]
+    DeserializeReply(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("invalid event metadata: {0}")]
+    InvalidEventMetadata(String),
+
+    #[error("invalid subject: {0}")]
+    InvalidSubject(String),
+
+    #[error("invalid request header: {0}")]
+    InvalidHeader(#[from] crate::request::InvalidHeader),
+
+    #[error("unsupported schema version {version} of event {event_type}, current version is {current_version}")]
+    UnsupportedSchemaVersion {
+        event_type: String,
+        version: u32,
+        current_version: u32,
+    },
+
+    #[error("no upcaster for schema version {version} of event {event_type}")]
+    MissingUpcaster { event_type: String, version: u32 },
+
+    #[error("failed to upcast the JSON data of event {event_type}: {source}")]
+    UpcastJson {
+        event_type: String,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("failed to upcast event {event_type} from schema version {version}: {source}")]
+    UpcastEvent {
+        event_type: String,
+        version: u32,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
 
     // #[error("Invalid gRPC request ({0}): {1}")]
     // ConvertError(String, String),
+    #[error("failed to serialize message: {0}")]
+    Serialize(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
 
-    // #[error("Serialization error: {0}")]
-    // SerializationError(#[from] serde_json::error::Error),
     #[error("Utf8 error: {0}")]
     Utf8Error(#[from] Utf8Error),
 
+    #[error("NATS configuration error: {0}")]
+    NatsConfigError(String),
+
+    #[error("NATS credentials error: {0}")]
+    NatsCredentialsError(#[source] std::io::Error),
+
     #[error("NATS connection error: {0}")]
     NatsConnectError(#[from] ConnectError),
 
     #[error("NATS publish error: {0}")]
     NatsPublishError(#[from] PublishError),
 
+    #[error("JetStream publish error: {0}")]
+    JetStreamPublishError(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("no JetStream stream stores the subject: {0}")]
+    StreamNotFound(String),
+
+    #[error("JetStream expected last sequence check failed for subject: {0}")]
+    WrongLastSequence(String),
+
+    #[error("JetStream expected last message id check failed for subject: {0}")]
+    WrongLastMessageId(String),
+
+    #[error("timed out waiting for the JetStream publish acknowledgement: {0}")]
+    PublishAckTimeout(String),
+
     #[error("NATS request error: {0}")]
     NatsRequestError(#[from] RequestError),
+
+    #[error("timed out waiting for the reply to the request: {0}")]
+    RequestTimeout(String),
+
+    #[error("NATS subscribe error: {0}")]
+    NatsSubscribeError(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("NATS unsubscribe error: {0}")]
+    NatsUnsubscribeError(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("JetStream stream error: {0}")]
+    NatsStreamError(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("JetStream consumer error: {0}")]
+    NatsConsumerError(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("JetStream consumer does not match its configuration: {0}")]
+    ConsumerMismatch(String),
+
+    #[error("NATS subscription task failed: {0}")]
+    SubscriptionTaskError(#[from] tokio::task::JoinError),
+
+    #[error("timed out draining NATS subscription: {0}")]
+    DrainTimeout(String),
+
+    #[error("NATS flush error: {0}")]
+    NatsFlushError(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("NATS server is shutting down")]
+    ShuttingDown,
+
+    #[error("timed out waiting for NATS subscriptions to shut down")]
+    ShutdownTimeout,
+
+    #[error("failed to listen for termination signals: {0}")]
+    SignalError(#[source] std::io::Error),
+
+    #[error("NATS reply contained neither data nor an error")]
+    EmptyReply,
+}
+
+/// Error returned by the typed request/reply traits ([RequestReplyJson](crate::server::RequestReplyJson),
+/// [RequestReplyProst](crate::server::RequestReplyProst)).
+///
+/// Distinguishes between an error returned by the remote service and a failure
+/// to deliver the request or decode its reply.
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError<R> {
+    /// The remote service replied with an error
+    #[error("remote error ({}): {}", .0.status, .0.message)]
+    Remote(ErrorModel<R>),
+
+    /// The remote service replied with a protobuf error reply that does not convert into an
+    /// [ErrorModel], e.g. with an unknown status
+    #[error("remote error ({}): {}", .0.code, .0.message)]
+    RemoteReply(proto_nats::ErrorReply),
+
+    /// The request could not be sent, or its reply could not be decoded
+    #[error(transparent)]
+    Transport(#[from] NatsTransportError),
 }
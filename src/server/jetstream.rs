@@ -0,0 +1,125 @@
+use async_nats::{
+    jetstream::context::{PublishError, PublishErrorKind},
+    HeaderMap,
+};
+
+use crate::server::NatsTransportError;
+
+/// Header carrying the deduplication id of a JetStream message
+pub const MESSAGE_ID_HEADER: &str = "Nats-Msg-Id";
+/// Header rejecting the publish unless the stream's last sequence matches
+pub const EXPECTED_LAST_SEQUENCE_HEADER: &str = "Nats-Expected-Last-Sequence";
+/// Header rejecting the publish unless the last sequence of the subject matches
+pub const EXPECTED_LAST_SUBJECT_SEQUENCE_HEADER: &str = "Nats-Expected-Last-Subject-Sequence";
+/// Header rejecting the publish unless the stream's last message id matches
+pub const EXPECTED_LAST_MESSAGE_ID_HEADER: &str = "Nats-Expected-Last-Msg-Id";
+/// Header rejecting the publish unless the subject is bound to the given stream
+pub const EXPECTED_STREAM_HEADER: &str = "Nats-Expected-Stream";
+
+/// Options of a JetStream publish, see [JetStreamPublishJson](crate::server::JetStreamPublishJson)
+/// and [JetStreamPublishProst](crate::server::JetStreamPublishProst).
+///
+/// ```
+/// use nats_transport::server::JetStreamPublishOptions;
+///
+/// // deduplicate retries of the same event, and append to the chat group's
+/// // event stream only if no other event was stored since version 41
+/// let options = JetStreamPublishOptions::new()
+///     .message_id("chatgroup-123-42")
+///     .expected_last_subject_sequence(41);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JetStreamPublishOptions {
+    pub(crate) message_id: Option<String>,
+    pub(crate) expected_last_sequence: Option<u64>,
+    pub(crate) expected_last_subject_sequence: Option<u64>,
+    pub(crate) expected_last_message_id: Option<String>,
+    pub(crate) expected_stream: Option<String>,
+}
+
+impl JetStreamPublishOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deduplication id: publishing the same id again within the stream's duplicate
+    /// window is acknowledged as a duplicate instead of being stored twice
+    pub fn message_id(mut self, message_id: impl Into<String>) -> Self {
+        self.message_id = Some(message_id.into());
+        self
+    }
+
+    /// Optimistic concurrency check on the last sequence of the whole stream
+    pub fn expected_last_sequence(mut self, sequence: u64) -> Self {
+        self.expected_last_sequence = Some(sequence);
+        self
+    }
+
+    /// Optimistic concurrency check on the last sequence of the published subject
+    /// (e.g. the event stream of a single aggregate)
+    pub fn expected_last_subject_sequence(mut self, sequence: u64) -> Self {
+        self.expected_last_subject_sequence = Some(sequence);
+        self
+    }
+
+    /// Optimistic concurrency check on the deduplication id of the stream's last message
+    pub fn expected_last_message_id(mut self, message_id: impl Into<String>) -> Self {
+        self.expected_last_message_id = Some(message_id.into());
+        self
+    }
+
+    /// Fails the publish unless the subject is stored in the given stream
+    pub fn expected_stream(mut self, stream: impl Into<String>) -> Self {
+        self.expected_stream = Some(stream.into());
+        self
+    }
+
+    /// Adds the JetStream headers of the options to the message headers
+    pub(crate) fn apply(&self, headers: Option<HeaderMap>) -> Option<HeaderMap> {
+        if *self == Self::default() {
+            return headers;
+        }
+
+        let mut headers = headers.unwrap_or_default();
+
+        if let Some(message_id) = &self.message_id {
+            headers.insert(MESSAGE_ID_HEADER, message_id.as_str());
+        }
+        if let Some(sequence) = self.expected_last_sequence {
+            headers.insert(EXPECTED_LAST_SEQUENCE_HEADER, sequence.to_string().as_str());
+        }
+        if let Some(sequence) = self.expected_last_subject_sequence {
+            headers.insert(
+                EXPECTED_LAST_SUBJECT_SEQUENCE_HEADER,
+                sequence.to_string().as_str(),
+            );
+        }
+        if let Some(message_id) = &self.expected_last_message_id {
+            headers.insert(EXPECTED_LAST_MESSAGE_ID_HEADER, message_id.as_str());
+        }
+        if let Some(stream) = &self.expected_stream {
+            headers.insert(EXPECTED_STREAM_HEADER, stream.as_str());
+        }
+
+        Some(headers)
+    }
+}
+
+/// Maps a JetStream publish failure to the matching [NatsTransportError]
+pub(crate) fn publish_error(subject: &str, err: PublishError) -> NatsTransportError {
+    match err.kind() {
+        PublishErrorKind::StreamNotFound => NatsTransportError::StreamNotFound(subject.to_string()),
+        PublishErrorKind::WrongLastSequence => {
+            NatsTransportError::WrongLastSequence(subject.to_string())
+        }
+        PublishErrorKind::WrongLastMessageId => {
+            NatsTransportError::WrongLastMessageId(subject.to_string())
+        }
+        PublishErrorKind::TimedOut => NatsTransportError::PublishAckTimeout(subject.to_string()),
+        _ => NatsTransportError::JetStreamPublishError(err.into()),
+    }
+}
+
+#[cfg(test)]
+#[path = "./jetstream_tests.rs"]
+mod jetstream_tests;
@@ -0,0 +1,78 @@
+#[cfg(test)]
+mod jetstream_tests {
+    use async_nats::{
+        jetstream::context::{PublishError, PublishErrorKind},
+        HeaderMap,
+    };
+
+    use crate::server::{
+        jetstream::{
+            publish_error, EXPECTED_LAST_MESSAGE_ID_HEADER, EXPECTED_LAST_SEQUENCE_HEADER,
+            EXPECTED_LAST_SUBJECT_SEQUENCE_HEADER, EXPECTED_STREAM_HEADER, MESSAGE_ID_HEADER,
+        },
+        JetStreamPublishOptions, NatsTransportError,
+    };
+
+    #[test]
+    fn test_default_options_keep_headers() {
+        let options = JetStreamPublishOptions::new();
+
+        assert!(options.apply(None).is_none());
+    }
+
+    #[test]
+    fn test_options_add_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-requestor", "42");
+
+        let headers = JetStreamPublishOptions::new()
+            .message_id("chatgroup-123-42")
+            .expected_last_sequence(7)
+            .expected_last_subject_sequence(41)
+            .expected_last_message_id("chatgroup-123-41")
+            .expected_stream("CHAT_EVENTS")
+            .apply(Some(headers))
+            .unwrap();
+
+        let header = |name: &str| headers.get(name).unwrap().to_string();
+
+        assert_eq!(header("x-requestor"), "42");
+        assert_eq!(header(MESSAGE_ID_HEADER), "chatgroup-123-42");
+        assert_eq!(header(EXPECTED_LAST_SEQUENCE_HEADER), "7");
+        assert_eq!(header(EXPECTED_LAST_SUBJECT_SEQUENCE_HEADER), "41");
+        assert_eq!(header(EXPECTED_LAST_MESSAGE_ID_HEADER), "chatgroup-123-41");
+        assert_eq!(header(EXPECTED_STREAM_HEADER), "CHAT_EVENTS");
+    }
+
+    #[test]
+    fn test_publish_error_kinds() {
+        let subject = "chat.chatgroup.event.created";
+
+        assert!(matches!(
+            publish_error(subject, PublishError::from(PublishErrorKind::StreamNotFound)),
+            NatsTransportError::StreamNotFound(s) if s == subject
+        ));
+        assert!(matches!(
+            publish_error(
+                subject,
+                PublishError::from(PublishErrorKind::WrongLastSequence)
+            ),
+            NatsTransportError::WrongLastSequence(_)
+        ));
+        assert!(matches!(
+            publish_error(
+                subject,
+                PublishError::from(PublishErrorKind::WrongLastMessageId)
+            ),
+            NatsTransportError::WrongLastMessageId(_)
+        ));
+        assert!(matches!(
+            publish_error(subject, PublishError::from(PublishErrorKind::TimedOut)),
+            NatsTransportError::PublishAckTimeout(_)
+        ));
+        assert!(matches!(
+            publish_error(subject, PublishError::from(PublishErrorKind::BrokenPipe)),
+            NatsTransportError::JetStreamPublishError(_)
+        ));
+    }
+}
@@ -1,16 +1,38 @@
 mod nats_server;
 pub use nats_server::NatsServer;
 
+mod nats_server_builder;
+pub use nats_server_builder::NatsServerBuilder;
+
+mod nats_config;
+pub use nats_config::{NatsConfig, DEFAULT_NATS_URL};
+
+mod shutdown;
+
+mod jetstream;
+pub use jetstream::{
+    JetStreamPublishOptions, EXPECTED_LAST_MESSAGE_ID_HEADER, EXPECTED_LAST_SEQUENCE_HEADER,
+    EXPECTED_LAST_SUBJECT_SEQUENCE_HEADER, EXPECTED_STREAM_HEADER, MESSAGE_ID_HEADER,
+};
+
+mod provisioning;
+pub use provisioning::{
+    ConsumerSpec, Drift, ProvisioningReport, StreamSpec, SubjectSpec, Topology,
+};
+
 mod nats_context;
 pub use nats_context::NatsContext;
 
 mod server_traits;
-pub use server_traits::{PublishJson, PublishProst, RequestJson, RequestProst};
+pub use server_traits::{
+    JetStreamPublishJson, JetStreamPublishProst, PublishJson, PublishProst, RequestJson,
+    RequestProst, RequestReplyJson, RequestReplyProst,
+};
 
 pub mod receiver;
 
 mod error;
-pub use error::NatsTransportError;
+pub use error::{ClientError, NatsTransportError};
 
 pub mod serde;
 
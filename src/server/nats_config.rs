@@ -0,0 +1,256 @@
+use std::{env, fmt, path::PathBuf, sync::Arc, time::Duration};
+
+use async_nats::ConnectOptions;
+use serde::Deserialize;
+
+use crate::server::NatsTransportError;
+
+pub const DEFAULT_NATS_URL: &str = "nats://localhost:4222";
+
+/// Connection settings used to build the [async_nats::ConnectOptions] for a [NatsServer](crate::server::NatsServer).
+///
+/// The config can be deserialized from a service configuration file or loaded from `NATS_*`
+/// environment variables with [NatsConfig::from_env].
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct NatsConfig {
+    /// Server url, e.g. `nats://localhost:4222` or `tls://nats.runtiva.com:4222`
+    pub url: String,
+
+    /// Connection name reported to the NATS server (shown in `nats server report connections`)
+    pub name: Option<String>,
+
+    /// Path to a `.creds` file containing the user JWT and nkey seed
+    pub credentials_file: Option<PathBuf>,
+
+    /// NKey seed used for nkey authentication, or to sign the nonce when `jwt` is set
+    pub nkey_seed: Option<String>,
+
+    /// User JWT. Requires `nkey_seed` to sign the server nonce.
+    pub jwt: Option<String>,
+
+    pub user: Option<String>,
+    pub password: Option<String>,
+    pub token: Option<String>,
+
+    /// Fail the connection if TLS cannot be established
+    pub tls_required: bool,
+
+    /// Client certificate and key (PEM) for mutual TLS
+    pub tls_client_cert: Option<PathBuf>,
+    pub tls_client_key: Option<PathBuf>,
+
+    /// Custom CA root certificate (PEM)
+    pub tls_root_certificate: Option<PathBuf>,
+
+    pub ping_interval_ms: Option<u64>,
+    pub connection_timeout_ms: Option<u64>,
+    pub request_timeout_ms: Option<u64>,
+
+    /// Custom prefix for request/reply inboxes (defaults to `_INBOX`)
+    pub inbox_prefix: Option<String>,
+}
+
+impl Default for NatsConfig {
+    fn default() -> Self {
+        Self {
+            url: DEFAULT_NATS_URL.to_string(),
+            name: None,
+            credentials_file: None,
+            nkey_seed: None,
+            jwt: None,
+            user: None,
+            password: None,
+            token: None,
+            tls_required: false,
+            tls_client_cert: None,
+            tls_client_key: None,
+            tls_root_certificate: None,
+            ping_interval_ms: None,
+            connection_timeout_ms: None,
+            request_timeout_ms: None,
+            inbox_prefix: None,
+        }
+    }
+}
+
+// Secrets are redacted so the config can be safely logged
+impl fmt::Debug for NatsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let redact = |value: &Option<String>| value.as_ref().map(|_| "***");
+
+        f.debug_struct("NatsConfig")
+            .field("url", &self.url)
+            .field("name", &self.name)
+            .field("credentials_file", &self.credentials_file)
+            .field("nkey_seed", &redact(&self.nkey_seed))
+            .field("jwt", &redact(&self.jwt))
+            .field("user", &self.user)
+            .field("password", &redact(&self.password))
+            .field("token", &redact(&self.token))
+            .field("tls_required", &self.tls_required)
+            .field("tls_client_cert", &self.tls_client_cert)
+            .field("tls_client_key", &self.tls_client_key)
+            .field("tls_root_certificate", &self.tls_root_certificate)
+            .field("ping_interval_ms", &self.ping_interval_ms)
+            .field("connection_timeout_ms", &self.connection_timeout_ms)
+            .field("request_timeout_ms", &self.request_timeout_ms)
+            .field("inbox_prefix", &self.inbox_prefix)
+            .finish()
+    }
+}
+
+impl NatsConfig {
+    /// Loads the config from the process environment:
+    ///
+    /// | Variable                   | Field                  |
+    /// |----------------------------|------------------------|
+    /// | `NATS_URL`                 | `url`                  |
+    /// | `NATS_NAME`                | `name`                 |
+    /// | `NATS_CREDS_FILE`          | `credentials_file`     |
+    /// | `NATS_NKEY_SEED`           | `nkey_seed`            |
+    /// | `NATS_JWT`                 | `jwt`                  |
+    /// | `NATS_USER`                | `user`                 |
+    /// | `NATS_PASSWORD`            | `password`             |
+    /// | `NATS_TOKEN`               | `token`                |
+    /// | `NATS_TLS_REQUIRED`        | `tls_required`         |
+    /// | `NATS_TLS_CERT`            | `tls_client_cert`      |
+    /// | `NATS_TLS_KEY`             | `tls_client_key`       |
+    /// | `NATS_TLS_CA`              | `tls_root_certificate` |
+    /// | `NATS_PING_INTERVAL_MS`    | `ping_interval_ms`     |
+    /// | `NATS_CONNECT_TIMEOUT_MS`  | `connection_timeout_ms`|
+    /// | `NATS_REQUEST_TIMEOUT_MS`  | `request_timeout_ms`   |
+    /// | `NATS_INBOX_PREFIX`        | `inbox_prefix`         |
+    pub fn from_env() -> Result<Self, NatsTransportError> {
+        Self::from_lookup(|key| env::var(key).ok())
+    }
+
+    /// Loads the config using the given variable lookup (see [NatsConfig::from_env] for the variable names)
+    pub fn from_lookup<F>(lookup: F) -> Result<Self, NatsTransportError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let parse_u64 = |key: &str| -> Result<Option<u64>, NatsTransportError> {
+            lookup(key)
+                .map(|value| {
+                    value.parse::<u64>().map_err(|err| {
+                        NatsTransportError::NatsConfigError(format!("{key}={value}: {err}"))
+                    })
+                })
+                .transpose()
+        };
+
+        let tls_required = match lookup("NATS_TLS_REQUIRED") {
+            Some(value) => value.parse::<bool>().map_err(|err| {
+                NatsTransportError::NatsConfigError(format!("NATS_TLS_REQUIRED={value}: {err}"))
+            })?,
+            None => false,
+        };
+
+        Ok(Self {
+            url: lookup("NATS_URL").unwrap_or_else(|| DEFAULT_NATS_URL.to_string()),
+            name: lookup("NATS_NAME"),
+            credentials_file: lookup("NATS_CREDS_FILE").map(PathBuf::from),
+            nkey_seed: lookup("NATS_NKEY_SEED"),
+            jwt: lookup("NATS_JWT"),
+            user: lookup("NATS_USER"),
+            password: lookup("NATS_PASSWORD"),
+            token: lookup("NATS_TOKEN"),
+            tls_required,
+            tls_client_cert: lookup("NATS_TLS_CERT").map(PathBuf::from),
+            tls_client_key: lookup("NATS_TLS_KEY").map(PathBuf::from),
+            tls_root_certificate: lookup("NATS_TLS_CA").map(PathBuf::from),
+            ping_interval_ms: parse_u64("NATS_PING_INTERVAL_MS")?,
+            connection_timeout_ms: parse_u64("NATS_CONNECT_TIMEOUT_MS")?,
+            request_timeout_ms: parse_u64("NATS_REQUEST_TIMEOUT_MS")?,
+            inbox_prefix: lookup("NATS_INBOX_PREFIX"),
+        })
+    }
+
+    /// Builds the [async_nats::ConnectOptions] described by this config
+    pub async fn connect_options(&self) -> Result<ConnectOptions, NatsTransportError> {
+        let mut options = match &self.credentials_file {
+            Some(path) => ConnectOptions::with_credentials_file(path.clone())
+                .await
+                .map_err(NatsTransportError::NatsCredentialsError)?,
+            None => ConnectOptions::new(),
+        };
+
+        match (&self.jwt, &self.nkey_seed) {
+            (Some(jwt), Some(seed)) => {
+                let key_pair = Arc::new(
+                    nkeys::KeyPair::from_seed(seed)
+                        .map_err(|err| NatsTransportError::NatsConfigError(err.to_string()))?,
+                );
+                options = options.jwt(jwt.clone(), move |nonce| {
+                    let key_pair = key_pair.clone();
+                    async move { key_pair.sign(&nonce).map_err(async_nats::AuthError::new) }
+                });
+            }
+            (Some(_), None) => {
+                return Err(NatsTransportError::NatsConfigError(
+                    "a JWT requires an nkey seed to sign the server nonce".to_string(),
+                ))
+            }
+            (None, Some(seed)) => options = options.nkey(seed.clone()),
+            (None, None) => {}
+        }
+
+        match (&self.user, &self.password) {
+            (Some(user), Some(password)) => {
+                options = options.user_and_password(user.clone(), password.clone());
+            }
+            (None, None) => {}
+            _ => {
+                return Err(NatsTransportError::NatsConfigError(
+                    "user and password authentication requires both a user and a password"
+                        .to_string(),
+                ))
+            }
+        }
+
+        if let Some(token) = &self.token {
+            options = options.token(token.clone());
+        }
+
+        match (&self.tls_client_cert, &self.tls_client_key) {
+            (Some(cert), Some(key)) => {
+                options = options.add_client_certificate(cert.clone(), key.clone());
+            }
+            (None, None) => {}
+            _ => {
+                return Err(NatsTransportError::NatsConfigError(
+                    "a TLS client certificate requires both a cert and a key".to_string(),
+                ))
+            }
+        }
+
+        if let Some(root) = &self.tls_root_certificate {
+            options = options.add_root_certificates(root.clone());
+        }
+
+        options = options.require_tls(self.tls_required);
+
+        if let Some(name) = &self.name {
+            options = options.name(name);
+        }
+
+        if let Some(ping_interval) = self.ping_interval_ms {
+            options = options.ping_interval(Duration::from_millis(ping_interval));
+        }
+
+        if let Some(timeout) = self.connection_timeout_ms {
+            options = options.connection_timeout(Duration::from_millis(timeout));
+        }
+
+        if let Some(timeout) = self.request_timeout_ms {
+            options = options.request_timeout(Some(Duration::from_millis(timeout)));
+        }
+
+        if let Some(prefix) = &self.inbox_prefix {
+            options = options.custom_inbox_prefix(prefix);
+        }
+
+        Ok(options)
+    }
+}
@@ -1,30 +1,108 @@
-use async_nats::Client;
+use std::{str::FromStr, time::Duration};
+
+use async_nats::{
+    jetstream::{self, publish::PublishAck},
+    Client, ConnectOptions, HeaderMap, Request, RequestErrorKind,
+};
 use async_trait::async_trait;
 use bytes::Bytes;
+use chat_proto::runtiva::nats::v1 as proto_nats;
 use prost::Message;
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Serialize};
 
+use crate::error::{typed_details_from_header, ErrorModel};
+use crate::request::{deadline_header, DEADLINE_HEADER};
+use crate::response::{NatsResponse, ERROR_DETAILS_HEADER, ERROR_REPLY_HEADER};
 use crate::server::{
-    serde::{NatsJson, NatsMessageSerde, Serializer},
-    server_traits::{RequestJson, RequestProst},
-    NatsTransportError, PublishJson, PublishProst,
+    jetstream::publish_error,
+    serde::{Deserializer, NatsJson, NatsMessageSerde, Serializer},
+    server_traits::{
+        JetStreamPublishJson, JetStreamPublishProst, RequestJson, RequestProst, RequestReplyJson,
+        RequestReplyProst,
+    },
+    shutdown::{termination_signal, ShutdownCoordinator, ShutdownListener},
+    ClientError, JetStreamPublishOptions, NatsServerBuilder, NatsTransportError, PublishJson,
+    PublishProst,
 };
 
 pub struct NatsServer {
     nats: Client,
+    jetstream: jetstream::Context,
+    shutdown: ShutdownCoordinator,
 }
 
 impl NatsServer {
     /// initializes the NATS client connection
     pub async fn initialize(nats_url: &str) -> Result<NatsServer, NatsTransportError> {
-        let client = async_nats::connect(nats_url).await?;
-        Ok(NatsServer { nats: client })
+        NatsServerBuilder::new(nats_url).connect().await
+    }
+
+    /// returns a [NatsServerBuilder] to configure authentication, TLS and connection settings
+    pub fn builder(nats_url: impl Into<String>) -> NatsServerBuilder {
+        NatsServerBuilder::new(nats_url)
+    }
+
+    /// initializes the NATS client connection using the given [async_nats::ConnectOptions]
+    pub async fn connect_with(
+        nats_url: &str,
+        options: ConnectOptions,
+    ) -> Result<NatsServer, NatsTransportError> {
+        let client = options.connect(nats_url).await?;
+        Ok(NatsServer {
+            jetstream: jetstream::new(client.clone()),
+            nats: client,
+            shutdown: ShutdownCoordinator::new(),
+        })
     }
 
     pub fn client(&self) -> &Client {
         &self.nats
     }
 
+    /// returns the JetStream context of the connection
+    pub fn jetstream(&self) -> &jetstream::Context {
+        &self.jetstream
+    }
+
+    /// returns true once [NatsServer::shutdown] has been called
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown.is_shutting_down()
+    }
+
+    /// registers a subscription with the shutdown coordinator
+    pub(crate) fn shutdown_listener(&self) -> Result<ShutdownListener, NatsTransportError> {
+        self.shutdown.listener()
+    }
+
+    /// Gracefully shuts down the server:
+    /// - all subscriptions created through [NatsReceiver](crate::server::receiver::NatsReceiver)
+    ///   unsubscribe and stop accepting new messages
+    /// - messages already received (and in-flight handlers) are processed, up to the `deadline`
+    /// - pending publishes (including handler replies) are flushed to the NATS server
+    ///
+    /// New subscriptions fail with [NatsTransportError::ShuttingDown] once shutdown has started.
+    ///
+    /// The client itself is not drained (async-nats 0.31 has no `Client::drain`): subscriptions
+    /// made directly on [NatsServer::client] are left as is, and the connection is only closed
+    /// once the [NatsServer] and every clone of its client are dropped.
+    pub async fn shutdown(&self, deadline: Duration) -> Result<(), NatsTransportError> {
+        let drained = self.shutdown.shutdown(deadline).await;
+
+        // flush even when draining timed out, so completed replies are not lost
+        self.nats
+            .flush()
+            .await
+            .map_err(|err| NatsTransportError::NatsFlushError(err.into()))?;
+
+        drained
+    }
+
+    /// Waits for SIGTERM or SIGINT (Ctrl-C), then gracefully shuts down (see [NatsServer::shutdown])
+    pub async fn shutdown_on_signal(&self, deadline: Duration) -> Result<(), NatsTransportError> {
+        termination_signal().await?;
+        self.shutdown(deadline).await
+    }
+
     // TODO: update payload_json to msg: NatsMsg<T>
     pub async fn push_msg(
         &self,
@@ -41,23 +119,82 @@ impl NatsServer {
     async fn internal_publish(
         &self,
         subject: String,
+        headers: Option<HeaderMap>,
         message: Bytes,
     ) -> Result<(), NatsTransportError> {
-        self.nats
-            .publish(subject, message)
-            .await
-            .map_err(NatsTransportError::NatsPublishError)
+        match headers {
+            Some(headers) => {
+                self.nats
+                    .publish_with_headers(subject, headers, message)
+                    .await
+            }
+            None => self.nats.publish(subject, message).await,
+        }
+        .map_err(NatsTransportError::NatsPublishError)
     }
 
     async fn internal_request(
         &self,
         subject: String,
+        headers: Option<HeaderMap>,
         message: Bytes,
     ) -> Result<async_nats::Message, NatsTransportError> {
+        match headers {
+            Some(headers) => {
+                self.nats
+                    .request_with_headers(subject, headers, message)
+                    .await
+            }
+            None => self.nats.request(subject, message).await,
+        }
+        .map_err(NatsTransportError::NatsRequestError)
+    }
+
+    /// Sends the request with its deadline in the [DEADLINE_HEADER] header, so the receiving
+    /// side can stop handling it once the requestor stopped waiting
+    async fn internal_request_with_timeout(
+        &self,
+        subject: String,
+        mut headers: HeaderMap,
+        message: Bytes,
+        timeout: Duration,
+    ) -> Result<async_nats::Message, NatsTransportError> {
+        if let Some(deadline) = deadline_header(timeout) {
+            headers.insert(DEADLINE_HEADER, deadline.as_str());
+        }
+
+        let request = Request::new()
+            .payload(message)
+            .headers(headers)
+            .timeout(Some(timeout));
+
         self.nats
-            .request(subject, message)
+            .send_request(subject.clone(), request)
             .await
-            .map_err(NatsTransportError::NatsRequestError)
+            .map_err(|err| match err.kind() {
+                RequestErrorKind::TimedOut => NatsTransportError::RequestTimeout(subject),
+                _ => NatsTransportError::NatsRequestError(err),
+            })
+    }
+
+    async fn internal_jetstream_publish(
+        &self,
+        subject: String,
+        headers: Option<HeaderMap>,
+        message: Bytes,
+        options: JetStreamPublishOptions,
+    ) -> Result<PublishAck, NatsTransportError> {
+        let ack = match options.apply(headers) {
+            Some(headers) => {
+                self.jetstream
+                    .publish_with_headers(subject.clone(), headers, message)
+                    .await
+            }
+            None => self.jetstream.publish(subject.clone(), message).await,
+        }
+        .map_err(|err| publish_error(&subject, err))?;
+
+        ack.await.map_err(|err| publish_error(&subject, err))
     }
 }
 
@@ -68,9 +205,19 @@ where
     T: Message + Default + 'static,
 {
     async fn publish(&self, subject: String, msg: T) -> Result<(), NatsTransportError> {
-        let serde = NatsMessageSerde::<T>::default();
-        let serialized_msg = serde.serialize(msg);
-        self.internal_publish(subject, serialized_msg).await
+        let serialized_msg = serialize::<_, NatsMessageSerde<T>>(msg)?;
+        self.internal_publish(subject, None, serialized_msg).await
+    }
+
+    async fn publish_with_headers(
+        &self,
+        subject: String,
+        headers: HeaderMap,
+        msg: T,
+    ) -> Result<(), NatsTransportError> {
+        let serialized_msg = serialize::<_, NatsMessageSerde<T>>(msg)?;
+        self.internal_publish(subject, Some(headers), serialized_msg)
+            .await
     }
 }
 
@@ -81,9 +228,57 @@ where
     T: Serialize + Send + Sync + 'static,
 {
     async fn publish(&self, subject: String, msg: T) -> Result<(), NatsTransportError> {
-        let serde = NatsJson::<T>::default();
-        let serialized_msg = serde.serialize(msg);
-        self.internal_publish(subject, serialized_msg).await
+        let serialized_msg = serialize::<_, NatsJson<T>>(msg)?;
+        self.internal_publish(subject, None, serialized_msg).await
+    }
+
+    async fn publish_with_headers(
+        &self,
+        subject: String,
+        headers: HeaderMap,
+        msg: T,
+    ) -> Result<(), NatsTransportError> {
+        let serialized_msg = serialize::<_, NatsJson<T>>(msg)?;
+        self.internal_publish(subject, Some(headers), serialized_msg)
+            .await
+    }
+}
+
+#[async_trait]
+impl<T> JetStreamPublishProst<T> for NatsServer
+where
+    Self: Send + Sync,
+    T: Message + Default + 'static,
+{
+    async fn jetstream_publish(
+        &self,
+        subject: String,
+        headers: Option<HeaderMap>,
+        msg: T,
+        options: JetStreamPublishOptions,
+    ) -> Result<PublishAck, NatsTransportError> {
+        let serialized_msg = serialize::<_, NatsMessageSerde<T>>(msg)?;
+        self.internal_jetstream_publish(subject, headers, serialized_msg, options)
+            .await
+    }
+}
+
+#[async_trait]
+impl<T> JetStreamPublishJson<T> for NatsServer
+where
+    Self: Send + Sync,
+    T: Serialize + Send + Sync + 'static,
+{
+    async fn jetstream_publish(
+        &self,
+        subject: String,
+        headers: Option<HeaderMap>,
+        msg: T,
+        options: JetStreamPublishOptions,
+    ) -> Result<PublishAck, NatsTransportError> {
+        let serialized_msg = serialize::<_, NatsJson<T>>(msg)?;
+        self.internal_jetstream_publish(subject, headers, serialized_msg, options)
+            .await
     }
 }
 
@@ -98,9 +293,31 @@ where
         subject: String,
         msg: T,
     ) -> Result<async_nats::Message, NatsTransportError> {
-        let serde = NatsMessageSerde::<T>::default();
-        let serialized_msg = serde.serialize(msg);
-        self.internal_request(subject, serialized_msg).await
+        let serialized_msg = serialize::<_, NatsMessageSerde<T>>(msg)?;
+        self.internal_request(subject, None, serialized_msg).await
+    }
+
+    async fn request_with_headers(
+        &self,
+        subject: String,
+        headers: HeaderMap,
+        msg: T,
+    ) -> Result<async_nats::Message, NatsTransportError> {
+        let serialized_msg = serialize::<_, NatsMessageSerde<T>>(msg)?;
+        self.internal_request(subject, Some(headers), serialized_msg)
+            .await
+    }
+
+    async fn request_with_timeout(
+        &self,
+        subject: String,
+        headers: HeaderMap,
+        msg: T,
+        timeout: Duration,
+    ) -> Result<async_nats::Message, NatsTransportError> {
+        let serialized_msg = serialize::<_, NatsMessageSerde<T>>(msg)?;
+        self.internal_request_with_timeout(subject, headers, serialized_msg, timeout)
+            .await
     }
 }
 
@@ -115,12 +332,131 @@ where
         subject: String,
         msg: T,
     ) -> Result<async_nats::Message, NatsTransportError> {
-        let serde = NatsJson::<T>::default();
-        let serialized_msg = serde.serialize(msg);
-        self.internal_request(subject, serialized_msg).await
+        let serialized_msg = serialize::<_, NatsJson<T>>(msg)?;
+        self.internal_request(subject, None, serialized_msg).await
+    }
+
+    async fn request_with_headers(
+        &self,
+        subject: String,
+        headers: HeaderMap,
+        msg: T,
+    ) -> Result<async_nats::Message, NatsTransportError> {
+        let serialized_msg = serialize::<_, NatsJson<T>>(msg)?;
+        self.internal_request(subject, Some(headers), serialized_msg)
+            .await
+    }
+
+    async fn request_with_timeout(
+        &self,
+        subject: String,
+        headers: HeaderMap,
+        msg: T,
+        timeout: Duration,
+    ) -> Result<async_nats::Message, NatsTransportError> {
+        let serialized_msg = serialize::<_, NatsJson<T>>(msg)?;
+        self.internal_request_with_timeout(subject, headers, serialized_msg, timeout)
+            .await
     }
 }
 
+#[async_trait]
+impl<T, Resp, R> RequestReplyJson<T, Resp, R> for NatsServer
+where
+    Self: Send + Sync,
+    T: Serialize + Send + Sync + 'static,
+    Resp: DeserializeOwned + Send + 'static,
+    R: DeserializeOwned + Send + 'static,
+{
+    async fn request_reply(&self, subject: String, msg: T) -> Result<Resp, ClientError<R>> {
+        let reply = RequestJson::request(self, subject, msg).await?;
+
+        let serde = NatsJson::<NatsResponse<Resp, R>>::default();
+        let response = serde
+            .deserialize(reply.payload)
+            .map_err(|err| NatsTransportError::DeserializeReply(Box::new(err)))?;
+
+        response.into_result()
+    }
+}
+
+#[async_trait]
+impl<T, Resp, R> RequestReplyProst<T, Resp, R> for NatsServer
+where
+    Self: Send + Sync,
+    T: Message + Send + Sync + Default + 'static,
+    Resp: Message + Default + Send + 'static,
+    R: FromStr + Send + 'static,
+{
+    async fn request_reply(&self, subject: String, msg: T) -> Result<Resp, ClientError<R>> {
+        let reply = RequestProst::request(self, subject.clone(), msg).await?;
+
+        let is_error_reply = reply
+            .headers
+            .as_ref()
+            .is_some_and(|headers| headers.get(ERROR_REPLY_HEADER).is_some());
+
+        if is_error_reply {
+            let serde = NatsMessageSerde::<proto_nats::ErrorReply>::default();
+            let error_reply = serde
+                .deserialize(reply.payload)
+                .map_err(|err| NatsTransportError::DeserializeReply(Box::new(err)))?;
+
+            let model = match ErrorModel::try_from(error_reply.clone()) {
+                Ok(model) => model,
+                Err(_) => return Err(ClientError::RemoteReply(error_reply)),
+            };
+
+            let details = reply
+                .headers
+                .as_ref()
+                .and_then(|headers| headers.get(ERROR_DETAILS_HEADER));
+            let model = with_details_header(model, &subject, details.map(|value| value.as_str()));
+
+            return Err(ClientError::Remote(model));
+        }
+
+        let serde = NatsMessageSerde::<Resp>::default();
+        serde
+            .deserialize(reply.payload)
+            .map_err(|err| NatsTransportError::DeserializeReply(Box::new(err)).into())
+    }
+}
+
+/// Adds the typed details of the [ERROR_DETAILS_HEADER] header of an error reply to its model.
+/// A malformed header only loses the typed details: it is logged and the model is kept.
+pub(crate) fn with_details_header<R>(
+    mut model: ErrorModel<R>,
+    subject: &str,
+    header: Option<&str>,
+) -> ErrorModel<R> {
+    let Some(header) = header else {
+        return model;
+    };
+
+    match typed_details_from_header(header) {
+        Ok(details) => model.typed_details.extend(details),
+        Err(err) => tracing::warn!(
+            subject,
+            error = %err,
+            "ignoring malformed error details header of an error reply"
+        ),
+    }
+
+    model
+}
+
+/// Serializes an outgoing message, mapping failures to [NatsTransportError::Serialize]
+fn serialize<T, S>(msg: T) -> Result<Bytes, NatsTransportError>
+where
+    S: Serializer<T> + Default,
+    <S as Serializer<T>>::Error: std::error::Error + Send + Sync + 'static,
+{
+    S::default()
+        .serialize(msg)
+        .map_err(|err| NatsTransportError::Serialize(Box::new(err)))
+}
+
 #[cfg(test)]
 #[path = "./nats_server_tests.rs"]
 mod nats_server_tests;
@@ -0,0 +1,133 @@
+use std::{path::PathBuf, time::Duration};
+
+use crate::server::{NatsConfig, NatsServer, NatsTransportError};
+
+/// Builder for a [NatsServer] connection with authentication, TLS and connection tuning.
+///
+/// ```no_run
+/// # async fn connect() -> Result<(), nats_transport::server::NatsTransportError> {
+/// use std::time::Duration;
+/// use nats_transport::server::NatsServer;
+///
+/// let nats = NatsServer::builder("tls://nats.runtiva.com:4222")
+///     .name("chat-persist")
+///     .credentials_file("/etc/nats/chat-persist.creds")
+///     .root_certificate("/etc/nats/ca.pem")
+///     .require_tls(true)
+///     .ping_interval(Duration::from_secs(10))
+///     .connect()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct NatsServerBuilder {
+    config: NatsConfig,
+}
+
+impl NatsServerBuilder {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            config: NatsConfig {
+                url: url.into(),
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn from_config(config: NatsConfig) -> Self {
+        Self { config }
+    }
+
+    /// Creates a builder from the `NATS_*` environment variables (see [NatsConfig::from_env])
+    pub fn from_env() -> Result<Self, NatsTransportError> {
+        Ok(Self::from_config(NatsConfig::from_env()?))
+    }
+
+    pub fn config(&self) -> &NatsConfig {
+        &self.config
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.config.name = Some(name.into());
+        self
+    }
+
+    pub fn credentials_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.credentials_file = Some(path.into());
+        self
+    }
+
+    pub fn nkey(mut self, seed: impl Into<String>) -> Self {
+        self.config.nkey_seed = Some(seed.into());
+        self
+    }
+
+    /// Authenticates with a user JWT, signing the server nonce with the given nkey seed
+    pub fn jwt(mut self, jwt: impl Into<String>, seed: impl Into<String>) -> Self {
+        self.config.jwt = Some(jwt.into());
+        self.config.nkey_seed = Some(seed.into());
+        self
+    }
+
+    pub fn user_and_password(
+        mut self,
+        user: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.config.user = Some(user.into());
+        self.config.password = Some(password.into());
+        self
+    }
+
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.config.token = Some(token.into());
+        self
+    }
+
+    pub fn require_tls(mut self, is_required: bool) -> Self {
+        self.config.tls_required = is_required;
+        self
+    }
+
+    pub fn client_certificate(mut self, cert: impl Into<PathBuf>, key: impl Into<PathBuf>) -> Self {
+        self.config.tls_client_cert = Some(cert.into());
+        self.config.tls_client_key = Some(key.into());
+        self
+    }
+
+    pub fn root_certificate(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.tls_root_certificate = Some(path.into());
+        self
+    }
+
+    pub fn ping_interval(mut self, interval: Duration) -> Self {
+        self.config.ping_interval_ms = Some(interval.as_millis() as u64);
+        self
+    }
+
+    pub fn connection_timeout(mut self, timeout: Duration) -> Self {
+        self.config.connection_timeout_ms = Some(timeout.as_millis() as u64);
+        self
+    }
+
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.config.request_timeout_ms = Some(timeout.as_millis() as u64);
+        self
+    }
+
+    pub fn inbox_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.config.inbox_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Connects to the NATS server with the configured options
+    pub async fn connect(self) -> Result<NatsServer, NatsTransportError> {
+        let options = self.config.connect_options().await?;
+        NatsServer::connect_with(&self.config.url, options).await
+    }
+}
+
+#[cfg(test)]
+#[path = "./nats_server_builder_tests.rs"]
+mod nats_server_builder_tests;
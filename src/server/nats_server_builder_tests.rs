@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod nats_server_builder_tests {
+    use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+    use crate::server::{NatsConfig, NatsServer, NatsTransportError};
+
+    #[test]
+    fn test_config_from_lookup() {
+        let vars = HashMap::from([
+            ("NATS_URL", "tls://nats.runtiva.com:4222"),
+            ("NATS_NAME", "chat-persist"),
+            ("NATS_CREDS_FILE", "/etc/nats/chat-persist.creds"),
+            ("NATS_TLS_REQUIRED", "true"),
+            ("NATS_TLS_CA", "/etc/nats/ca.pem"),
+            ("NATS_PING_INTERVAL_MS", "10000"),
+            ("NATS_INBOX_PREFIX", "_INBOX.chat-persist"),
+        ]);
+
+        let config =
+            NatsConfig::from_lookup(|key| vars.get(key).map(|value| value.to_string())).unwrap();
+
+        assert_eq!(config.url, "tls://nats.runtiva.com:4222");
+        assert_eq!(config.name, Some("chat-persist".to_string()));
+        assert_eq!(
+            config.credentials_file,
+            Some(PathBuf::from("/etc/nats/chat-persist.creds"))
+        );
+        assert!(config.tls_required);
+        assert_eq!(
+            config.tls_root_certificate,
+            Some(PathBuf::from("/etc/nats/ca.pem"))
+        );
+        assert_eq!(config.ping_interval_ms, Some(10000));
+        assert_eq!(config.inbox_prefix, Some("_INBOX.chat-persist".to_string()));
+        assert!(config.user.is_none());
+    }
+
+    #[test]
+    fn test_config_from_lookup_invalid_number() {
+        let result = NatsConfig::from_lookup(|key| match key {
+            "NATS_PING_INTERVAL_MS" => Some("ten".to_string()),
+            _ => None,
+        });
+
+        assert!(matches!(
+            result,
+            Err(NatsTransportError::NatsConfigError(_))
+        ));
+    }
+
+    #[test]
+    fn test_config_debug_redacts_secrets() {
+        let builder = NatsServer::builder("nats://localhost:4222")
+            .user_and_password("chat", "s3cr3t")
+            .token("my-token");
+
+        let output = format!("{:?}", builder.config());
+
+        assert!(!output.contains("s3cr3t"));
+        assert!(!output.contains("my-token"));
+        assert!(output.contains("chat"));
+    }
+
+    #[test]
+    fn test_builder_settings() {
+        let builder = NatsServer::builder("nats://localhost:4222")
+            .name("chat-persist")
+            .client_certificate("cert.pem", "key.pem")
+            .request_timeout(Duration::from_secs(3));
+
+        let config = builder.config();
+        assert_eq!(config.name, Some("chat-persist".to_string()));
+        assert_eq!(config.tls_client_cert, Some(PathBuf::from("cert.pem")));
+        assert_eq!(config.tls_client_key, Some(PathBuf::from("key.pem")));
+        assert_eq!(config.request_timeout_ms, Some(3000));
+    }
+
+    #[tokio::test]
+    async fn test_jwt_requires_seed() {
+        let config = NatsConfig {
+            jwt: Some("eyJ0eXAiOiJKV1QiLCJhbGciOiJlZDI1NTE5In0".to_string()),
+            ..Default::default()
+        };
+
+        let result = config.connect_options().await;
+
+        assert!(matches!(
+            result,
+            Err(NatsTransportError::NatsConfigError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_user_requires_password() {
+        let config = NatsConfig {
+            user: Some("chat".to_string()),
+            ..Default::default()
+        };
+
+        let result = config.connect_options().await;
+
+        assert!(matches!(
+            result,
+            Err(NatsTransportError::NatsConfigError(_))
+        ));
+    }
+}
@@ -2,8 +2,10 @@
 mod nats_server_tests {
     use serde::Serialize;
 
+    use crate::error::{BadRequest, ErrorModel, ErrorReason, Status};
     use crate::proto_test as proto;
-    use crate::server::{NatsServer, NatsTransportError};
+    use crate::server::{nats_server::with_details_header, NatsServer, NatsTransportError};
+    use crate::Subject;
 
     #[tokio::test]
     async fn test_publish_prost() -> Result<(), NatsTransportError> {
@@ -32,13 +34,35 @@ mod nats_server_tests {
         };
 
         use crate::server::nats_server::PublishJson;
-        let subject = format!("chat.channel.command.create.[channel_id]");
+        let subject = Subject::command("channel", "create")?
+            .with_id("[channel_id]")?
+            .to_string();
 
         nats.publish(subject, test_msg).await?;
 
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_jetstream_publish_without_stream() -> Result<(), NatsTransportError> {
+        let nats = NatsServer::initialize("").await?;
+
+        let test_msg = SerializableUser {
+            id: "1234".into(),
+            attr: "test".into(),
+            credit: 23.23,
+        };
+
+        use crate::server::{JetStreamPublishJson, JetStreamPublishOptions};
+        let options = JetStreamPublishOptions::new().message_id("user-1234");
+        let result = nats
+            .jetstream_publish("unstored.user.created".into(), None, test_msg, options)
+            .await;
+
+        assert!(matches!(result, Err(NatsTransportError::StreamNotFound(_))));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_push_msg() -> Result<(), NatsTransportError> {
         let nats = NatsServer::initialize("".into()).await?;
@@ -58,4 +82,26 @@ mod nats_server_tests {
         pub attr: String,
         pub credit: f32,
     }
+
+    #[test]
+    fn test_malformed_error_details_header() {
+        let model: ErrorModel<ErrorReason> = ErrorModel::new(
+            Status::InvalidArgument,
+            400,
+            "Invalid chat group".to_string(),
+        );
+        let with_details = model
+            .clone()
+            .with_typed_detail(BadRequest::new().field_violation("title", "empty"));
+        let header = with_details.typed_details_header().unwrap();
+
+        let restored = with_details_header(model.clone(), "chat.chatgroup.create", Some(&header));
+        assert_eq!(restored.typed_details, with_details.typed_details);
+
+        // the model of the error reply is kept without its typed details
+        let kept = with_details_header(model, "chat.chatgroup.create", Some("not base64"));
+        assert_eq!(kept.status, Status::InvalidArgument);
+        assert_eq!(kept.message, "Invalid chat group");
+        assert!(kept.typed_details.is_empty());
+    }
 }
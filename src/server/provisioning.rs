@@ -0,0 +1,437 @@
+use std::{collections::HashSet, fmt::Debug, time::Duration};
+
+use async_nats::jetstream::{
+    consumer::{self, AckPolicy},
+    stream::{self, RetentionPolicy, StorageType},
+    Context,
+};
+use futures::TryStreamExt;
+use serde::Deserialize;
+
+use crate::{
+    server::{NatsServer, NatsTransportError},
+    SubjectKind, SubjectPattern,
+};
+
+/// Declarative description of the JetStream streams (and their durable consumers) used by a service.
+///
+/// [Topology::reconcile] creates the missing resources at startup, updates the settings that
+/// can be changed in place and reports the incompatible drift. Options left unset take the
+/// server defaults on creation and are neither compared nor changed on existing resources.
+///
+/// ```
+/// use nats_transport::server::Topology;
+///
+/// let topology = Topology::from_toml(r#"
+///     [[streams]]
+///     name = "CHAT_EVENTS"
+///     subjects = [{ domain = "chatgroup" }, "chat.user.event.>"]
+///     retention = "limits"
+///     storage = "file"
+///     replicas = 3
+///     max_age_ms = 604800000
+///     duplicate_window_ms = 120000
+///
+///     [[streams.consumers]]
+///     durable_name = "chat-persist"
+///     filter_subject = { domain = "chatgroup", event = "created" }
+///     max_deliver = 5
+/// "#).unwrap();
+///
+/// assert_eq!(topology.streams[0].subjects[0].subject(), "chat.chatgroup.event.>");
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Topology {
+    #[serde(default)]
+    pub streams: Vec<StreamSpec>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StreamSpec {
+    pub name: String,
+    pub subjects: Vec<SubjectSpec>,
+
+    pub retention: Option<RetentionPolicy>,
+    pub storage: Option<StorageType>,
+    pub replicas: Option<usize>,
+
+    pub max_age_ms: Option<u64>,
+
+    /// Window in which messages published with the same `Nats-Msg-Id` are deduplicated
+    pub duplicate_window_ms: Option<u64>,
+
+    #[serde(default)]
+    pub consumers: Vec<ConsumerSpec>,
+}
+
+/// Durable pull consumer of a [StreamSpec]
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConsumerSpec {
+    pub durable_name: String,
+    pub filter_subject: Option<SubjectSpec>,
+    pub max_deliver: Option<i64>,
+    pub ack_wait_ms: Option<u64>,
+}
+
+/// Subject of a stream or consumer: either the events of a chat domain (see
+/// [SubjectPattern]), or a raw subject
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum SubjectSpec {
+    /// `chat.<domain>.event.<event>`, all events of the domain by default
+    ChatEvent {
+        domain: String,
+        #[serde(default = "all_events")]
+        event: String,
+    },
+    Subject(String),
+}
+
+/// Outcome of [Topology::reconcile], listing the streams (by name) and consumers
+/// (as `<stream>/<consumer>`) by what was done to them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProvisioningReport {
+    pub created: Vec<String>,
+    pub updated: Vec<String>,
+    pub unchanged: Vec<String>,
+
+    /// Incompatible differences, which must be resolved by hand (e.g. by recreating the stream)
+    pub drift: Vec<Drift>,
+}
+
+/// Setting of an existing resource that differs from the topology and cannot be updated in place
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drift {
+    pub resource: String,
+    pub field: &'static str,
+    pub expected: String,
+    pub actual: String,
+}
+
+/// Differences between a spec and the configuration of an existing resource
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct Diff {
+    pub changed: Vec<&'static str>,
+    pub incompatible: Vec<(&'static str, String, String)>,
+}
+
+fn all_events() -> String {
+    ">".to_string()
+}
+
+fn chat_event(domain: &str, event: &str) -> SubjectPattern {
+    SubjectPattern::new()
+        .domain(domain)
+        .kind(SubjectKind::Event)
+        .name(event)
+}
+
+impl Topology {
+    pub fn from_toml(toml: &str) -> Result<Self, NatsTransportError> {
+        toml::from_str::<Self>(toml)
+            .map_err(|err| NatsTransportError::NatsConfigError(err.to_string()))?
+            .validated()
+    }
+
+    pub fn from_yaml(yaml: &str) -> Result<Self, NatsTransportError> {
+        serde_yaml::from_str::<Self>(yaml)
+            .map_err(|err| NatsTransportError::NatsConfigError(err.to_string()))?
+            .validated()
+    }
+
+    /// Rejects chat event subjects with invalid domain or event tokens
+    fn validated(self) -> Result<Self, NatsTransportError> {
+        let subjects = self.streams.iter().flat_map(|stream| {
+            let filters = stream
+                .consumers
+                .iter()
+                .filter_map(|consumer| consumer.filter_subject.as_ref());
+            stream.subjects.iter().chain(filters)
+        });
+
+        for subject in subjects {
+            if let SubjectSpec::ChatEvent { domain, event } = subject {
+                chat_event(domain, event)
+                    .build()
+                    .map_err(|err| NatsTransportError::NatsConfigError(err.to_string()))?;
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Creates the missing streams and consumers, and updates the compatible changes.
+    /// Resources with incompatible drift are left untouched and reported.
+    pub async fn reconcile(
+        &self,
+        nats_server: &NatsServer,
+    ) -> Result<ProvisioningReport, NatsTransportError> {
+        let jetstream = nats_server.jetstream();
+        let mut report = ProvisioningReport::default();
+
+        let existing: HashSet<String> = jetstream
+            .stream_names()
+            .try_collect()
+            .await
+            .map_err(stream_error)?;
+
+        for spec in &self.streams {
+            if existing.contains(&spec.name) {
+                update_stream(jetstream, spec, &mut report).await?;
+            } else {
+                let mut config = stream::Config {
+                    name: spec.name.clone(),
+                    ..Default::default()
+                };
+                spec.apply(&mut config);
+                jetstream
+                    .create_stream(config)
+                    .await
+                    .map_err(stream_error)?;
+                report.created.push(spec.name.clone());
+            }
+
+            reconcile_consumers(jetstream, spec, &mut report).await?;
+        }
+
+        Ok(report)
+    }
+}
+
+async fn update_stream(
+    jetstream: &Context,
+    spec: &StreamSpec,
+    report: &mut ProvisioningReport,
+) -> Result<(), NatsTransportError> {
+    let stream = jetstream
+        .get_stream(&spec.name)
+        .await
+        .map_err(stream_error)?;
+    let actual = stream.cached_info().config.clone();
+
+    let diff = spec.diff(&actual);
+    if report.record(&spec.name, diff) {
+        let mut config = actual;
+        spec.apply(&mut config);
+        jetstream
+            .update_stream(config)
+            .await
+            .map_err(stream_error)?;
+    }
+
+    Ok(())
+}
+
+async fn reconcile_consumers(
+    jetstream: &Context,
+    spec: &StreamSpec,
+    report: &mut ProvisioningReport,
+) -> Result<(), NatsTransportError> {
+    if spec.consumers.is_empty() {
+        return Ok(());
+    }
+
+    let stream = jetstream
+        .get_stream(&spec.name)
+        .await
+        .map_err(stream_error)?;
+    let existing: HashSet<String> = stream
+        .consumer_names()
+        .try_collect()
+        .await
+        .map_err(consumer_error)?;
+
+    for consumer_spec in &spec.consumers {
+        let resource = format!("{}/{}", spec.name, consumer_spec.durable_name);
+
+        let config = if existing.contains(&consumer_spec.durable_name) {
+            let actual = stream
+                .consumer_info(&consumer_spec.durable_name)
+                .await
+                .map_err(consumer_error)?
+                .config;
+
+            if !report.record(&resource, consumer_spec.diff(&actual)) {
+                continue;
+            }
+            actual
+        } else {
+            report.created.push(resource);
+            consumer::Config {
+                durable_name: Some(consumer_spec.durable_name.clone()),
+                ack_policy: AckPolicy::Explicit,
+                ..Default::default()
+            }
+        };
+
+        let mut config = config;
+        consumer_spec.apply(&mut config);
+
+        // creating an existing durable consumer updates its configuration
+        stream
+            .create_consumer(config)
+            .await
+            .map_err(consumer_error)?;
+    }
+
+    Ok(())
+}
+
+impl StreamSpec {
+    /// Sets the managed settings on the stream configuration
+    pub(crate) fn apply(&self, config: &mut stream::Config) {
+        config.subjects = self.subjects.iter().map(SubjectSpec::subject).collect();
+        if let Some(retention) = self.retention {
+            config.retention = retention;
+        }
+        if let Some(storage) = self.storage {
+            config.storage = storage;
+        }
+        if let Some(replicas) = self.replicas {
+            config.num_replicas = replicas;
+        }
+        if let Some(max_age_ms) = self.max_age_ms {
+            config.max_age = Duration::from_millis(max_age_ms);
+        }
+        if let Some(duplicate_window_ms) = self.duplicate_window_ms {
+            config.duplicate_window = Duration::from_millis(duplicate_window_ms);
+        }
+    }
+
+    /// Compares the managed settings with the configuration of an existing stream.
+    /// The retention and storage of a stream cannot be changed once it is created.
+    /// Unset settings keep the actual value and never differ.
+    pub(crate) fn diff(&self, actual: &stream::Config) -> Diff {
+        let mut desired = actual.clone();
+        self.apply(&mut desired);
+
+        let mut diff = Diff::default();
+
+        diff.incompatible_if("retention", &desired.retention, &actual.retention);
+        diff.incompatible_if("storage", &desired.storage, &actual.storage);
+
+        diff.changed_if("subjects", desired.subjects != actual.subjects);
+        diff.changed_if("replicas", desired.num_replicas != actual.num_replicas);
+        diff.changed_if("max_age", desired.max_age != actual.max_age);
+        diff.changed_if(
+            "duplicate_window",
+            desired.duplicate_window != actual.duplicate_window,
+        );
+
+        diff
+    }
+}
+
+impl ConsumerSpec {
+    /// Sets the managed settings on the consumer configuration
+    pub(crate) fn apply(&self, config: &mut consumer::Config) {
+        if let Some(filter_subject) = &self.filter_subject {
+            config.filter_subject = filter_subject.subject();
+        }
+        if let Some(max_deliver) = self.max_deliver {
+            config.max_deliver = max_deliver;
+        }
+        if let Some(ack_wait_ms) = self.ack_wait_ms {
+            config.ack_wait = Duration::from_millis(ack_wait_ms);
+        }
+    }
+
+    /// Compares the managed settings with the configuration of an existing consumer.
+    /// Push consumers and consumers without explicit acks cannot be turned into
+    /// the durable pull consumers of the topology.
+    pub(crate) fn diff(&self, actual: &consumer::Config) -> Diff {
+        let mut desired = actual.clone();
+        self.apply(&mut desired);
+
+        let mut diff = Diff::default();
+
+        if let Some(deliver_subject) = &actual.deliver_subject {
+            diff.incompatible.push((
+                "mode",
+                "pull".to_string(),
+                format!("push ({})", deliver_subject),
+            ));
+        }
+        diff.incompatible_if("ack_policy", &AckPolicy::Explicit, &actual.ack_policy);
+
+        diff.changed_if(
+            "filter_subject",
+            desired.filter_subject != actual.filter_subject,
+        );
+        diff.changed_if("max_deliver", desired.max_deliver != actual.max_deliver);
+        diff.changed_if("ack_wait", desired.ack_wait != actual.ack_wait);
+
+        diff
+    }
+}
+
+impl SubjectSpec {
+    pub fn subject(&self) -> String {
+        match self {
+            SubjectSpec::ChatEvent { domain, event } => chat_event(domain, event).to_string(),
+            SubjectSpec::Subject(subject) => subject.clone(),
+        }
+    }
+}
+
+impl Diff {
+    fn changed_if(&mut self, field: &'static str, changed: bool) {
+        if changed {
+            self.changed.push(field);
+        }
+    }
+
+    fn incompatible_if<T: PartialEq + Debug>(
+        &mut self,
+        field: &'static str,
+        desired: &T,
+        actual: &T,
+    ) {
+        if desired != actual {
+            self.incompatible
+                .push((field, format!("{:?}", desired), format!("{:?}", actual)));
+        }
+    }
+}
+
+impl ProvisioningReport {
+    /// Returns true when incompatible drift was found
+    pub fn has_drift(&self) -> bool {
+        !self.drift.is_empty()
+    }
+
+    /// Records the diff of an existing resource, returning true if it must be updated
+    fn record(&mut self, resource: &str, diff: Diff) -> bool {
+        if !diff.incompatible.is_empty() {
+            self.drift.extend(
+                diff.incompatible
+                    .into_iter()
+                    .map(|(field, expected, actual)| Drift {
+                        resource: resource.to_string(),
+                        field,
+                        expected,
+                        actual,
+                    }),
+            );
+            false
+        } else if !diff.changed.is_empty() {
+            self.updated.push(resource.to_string());
+            true
+        } else {
+            self.unchanged.push(resource.to_string());
+            false
+        }
+    }
+}
+
+fn stream_error(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> NatsTransportError {
+    NatsTransportError::NatsStreamError(err.into())
+}
+
+fn consumer_error(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> NatsTransportError {
+    NatsTransportError::NatsConsumerError(err.into())
+}
+
+#[cfg(test)]
+#[path = "./provisioning_tests.rs"]
+mod provisioning_tests;
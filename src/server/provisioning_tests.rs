@@ -0,0 +1,180 @@
+#[cfg(test)]
+mod provisioning_tests {
+    use std::time::Duration;
+
+    use async_nats::jetstream::{
+        consumer,
+        stream::{self, RetentionPolicy, StorageType},
+    };
+
+    use crate::server::{NatsTransportError, SubjectSpec, Topology};
+
+    const TOPOLOGY: &str = r#"
+streams:
+  - name: CHAT_EVENTS
+    subjects:
+      - domain: chatgroup
+      - chat.user.event.>
+    retention: interest
+    storage: memory
+    replicas: 3
+    duplicate_window_ms: 60000
+    consumers:
+      - durable_name: chat-persist
+        filter_subject:
+          domain: chatgroup
+          event: created
+        max_deliver: 5
+"#;
+
+    fn existing_stream() -> stream::Config {
+        stream::Config {
+            name: "CHAT_EVENTS".to_string(),
+            subjects: vec![
+                "chat.chatgroup.event.>".to_string(),
+                "chat.user.event.>".to_string(),
+            ],
+            retention: RetentionPolicy::Interest,
+            storage: StorageType::Memory,
+            num_replicas: 3,
+            duplicate_window: Duration::from_secs(60),
+            max_bytes: 1024,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_load_yaml() {
+        let topology = Topology::from_yaml(TOPOLOGY).unwrap();
+        let stream = &topology.streams[0];
+
+        assert_eq!(stream.retention, Some(RetentionPolicy::Interest));
+        assert_eq!(stream.storage, Some(StorageType::Memory));
+        assert_eq!(stream.replicas, Some(3));
+        assert_eq!(stream.max_age_ms, None);
+        assert_eq!(
+            stream.subjects[1],
+            SubjectSpec::Subject("chat.user.event.>".to_string())
+        );
+
+        let consumer = &stream.consumers[0];
+        assert_eq!(
+            consumer.filter_subject.as_ref().unwrap().subject(),
+            "chat.chatgroup.event.created"
+        );
+        assert_eq!(consumer.max_deliver, Some(5));
+    }
+
+    #[test]
+    fn test_invalid_topology() {
+        assert!(Topology::from_toml("[[streams]]\nreplicas = 3").is_err());
+    }
+
+    #[test]
+    fn test_invalid_chat_event_subject() {
+        let toml = r#"
+            [[streams]]
+            name = "CHATGROUP"
+            subjects = [{ domain = "chat group" }]
+        "#;
+
+        assert!(matches!(
+            Topology::from_toml(toml),
+            Err(NatsTransportError::NatsConfigError(_))
+        ));
+    }
+
+    #[test]
+    fn test_stream_in_sync() {
+        let topology = Topology::from_yaml(TOPOLOGY).unwrap();
+
+        let diff = topology.streams[0].diff(&existing_stream());
+
+        assert!(diff.changed.is_empty());
+        assert!(diff.incompatible.is_empty());
+    }
+
+    #[test]
+    fn test_stream_compatible_changes() {
+        let topology = Topology::from_yaml(TOPOLOGY).unwrap();
+        let spec = &topology.streams[0];
+
+        let mut actual = existing_stream();
+        actual.subjects.pop();
+        actual.num_replicas = 1;
+
+        let diff = spec.diff(&actual);
+        assert_eq!(diff.changed, vec!["subjects", "replicas"]);
+        assert!(diff.incompatible.is_empty());
+
+        // settings outside of the topology are kept when updating
+        spec.apply(&mut actual);
+        assert_eq!(actual, existing_stream());
+    }
+
+    #[test]
+    fn test_stream_incompatible_drift() {
+        let topology = Topology::from_yaml(TOPOLOGY).unwrap();
+
+        let mut actual = existing_stream();
+        actual.storage = StorageType::File;
+
+        let diff = topology.streams[0].diff(&actual);
+        assert_eq!(
+            diff.incompatible,
+            vec![("storage", "Memory".to_string(), "File".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_unset_options_are_not_reconciled() {
+        let topology = Topology::from_toml(
+            r#"
+            [[streams]]
+            name = "CHAT_COMMANDS"
+            subjects = ["chat.*.command.>"]
+        "#,
+        )
+        .unwrap();
+        let spec = &topology.streams[0];
+
+        let actual = stream::Config {
+            name: "CHAT_COMMANDS".to_string(),
+            subjects: vec!["chat.*.command.>".to_string()],
+            retention: RetentionPolicy::WorkQueue,
+            storage: StorageType::Memory,
+            num_replicas: 3,
+            ..Default::default()
+        };
+
+        let diff = spec.diff(&actual);
+        assert!(diff.changed.is_empty());
+        assert!(diff.incompatible.is_empty());
+
+        let mut updated = actual.clone();
+        spec.apply(&mut updated);
+        assert_eq!(updated, actual);
+    }
+
+    #[test]
+    fn test_consumer_diff() {
+        let topology = Topology::from_yaml(TOPOLOGY).unwrap();
+        let spec = &topology.streams[0].consumers[0];
+
+        let mut actual = consumer::Config {
+            durable_name: Some("chat-persist".to_string()),
+            ack_policy: consumer::AckPolicy::Explicit,
+            filter_subject: "chat.chatgroup.event.created".to_string(),
+            max_deliver: 3,
+            ..Default::default()
+        };
+
+        let diff = spec.diff(&actual);
+        assert_eq!(diff.changed, vec!["max_deliver"]);
+        assert!(diff.incompatible.is_empty());
+
+        actual.deliver_subject = Some("deliver.chat-persist".to_string());
+        let diff = spec.diff(&actual);
+        assert_eq!(diff.incompatible[0].0, "mode");
+    }
+}
@@ -0,0 +1,186 @@
+use std::{cell::Cell, sync::Arc, time::Duration};
+
+use async_nats::{
+    jetstream::{self, AckKind},
+    Message,
+};
+use futures::{future::BoxFuture, Future, FutureExt};
+use tokio::time::{Instant, MissedTickBehavior};
+
+use crate::error::Status;
+
+use super::{dispatcher::Handler, MessageHandler};
+
+/// Outcome of processing a JetStream message, applied to the message by the
+/// [JetStreamReceiver](super::JetStreamReceiver) once the handler completes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AckOutcome {
+    /// The message was processed and is removed from the consumer's pending messages
+    Ack,
+
+    /// The message could not be processed now and is redelivered, after the delay
+    /// if one is given (otherwise immediately)
+    Nak(Option<Duration>),
+
+    /// The message can never be processed (e.g. it cannot be decoded) and is not redelivered
+    Term,
+
+    /// Processing continues after the handler returned, e.g. in a background job: the ack wait
+    /// of the message is reset, and the message must then be acknowledged by publishing to its
+    /// reply subject (its ack subject, e.g. `+ACK`) before the ack wait passes again, otherwise
+    /// it is redelivered.
+    ///
+    /// Progress is also reported automatically while a handler runs, so a long handler does
+    /// not need this outcome to keep its message from being redelivered.
+    InProgress,
+}
+
+/// Ack wait of the consumers that do not configure one, as set by the NATS server
+pub(crate) const DEFAULT_ACK_WAIT: Duration = Duration::from_secs(30);
+
+impl From<AckOutcome> for AckKind {
+    fn from(outcome: AckOutcome) -> Self {
+        match outcome {
+            AckOutcome::Ack => AckKind::Ack,
+            AckOutcome::Nak(delay) => AckKind::Nak(delay),
+            AckOutcome::Term => AckKind::Term,
+            AckOutcome::InProgress => AckKind::Progress,
+        }
+    }
+}
+
+/// Outcome of a message whose processing failed with the status: transient failures are
+/// redelivered (up to the consumer's `max_deliver`), other failures (e.g. an invalid request)
+/// cannot succeed on a retry and are terminated
+pub(crate) fn error_outcome(status: Status) -> AckOutcome {
+    match status {
+        Status::Ok => AckOutcome::Ack,
+        Status::Cancelled
+        | Status::Unknown
+        | Status::DeadlineExceeded
+        | Status::ResourceExhausted
+        | Status::Aborted
+        | Status::Internal
+        | Status::Unavailable
+        | Status::DatabaseError
+        | Status::MessagingError => AckOutcome::Nak(None),
+        Status::InvalidArgument
+        | Status::NotFound
+        | Status::AlreadyExists
+        | Status::PermissionDenied
+        | Status::FailedPrecondition
+        | Status::OutOfRange
+        | Status::Unimplemented
+        | Status::DataLoss
+        | Status::Unauthenticated => AckOutcome::Term,
+    }
+}
+
+tokio::task_local! {
+    /// Outcome reported by the core handler run by [ack_on_completion], Ack when none is reported
+    static REPORTED_OUTCOME: Cell<Option<AckOutcome>>;
+}
+
+/// Reports the outcome of the message being processed, when the handler runs on a
+/// [JetStreamReceiver](super::JetStreamReceiver) with a core handler signature (e.g. a
+/// [json_handler](super::json_handler)). Does nothing on other receivers.
+pub(crate) fn report_outcome(outcome: AckOutcome) {
+    let _ = REPORTED_OUTCOME.try_with(|reported| reported.set(Some(outcome)));
+}
+
+/// Type-erased JetStream subscription callback, returning the [AckOutcome] of each message
+pub type OutcomeHandler = Arc<dyn Fn(Message) -> BoxFuture<'static, AckOutcome> + Send + Sync>;
+
+/// Wraps an async callback returning an [AckOutcome] into an [OutcomeHandler]
+pub fn outcome_handler<F, Fut>(proc: F) -> OutcomeHandler
+where
+    F: Fn(Message) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = AckOutcome> + Send + 'static,
+{
+    Arc::new(move |message| proc(message).boxed())
+}
+
+/// Turns an [OutcomeHandler] into a JetStream message handler that acknowledges each
+/// message with the outcome of the handler. While the handler runs, progress is reported
+/// every half `ack_wait`, so the message is not redelivered to another subscriber meanwhile.
+pub(crate) fn acking(handler: OutcomeHandler, ack_wait: Duration) -> Handler<jetstream::Message> {
+    let interval = ack_wait / 2;
+
+    Arc::new(move |message: jetstream::Message| {
+        let processing = handler(message.message.clone());
+
+        async move {
+            let outcome = with_progress(processing, interval, || async {
+                if let Err(err) = message.ack_with(AckKind::Progress).await {
+                    tracing::warn!(
+                        subject = %message.message.subject,
+                        error = %err,
+                        "failed to report JetStream message progress"
+                    );
+                }
+            })
+            .await;
+
+            // a lost acknowledgement only results in the message being redelivered
+            if let Err(err) = message.ack_with(outcome.into()).await {
+                tracing::warn!(
+                    subject = %message.message.subject,
+                    error = %err,
+                    "failed to acknowledge JetStream message"
+                );
+            }
+        }
+        .boxed()
+    })
+}
+
+/// Runs the future, calling `progress` every `interval` until it completes
+pub(crate) async fn with_progress<T, P, PFut>(
+    future: impl Future<Output = T>,
+    interval: Duration,
+    mut progress: P,
+) -> T
+where
+    P: FnMut() -> PFut,
+    PFut: Future<Output = ()>,
+{
+    tokio::pin!(future);
+
+    let mut ticks = tokio::time::interval_at(Instant::now() + interval, interval);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            output = &mut future => return output,
+            _ = ticks.tick() => progress().await,
+        }
+    }
+}
+
+/// Runs an [OutcomeHandler] where messages are not acknowledged, discarding the outcome
+pub(crate) fn ignore_outcome(handler: OutcomeHandler) -> MessageHandler {
+    Arc::new(move |message: Message| handler(message).map(|_| ()).boxed())
+}
+
+/// Acknowledges messages processed by a core [MessageHandler] once it completes, with the
+/// outcome reported by the handler (see [report_outcome]) or [AckOutcome::Ack].
+///
+/// The reply subject of a JetStream message is its ack subject, so it is removed from the
+/// message: a handler replying to requests would otherwise acknowledge the message with its reply.
+pub(crate) fn ack_on_completion(handler: MessageHandler) -> OutcomeHandler {
+    Arc::new(move |mut message: Message| {
+        message.reply = None;
+        let processing = handler(message);
+
+        REPORTED_OUTCOME
+            .scope(Cell::new(None), async move {
+                processing.await;
+                REPORTED_OUTCOME.with(Cell::get).unwrap_or(AckOutcome::Ack)
+            })
+            .boxed()
+    })
+}
+
+#[cfg(test)]
+#[path = "./ack_tests.rs"]
+mod ack_tests;
@@ -0,0 +1,166 @@
+#[cfg(test)]
+mod ack_tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use async_nats::{jetstream::AckKind, Message};
+    use bytes::Bytes;
+
+    use crate::{
+        error::{ErrorModel, ErrorReason, Status, ToErrorModel},
+        request::NatsEnvelope,
+        server::receiver::{
+            ack::{ack_on_completion, error_outcome, with_progress},
+            handler::process_json,
+            message_handler, test_message, AckOutcome, OutcomeHandler,
+        },
+    };
+
+    fn jetstream_message(payload: Bytes) -> Message {
+        test_message(
+            "chat.chatgroup.event.created",
+            Some("$JS.ACK.CHAT.persist.1.1.1.0.0"),
+            payload,
+        )
+    }
+
+    fn ack_payload(outcome: AckOutcome) -> Bytes {
+        AckKind::from(outcome).into()
+    }
+
+    #[test]
+    fn test_ack_kinds() {
+        assert_eq!(ack_payload(AckOutcome::Ack), Bytes::from_static(b"+ACK"));
+        assert_eq!(
+            ack_payload(AckOutcome::Nak(None)),
+            Bytes::from_static(b"-NAK")
+        );
+        assert_eq!(
+            ack_payload(AckOutcome::Nak(Some(Duration::from_secs(2)))),
+            Bytes::from_static(b"-NAK {\"delay\":2000000000}")
+        );
+        assert_eq!(ack_payload(AckOutcome::Term), Bytes::from_static(b"+TERM"));
+        assert_eq!(
+            ack_payload(AckOutcome::InProgress),
+            Bytes::from_static(b"+WPI")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_progress_is_reported_while_processing() {
+        let progress = AtomicUsize::new(0);
+
+        let output = with_progress(
+            async {
+                tokio::time::sleep(Duration::from_millis(250)).await;
+                "done"
+            },
+            Duration::from_millis(100),
+            || async {
+                progress.fetch_add(1, Ordering::SeqCst);
+            },
+        )
+        .await;
+
+        assert_eq!(output, "done");
+        assert_eq!(progress.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_no_progress_for_quick_processing() {
+        let progress = AtomicUsize::new(0);
+
+        with_progress(async {}, Duration::from_millis(100), || async {
+            progress.fetch_add(1, Ordering::SeqCst);
+        })
+        .await;
+
+        assert_eq!(progress.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_core_handler_is_acked_on_completion() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+
+        let handler = ack_on_completion(message_handler(move |_msg: Message| {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        }));
+
+        assert_eq!(
+            handler(jetstream_message(Bytes::new())).await,
+            AckOutcome::Ack
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    struct Unavailable;
+
+    impl ToErrorModel<ErrorReason> for Unavailable {
+        fn to_error_model(&self, _: Option<i64>, _: Option<String>) -> ErrorModel<ErrorReason> {
+            ErrorModel::new(self.status(), self.error_code(), self.msg())
+        }
+
+        fn msg(&self) -> String {
+            "Database unavailable".to_string()
+        }
+
+        fn error_code(&self) -> i32 {
+            503
+        }
+
+        fn status(&self) -> Status {
+            Status::Unavailable
+        }
+    }
+
+    async fn echo(request: NatsEnvelope<String>) -> Result<String, Unavailable> {
+        match request.data.as_str() {
+            "unavailable" => Err(Unavailable),
+            _ => Ok(request.data),
+        }
+    }
+
+    /// Core handler replying to requests, run on a JetStream message
+    fn replying_handler() -> OutcomeHandler {
+        ack_on_completion(message_handler(|message: Message| async move {
+            // the ack subject is not exposed as a reply subject
+            assert!(message.reply.is_none());
+            assert!(
+                process_json::<String, String, ErrorReason, _, _, _>(&echo, message)
+                    .await
+                    .is_none()
+            );
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_core_handler_errors_are_not_acked() {
+        let handler = replying_handler();
+
+        let ok = jetstream_message(Bytes::from_static(b"\"hello\""));
+        assert_eq!(handler(ok).await, AckOutcome::Ack);
+
+        let invalid = jetstream_message(Bytes::from_static(b"not json"));
+        assert_eq!(handler(invalid).await, AckOutcome::Term);
+
+        let unavailable = jetstream_message(Bytes::from_static(b"\"unavailable\""));
+        assert_eq!(handler(unavailable).await, AckOutcome::Nak(None));
+    }
+
+    #[test]
+    fn test_error_outcomes() {
+        assert_eq!(error_outcome(Status::Unavailable), AckOutcome::Nak(None));
+        assert_eq!(error_outcome(Status::DatabaseError), AckOutcome::Nak(None));
+        assert_eq!(error_outcome(Status::InvalidArgument), AckOutcome::Term);
+        assert_eq!(error_outcome(Status::Unimplemented), AckOutcome::Term);
+    }
+}
@@ -0,0 +1,107 @@
+use std::{sync::Arc, time::Duration};
+
+use serde::Deserialize;
+
+use crate::server::NatsServer;
+
+use super::{JetStreamReceiver, NatsReceiver, Subscribe};
+
+/// How the messages of a subscription are delivered, so a service can switch a subject
+/// from ephemeral to durable delivery through its configuration.
+///
+/// ```
+/// use nats_transport::server::receiver::Delivery;
+///
+/// let delivery: Delivery = serde_json::from_str(
+///     r#"{ "delivery": "durable", "stream": "CHAT", "durable_name": "chat-persist", "max_deliver": 5 }"#,
+/// )
+/// .unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "delivery", rename_all = "snake_case")]
+pub enum Delivery {
+    /// Core NATS subscription: messages published while no subscriber is online are lost
+    Ephemeral,
+
+    /// JetStream durable consumer: messages are stored and redelivered until acknowledged
+    Durable(DurableConsumerConfig),
+}
+
+impl Delivery {
+    /// Returns the receiver matching the delivery
+    pub fn receiver(&self, nats_server: Arc<NatsServer>) -> Arc<dyn Subscribe> {
+        match self {
+            Delivery::Ephemeral => Arc::new(NatsReceiver::new(nats_server)),
+            Delivery::Durable(consumer) => {
+                Arc::new(JetStreamReceiver::new(nats_server, consumer.clone()))
+            }
+        }
+    }
+}
+
+/// Whether the durable consumer pulls messages in batches, or the server pushes them
+/// to a delivery subject
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsumerMode {
+    #[default]
+    Pull,
+    Push,
+}
+
+/// Durable JetStream consumer used by a [JetStreamReceiver]. The consumer is created on the
+/// stream when it does not exist yet, filtered on the subscribed subject.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct DurableConsumerConfig {
+    /// Name of the stream storing the subscribed subject
+    pub stream: String,
+
+    /// Name of the consumer. Service replicas sharing the name share the messages.
+    pub durable_name: String,
+
+    #[serde(default)]
+    pub mode: ConsumerMode,
+
+    /// Maximum number of delivery attempts of a message (unlimited by default)
+    #[serde(default)]
+    pub max_deliver: Option<i64>,
+
+    /// Time allowed to acknowledge a message before it is redelivered (30s by default)
+    #[serde(default)]
+    pub ack_wait_ms: Option<u64>,
+}
+
+impl DurableConsumerConfig {
+    pub fn new(stream: impl Into<String>, durable_name: impl Into<String>) -> Self {
+        Self {
+            stream: stream.into(),
+            durable_name: durable_name.into(),
+            mode: ConsumerMode::default(),
+            max_deliver: None,
+            ack_wait_ms: None,
+        }
+    }
+
+    pub fn mode(mut self, mode: ConsumerMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn max_deliver(mut self, max_deliver: i64) -> Self {
+        self.max_deliver = Some(max_deliver);
+        self
+    }
+
+    pub fn ack_wait(mut self, ack_wait: Duration) -> Self {
+        self.ack_wait_ms = Some(ack_wait.as_millis() as u64);
+        self
+    }
+
+    pub(crate) fn ack_wait_duration(&self) -> Option<Duration> {
+        self.ack_wait_ms.map(Duration::from_millis)
+    }
+}
+
+#[cfg(test)]
+#[path = "./consumer_config_tests.rs"]
+mod consumer_config_tests;
@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod consumer_config_tests {
+    use std::time::Duration;
+
+    use crate::server::receiver::{ConsumerMode, Delivery, DurableConsumerConfig};
+
+    #[test]
+    fn test_deserialize_ephemeral_delivery() {
+        let delivery: Delivery = serde_json::from_str(r#"{ "delivery": "ephemeral" }"#).unwrap();
+
+        assert_eq!(delivery, Delivery::Ephemeral);
+    }
+
+    #[test]
+    fn test_deserialize_durable_delivery() {
+        let delivery: Delivery = serde_json::from_str(
+            r#"{
+                "delivery": "durable",
+                "stream": "CHAT",
+                "durable_name": "chat-persist",
+                "mode": "push",
+                "max_deliver": 5,
+                "ack_wait_ms": 10000
+            }"#,
+        )
+        .unwrap();
+
+        let expected = DurableConsumerConfig::new("CHAT", "chat-persist")
+            .mode(ConsumerMode::Push)
+            .max_deliver(5)
+            .ack_wait(Duration::from_secs(10));
+
+        assert_eq!(delivery, Delivery::Durable(expected));
+    }
+
+    #[test]
+    fn test_durable_defaults() {
+        let config: DurableConsumerConfig =
+            serde_json::from_str(r#"{ "stream": "CHAT", "durable_name": "chat-persist" }"#)
+                .unwrap();
+
+        assert_eq!(config.mode, ConsumerMode::Pull);
+        assert_eq!(config.max_deliver, None);
+        assert_eq!(config.ack_wait_duration(), None);
+    }
+}
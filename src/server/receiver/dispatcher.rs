@@ -0,0 +1,216 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    panic::AssertUnwindSafe,
+    sync::{Arc, Mutex},
+};
+
+use async_nats::{jetstream, Message};
+use futures::{future::BoxFuture, FutureExt};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use super::{OrderingKey, SubscribeOptions};
+
+/// Messages buffered per unit of concurrency by an ordered dispatcher, e.g. 1024 messages
+/// waiting for their key with a concurrency of 16
+const PENDING_PER_CONCURRENCY: usize = 64;
+
+/// Messages waiting for the message of the same key being processed, by key. A key has a
+/// queue while a worker processes its messages.
+type KeyQueues<M> = Mutex<HashMap<String, VecDeque<(M, OwnedSemaphorePermit)>>>;
+
+/// Callback processing a dispatched message, a [MessageHandler](super::MessageHandler) for
+/// core NATS messages
+pub(crate) type Handler<M> = Arc<dyn Fn(M) -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// Message received by a subscription: a core NATS message, or a JetStream message that
+/// is acknowledged once processed
+pub(crate) trait Dispatch: Send + 'static {
+    fn subject(&self) -> &str;
+}
+
+impl Dispatch for Message {
+    fn subject(&self) -> &str {
+        self.subject.as_ref()
+    }
+}
+
+impl Dispatch for jetstream::Message {
+    fn subject(&self) -> &str {
+        self.message.subject.as_ref()
+    }
+}
+
+/// Runs the subscription callback for each received message, according to the
+/// concurrency and ordering of the [SubscribeOptions].
+pub(crate) enum Dispatcher<M = Message> {
+    /// Processes one message at a time on the receiver task
+    Sequential(Handler<M>),
+
+    /// Processes up to `limit` messages in parallel, in no particular order
+    Concurrent {
+        proc: Handler<M>,
+        limit: u32,
+        semaphore: Arc<Semaphore>,
+    },
+
+    /// Queues messages by key, processing the messages of each key in order and up to the
+    /// concurrency limit of keys in parallel. A slow key only holds back its own messages, until
+    /// `pending_limit` messages are waiting in total.
+    Ordered {
+        proc: Handler<M>,
+        key: OrderingKey,
+        queues: Arc<KeyQueues<M>>,
+        semaphore: Arc<Semaphore>,
+        pending_limit: u32,
+        pending: Arc<Semaphore>,
+    },
+}
+
+impl<M: Dispatch> Dispatcher<M> {
+    pub fn new(options: &SubscribeOptions, proc: Handler<M>) -> Self {
+        // bounded by SubscribeOptions::concurrency, so the permits fit in a u32
+        let limit = options.concurrency as u32;
+
+        match options.ordering {
+            Some(key) => {
+                let pending_limit = limit * PENDING_PER_CONCURRENCY as u32;
+
+                Dispatcher::Ordered {
+                    proc,
+                    key,
+                    queues: Arc::new(Mutex::new(HashMap::new())),
+                    semaphore: Arc::new(Semaphore::new(limit as usize)),
+                    pending_limit,
+                    pending: Arc::new(Semaphore::new(pending_limit as usize)),
+                }
+            }
+            None if limit > 1 => Dispatcher::Concurrent {
+                proc,
+                limit,
+                semaphore: Arc::new(Semaphore::new(limit as usize)),
+            },
+            None => Dispatcher::Sequential(proc),
+        }
+    }
+
+    /// Dispatches the message, waiting while the concurrency limit (or the pending messages
+    /// limit when ordered) is reached
+    pub async fn dispatch(&mut self, message: M) {
+        match self {
+            Dispatcher::Sequential(proc) => proc(message).await,
+            Dispatcher::Concurrent {
+                proc, semaphore, ..
+            } => {
+                let permit = semaphore
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .expect("dispatcher semaphore is never closed");
+                // the handler is called on the receiver task, e.g. so that a service is called
+                // while it is still ready
+                let processing = proc(message);
+
+                tokio::spawn(async move {
+                    processing.await;
+                    drop(permit);
+                });
+            }
+            Dispatcher::Ordered {
+                proc,
+                key,
+                queues,
+                semaphore,
+                pending,
+                ..
+            } => {
+                let pending = pending
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .expect("dispatcher semaphore is never closed");
+                let key = key.key(message.subject()).to_string();
+
+                {
+                    let mut queues = queues.lock().expect("dispatcher queues are never poisoned");
+                    if let Some(queue) = queues.get_mut(&key) {
+                        // the worker of the key picks it up after the messages before it
+                        queue.push_back((message, pending));
+                        return;
+                    }
+                    queues.insert(key.clone(), VecDeque::from([(message, pending)]));
+                }
+
+                tokio::spawn(process_key(
+                    proc.clone(),
+                    key,
+                    queues.clone(),
+                    semaphore.clone(),
+                ));
+            }
+        }
+    }
+
+    /// Waits for the messages that are being processed to complete
+    pub async fn finish(self) {
+        match self {
+            Dispatcher::Sequential(_) => {}
+            Dispatcher::Concurrent {
+                limit, semaphore, ..
+            } => {
+                // every permit is available again once all in-flight messages are processed
+                let _ = semaphore.acquire_many(limit).await;
+            }
+            Dispatcher::Ordered {
+                pending_limit,
+                pending,
+                ..
+            } => {
+                // a message releases its pending permit once processed
+                let _ = pending.acquire_many(pending_limit).await;
+            }
+        }
+    }
+}
+
+/// Processes the queued messages of the key in order, removing its queue once empty.
+/// A panicking handler only loses its message: the worker goes on with the next messages
+/// of the key, so that its queue and their pending permits are not leaked.
+async fn process_key<M: Dispatch>(
+    proc: Handler<M>,
+    key: String,
+    queues: Arc<KeyQueues<M>>,
+    semaphore: Arc<Semaphore>,
+) {
+    loop {
+        let next = {
+            let mut queues = queues.lock().expect("dispatcher queues are never poisoned");
+            let next = queues.get_mut(&key).and_then(VecDeque::pop_front);
+            if next.is_none() {
+                queues.remove(&key);
+            }
+            next
+        };
+
+        let Some((message, pending)) = next else {
+            return;
+        };
+
+        let permit = semaphore
+            .acquire()
+            .await
+            .expect("dispatcher semaphore is never closed");
+        if AssertUnwindSafe(async { proc(message).await })
+            .catch_unwind()
+            .await
+            .is_err()
+        {
+            tracing::error!(key = %key, "message handler panicked");
+        }
+        drop(permit);
+        drop(pending);
+    }
+}
+
+#[cfg(test)]
+#[path = "./dispatcher_tests.rs"]
+mod dispatcher_tests;
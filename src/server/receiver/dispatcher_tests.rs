@@ -0,0 +1,219 @@
+#[cfg(test)]
+mod dispatcher_tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        time::Duration,
+    };
+
+    use async_nats::Message;
+
+    use crate::server::receiver::{
+        dispatcher::Dispatcher, message_handler, test_message, SubscribeOptions, MAX_CONCURRENCY,
+    };
+
+    #[tokio::test]
+    async fn test_concurrency_limit() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        let processed = Arc::new(AtomicUsize::new(0));
+
+        let proc = {
+            let (running, max_running, processed) =
+                (running.clone(), max_running.clone(), processed.clone());
+            move |_message: Message| {
+                let (running, max_running, processed) =
+                    (running.clone(), max_running.clone(), processed.clone());
+                async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    processed.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        };
+
+        let mut dispatcher = Dispatcher::new(
+            &SubscribeOptions::new().concurrency(3),
+            message_handler(proc),
+        );
+        for _ in 0..12 {
+            dispatcher
+                .dispatch(test_message("chat.topic.1", None, "msg"))
+                .await;
+        }
+        dispatcher.finish().await;
+
+        assert_eq!(processed.load(Ordering::SeqCst), 12);
+        assert!(max_running.load(Ordering::SeqCst) <= 3);
+        assert!(max_running.load(Ordering::SeqCst) > 1);
+    }
+
+    #[tokio::test]
+    async fn test_ordered_by_subject_token() {
+        let received = Arc::new(Mutex::new(Vec::<(String, String)>::new()));
+
+        let proc = {
+            let received = received.clone();
+            move |message: Message| {
+                let received = received.clone();
+                async move {
+                    // later messages of other chats may overtake, but never of the same chat
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                    let chat_id = message.subject.split('.').nth(2).unwrap().to_string();
+                    let payload = String::from_utf8(message.payload.to_vec()).unwrap();
+                    received.lock().unwrap().push((chat_id, payload));
+                }
+            }
+        };
+
+        let options = SubscribeOptions::new()
+            .concurrency(4)
+            .ordered_by_subject_token(2);
+        let mut dispatcher = Dispatcher::new(&options, message_handler(proc));
+
+        let payloads = ["1", "2", "3", "4", "5"];
+        for payload in payloads {
+            for chat_id in ["a", "b", "c"] {
+                let subject = format!("chat.topic.{}.message", chat_id);
+                dispatcher
+                    .dispatch(test_message(&subject, None, payload))
+                    .await;
+            }
+        }
+        dispatcher.finish().await;
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 15);
+
+        for chat_id in ["a", "b", "c"] {
+            let chat_payloads: Vec<&str> = received
+                .iter()
+                .filter(|(id, _)| id == chat_id)
+                .map(|(_, payload)| payload.as_str())
+                .collect();
+            assert_eq!(chat_payloads, payloads);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ordered_slow_key_does_not_block_other_keys() {
+        let (release, released) = tokio::sync::oneshot::channel::<()>();
+        let released = Arc::new(tokio::sync::Mutex::new(Some(released)));
+        let processed = Arc::new(Mutex::new(Vec::<String>::new()));
+
+        let proc = {
+            let (released, processed) = (released.clone(), processed.clone());
+            move |message: Message| {
+                let (released, processed) = (released.clone(), processed.clone());
+                async move {
+                    let payload = String::from_utf8(message.payload.to_vec()).unwrap();
+                    if payload == "slow" {
+                        let released = released.lock().await.take().unwrap();
+                        let _ = released.await;
+                    }
+                    processed.lock().unwrap().push(payload);
+                }
+            }
+        };
+
+        let options = SubscribeOptions::new()
+            .concurrency(2)
+            .ordered_by_subject_token(2);
+        let mut dispatcher = Dispatcher::new(&options, message_handler(proc));
+
+        dispatcher
+            .dispatch(test_message("chat.topic.a", None, "slow"))
+            .await;
+        for _ in 0..3 {
+            dispatcher
+                .dispatch(test_message("chat.topic.a", None, "a"))
+                .await;
+        }
+        for _ in 0..20 {
+            dispatcher
+                .dispatch(test_message("chat.topic.b", None, "b"))
+                .await;
+        }
+
+        // the messages of chat b are processed while chat a is stuck on its first message
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while processed.lock().unwrap().len() < 20 {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert!(processed
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|payload| payload == "b"));
+
+        release.send(()).unwrap();
+        dispatcher.finish().await;
+
+        let processed = processed.lock().unwrap();
+        assert_eq!(processed.len(), 24);
+        assert_eq!(processed[20..], ["slow", "a", "a", "a"]);
+    }
+
+    #[tokio::test]
+    async fn test_ordered_handler_panic() {
+        let processed = Arc::new(Mutex::new(Vec::<String>::new()));
+
+        let proc = {
+            let processed = processed.clone();
+            move |message: Message| {
+                let processed = processed.clone();
+                async move {
+                    let payload = String::from_utf8(message.payload.to_vec()).unwrap();
+                    if payload == "panic" {
+                        panic!("handler panic");
+                    }
+                    processed.lock().unwrap().push(payload);
+                }
+            }
+        };
+
+        let options = SubscribeOptions::new()
+            .concurrency(2)
+            .ordered_by_subject_token(2);
+        let mut dispatcher = Dispatcher::new(&options, message_handler(proc));
+
+        for payload in ["1", "panic", "2", "3"] {
+            dispatcher
+                .dispatch(test_message("chat.topic.a", None, payload))
+                .await;
+        }
+
+        // the worker of the key goes on after the panic and releases every pending permit
+        tokio::time::timeout(Duration::from_secs(1), dispatcher.finish())
+            .await
+            .unwrap();
+        assert_eq!(*processed.lock().unwrap(), ["1", "2", "3"]);
+    }
+
+    #[test]
+    fn test_options() {
+        let options = SubscribeOptions::new()
+            .queue_group("chat-persist")
+            .concurrency(0)
+            .ordered_by_subject_token(2);
+
+        assert_eq!(options.queue_group, Some("chat-persist".to_string()));
+        assert_eq!(options.concurrency, 1);
+        assert_eq!(
+            SubscribeOptions::new().concurrency(usize::MAX).concurrency,
+            MAX_CONCURRENCY
+        );
+        assert_eq!(
+            options.ordering.unwrap().key("chat.topic.1234.message"),
+            "1234"
+        );
+        assert_eq!(options.ordering.unwrap().key("chat.topic"), "");
+    }
+}
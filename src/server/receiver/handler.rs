@@ -0,0 +1,411 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_nats::{HeaderMap, Message};
+use bytes::Bytes;
+use chat_proto::runtiva::nats::v1 as proto_nats;
+use futures::{future::BoxFuture, Future, FutureExt};
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::ack::{error_outcome, report_outcome};
+use crate::{
+    error::{ErrorModel, Status, ToErrorModel},
+    request::{Converter, NatsEnvelope, RequestHeaders, TryFromNatsRequest},
+    response::{NatsResponse, ERROR_DETAILS_HEADER, ERROR_REPLY_HEADER},
+    server::{
+        serde::{Deserializer, NatsJson, NatsMessageSerde, Serde, Serializer},
+        NatsServer,
+    },
+};
+
+/// Wraps a typed JSON handler into a subscription callback for [Subscribe](super::Subscribe).
+///
+/// Each message payload is decoded into `Req` and passed to the handler, along with the native
+/// NATS message headers as [RequestHeaders]. When the message was sent as a request, the handler
+/// result is published back to the reply subject as a [NatsResponse]. Errors are converted with
+/// [ToErrorModel], using the requestor id from the headers and the message subject as the request.
+/// Requests carrying a [DEADLINE_HEADER](crate::request::DEADLINE_HEADER) are replied with a
+/// `DeadlineExceeded` error instead once their deadline passed, and the handler is cancelled.
+///
+/// ```ignore
+/// let receiver = NatsReceiver::new(nats.clone());
+/// receiver
+///     .subscribe(
+///         "chat.chatgroup.command.create".to_string(),
+///         json_handler(nats, create_chat_group),
+///     )
+///     .await?;
+///
+/// async fn create_chat_group(
+///     request: NatsEnvelope<CreateChatGroupRequest>,
+/// ) -> Result<ChatGroup, ChatGroupError> {
+///     ...
+/// }
+/// ```
+pub fn json_handler<Req, Resp, R, E, H, Fut>(
+    nats_server: Arc<NatsServer>,
+    handler: H,
+) -> impl Fn(Message) -> BoxFuture<'static, ()> + Send + Sync + 'static
+where
+    Req: DeserializeOwned + Send + 'static,
+    Resp: Serialize + Send + 'static,
+    R: Serialize + Send + 'static,
+    E: ToErrorModel<R> + Send + 'static,
+    H: Fn(NatsEnvelope<Req>) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Resp, E>> + Send + 'static,
+{
+    let handler = Arc::new(handler);
+
+    move |message: Message| {
+        let nats_server = nats_server.clone();
+        let handler = handler.clone();
+
+        async move {
+            if let Some(reply) = process_json(handler.as_ref(), message).await {
+                reply.send(&nats_server).await;
+            }
+        }
+        .boxed()
+    }
+}
+
+/// Wraps a typed protobuf handler into a subscription callback for [Subscribe](super::Subscribe).
+///
+/// Each message payload is decoded through the [Converter] and passed to the handler. Native NATS
+/// message headers are merged into the headers embedded in the request. When the message was
+/// sent as a request, the handler result is published back to the reply subject: the response
+/// message on success, or a `proto_nats::ErrorReply` flagged with the [ERROR_REPLY_HEADER]
+/// header on failure.
+pub fn prost_handler<Msg, OutMsg, S, Resp, R, E, H, Fut>(
+    nats_server: Arc<NatsServer>,
+    converter: Converter<Msg, OutMsg, S>,
+    handler: H,
+) -> impl Fn(Message) -> BoxFuture<'static, ()> + Send + Sync + 'static
+where
+    Msg: TryFromNatsRequest<OutMsg> + Debug + Send + Sync + 'static,
+    <Msg as TryFromNatsRequest<OutMsg>>::Error: std::error::Error + Send + Sync + 'static,
+    OutMsg: prost::Message + Send + Sync + 'static,
+    S: Serde<OutMsg> + Send + Sync + 'static,
+    <S as Deserializer<OutMsg>>::Error: std::error::Error + Send + Sync + 'static,
+    Resp: prost::Message + Default + Send + 'static,
+    R: ToString + Send + 'static,
+    E: ToErrorModel<R> + Send + 'static,
+    H: Fn(NatsEnvelope<Msg>) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Resp, E>> + Send + 'static,
+{
+    let converter = Arc::new(converter);
+    let handler = Arc::new(handler);
+
+    move |message: Message| {
+        let nats_server = nats_server.clone();
+        let converter = converter.clone();
+        let handler = handler.clone();
+
+        async move {
+            if let Some(reply) = process_prost(converter.as_ref(), handler.as_ref(), message).await
+            {
+                reply.send(&nats_server).await;
+            }
+        }
+        .boxed()
+    }
+}
+
+/// Reply to be published for a request message
+#[derive(Debug)]
+pub(crate) struct Reply {
+    pub subject: String,
+    pub headers: Option<HeaderMap>,
+    pub payload: Bytes,
+}
+
+impl Reply {
+    pub(crate) async fn send(self, nats_server: &NatsServer) {
+        let client = nats_server.client();
+
+        let result = match self.headers {
+            Some(headers) => {
+                client
+                    .publish_with_headers(self.subject.clone(), headers, self.payload)
+                    .await
+            }
+            None => client.publish(self.subject.clone(), self.payload).await,
+        };
+
+        // A failed reply cannot be reported back to the requestor, who will time out instead
+        if let Err(err) = result {
+            tracing::warn!(subject = %self.subject, error = %err, "failed to publish reply");
+        }
+    }
+}
+
+/// Decodes the message, runs the handler and builds the JSON reply (if the message expects one)
+pub(crate) async fn process_json<Req, Resp, R, E, H, Fut>(
+    handler: &H,
+    message: Message,
+) -> Option<Reply>
+where
+    Req: DeserializeOwned,
+    Resp: Serialize,
+    R: Serialize,
+    E: ToErrorModel<R>,
+    H: Fn(NatsEnvelope<Req>) -> Fut,
+    Fut: Future<Output = Result<Resp, E>>,
+{
+    let request = message.subject.to_string();
+
+    let response: NatsResponse<Resp, R> =
+        match json_request::<Req, R>(message.headers.as_ref(), message.payload) {
+            Ok(envelope) => {
+                let requestor = envelope.headers.requestor();
+                let remaining = envelope.remaining();
+
+                match within_deadline(remaining, || handler(envelope)).await {
+                    Ok(Ok(data)) => NatsResponse::new(data),
+                    Ok(Err(err)) => NatsResponse::with_error(err, requestor, Some(request)),
+                    Err(model) => NatsResponse {
+                        error: Some(model),
+                        data: None,
+                    },
+                }
+            }
+            Err(model) => NatsResponse {
+                error: Some(model),
+                data: None,
+            },
+        };
+
+    let reply = reply_subject(&message.subject, message.reply, response.error.as_ref())?;
+    json_reply(reply, response)
+}
+
+/// Reply subject of a processed message. An error is reported as the outcome of the message
+/// (see [report_outcome]), and logged when there is no reply subject to send it to.
+pub(crate) fn reply_subject<R>(
+    subject: &str,
+    reply: Option<impl ToString>,
+    error: Option<&ErrorModel<R>>,
+) -> Option<String> {
+    if let Some(model) = error {
+        report_outcome(error_outcome(model.status));
+        if reply.is_none() {
+            log_unreplied(subject, model);
+        }
+    }
+
+    reply.map(|reply| reply.to_string())
+}
+
+/// Logs the error of a message without a reply subject (e.g. an event), which is lost otherwise
+fn log_unreplied<R>(subject: &str, model: &ErrorModel<R>) {
+    tracing::warn!(
+        subject,
+        status = %model.status,
+        error = %model.message,
+        "failed to process message without a reply subject"
+    );
+}
+
+/// Decodes a JSON request payload, along with the native NATS message headers
+pub(crate) fn json_request<Req, R>(
+    headers: Option<&HeaderMap>,
+    payload: Bytes,
+) -> Result<NatsEnvelope<Req>, ErrorModel<R>>
+where
+    Req: DeserializeOwned,
+{
+    let headers = headers.map(RequestHeaders::from).unwrap_or_default();
+
+    NatsJson::<Req>::default()
+        .deserialize(payload)
+        .map(|data| NatsEnvelope::new(headers, data))
+        .map_err(invalid_request)
+}
+
+/// Serializes the JSON reply to the reply subject
+pub(crate) fn json_reply<Resp, R>(reply: String, response: NatsResponse<Resp, R>) -> Option<Reply>
+where
+    Resp: Serialize,
+    R: Serialize,
+{
+    let serde = NatsJson::<NatsResponse<Resp, R>>::default();
+
+    // a response that cannot be serialized is replaced by an internal error,
+    // the requestor times out if even that one fails
+    let payload = match serde.serialize(response) {
+        Ok(payload) => payload,
+        Err(err) => serde
+            .serialize(NatsResponse {
+                error: Some(unserializable_response(err)),
+                data: None,
+            })
+            .ok()?,
+    };
+
+    Some(Reply {
+        subject: reply,
+        headers: None,
+        payload,
+    })
+}
+
+/// Decodes the message, runs the handler and builds the protobuf reply (if the message expects one)
+pub(crate) async fn process_prost<Msg, OutMsg, S, Resp, R, E, H, Fut>(
+    converter: &Converter<Msg, OutMsg, S>,
+    handler: &H,
+    message: Message,
+) -> Option<Reply>
+where
+    Msg: TryFromNatsRequest<OutMsg> + Debug,
+    <Msg as TryFromNatsRequest<OutMsg>>::Error: std::error::Error + Send + Sync + 'static,
+    OutMsg: prost::Message,
+    S: Serde<OutMsg>,
+    <S as Deserializer<OutMsg>>::Error: std::error::Error + Send + Sync + 'static,
+    Resp: prost::Message + Default,
+    R: ToString,
+    E: ToErrorModel<R>,
+    H: Fn(NatsEnvelope<Msg>) -> Fut,
+    Fut: Future<Output = Result<Resp, E>>,
+{
+    let request = message.subject.to_string();
+
+    let result: Result<Resp, ErrorModel<R>> =
+        match prost_request(converter, message.headers.as_ref(), message.payload) {
+            Ok(envelope) => {
+                let requestor = envelope.headers.requestor();
+                let remaining = envelope.remaining();
+
+                within_deadline(remaining, || handler(envelope))
+                    .await
+                    .and_then(|result| {
+                        result.map_err(|err| err.to_error_model(requestor, Some(request)))
+                    })
+            }
+            Err(model) => Err(model),
+        };
+
+    let reply = reply_subject(&message.subject, message.reply, result.as_ref().err())?;
+    Some(prost_reply(reply, result))
+}
+
+/// Decodes a protobuf request payload through the [Converter]. Native NATS message headers are
+/// merged into the headers embedded in the request, which take precedence.
+pub(crate) fn prost_request<Msg, OutMsg, S, R>(
+    converter: &Converter<Msg, OutMsg, S>,
+    headers: Option<&HeaderMap>,
+    payload: Bytes,
+) -> Result<NatsEnvelope<Msg>, ErrorModel<R>>
+where
+    Msg: TryFromNatsRequest<OutMsg> + Debug,
+    <Msg as TryFromNatsRequest<OutMsg>>::Error: std::error::Error + Send + Sync + 'static,
+    OutMsg: prost::Message,
+    S: Serde<OutMsg>,
+    <S as Deserializer<OutMsg>>::Error: std::error::Error + Send + Sync + 'static,
+{
+    let mut envelope = converter.convert(payload).map_err(invalid_request)?;
+
+    if let Some(headers) = headers {
+        envelope.headers.merge(headers.into());
+    }
+
+    Ok(envelope)
+}
+
+/// Encodes the protobuf reply to the reply subject: the response message, or a
+/// `proto_nats::ErrorReply` flagged with the [ERROR_REPLY_HEADER] header
+pub(crate) fn prost_reply<Resp, R>(reply: String, result: Result<Resp, ErrorModel<R>>) -> Reply
+where
+    Resp: prost::Message + Default,
+    R: ToString,
+{
+    let (headers, payload) = match result {
+        Ok(response) => (
+            None,
+            NatsMessageSerde::<Resp>::default()
+                .serialize(response)
+                .unwrap_or_else(|never| match never {}),
+        ),
+        Err(model) => {
+            let (headers, payload) = error_reply(model);
+            (Some(headers), payload)
+        }
+    };
+
+    Reply {
+        subject: reply,
+        headers,
+        payload,
+    }
+}
+
+/// Encodes the model as a `proto_nats::ErrorReply` flagged with the [ERROR_REPLY_HEADER]
+/// header, with its typed details in the [ERROR_DETAILS_HEADER] header
+pub(crate) fn error_reply<R>(model: ErrorModel<R>) -> (HeaderMap, Bytes)
+where
+    R: ToString,
+{
+    let mut headers = HeaderMap::new();
+    headers.insert(ERROR_REPLY_HEADER, "true");
+    if let Some(details) = model.typed_details_header() {
+        headers.insert(ERROR_DETAILS_HEADER, details.as_str());
+    }
+
+    let error_reply: proto_nats::ErrorReply = model.into();
+    let payload = NatsMessageSerde::<proto_nats::ErrorReply>::default()
+        .serialize(error_reply)
+        .unwrap_or_else(|never| match never {});
+
+    (headers, payload)
+}
+
+/// Runs the handler within the time left until the request deadline (see
+/// [DEADLINE_HEADER](crate::request::DEADLINE_HEADER)). The handler is not started when the
+/// deadline has already passed, and is cancelled when it passes while it runs.
+pub(crate) async fn within_deadline<T, R, H, Fut>(
+    remaining: Option<Duration>,
+    handler: H,
+) -> Result<T, ErrorModel<R>>
+where
+    H: FnOnce() -> Fut,
+    Fut: Future<Output = T>,
+{
+    match remaining {
+        None => Ok(handler().await),
+        Some(remaining) if remaining.is_zero() => Err(deadline_exceeded()),
+        Some(remaining) => tokio::time::timeout(remaining, handler())
+            .await
+            .map_err(|_| deadline_exceeded()),
+    }
+}
+
+/// ErrorModel returned when the request deadline passed before the handler completed
+pub(crate) fn deadline_exceeded<R>() -> ErrorModel<R> {
+    ErrorModel::new(
+        Status::DeadlineExceeded,
+        504,
+        "Request deadline exceeded".to_string(),
+    )
+}
+
+/// ErrorModel returned when a request payload cannot be decoded into the handler's request type
+fn invalid_request<R>(err: impl std::error::Error) -> ErrorModel<R> {
+    ErrorModel::new(
+        Status::InvalidArgument,
+        400,
+        format!("Invalid request: {}", err),
+    )
+}
+
+/// ErrorModel returned when the handler's response cannot be serialized
+fn unserializable_response<R>(err: impl std::error::Error) -> ErrorModel<R> {
+    ErrorModel::new(
+        Status::Internal,
+        500,
+        format!("Failed to serialize response: {}", err),
+    )
+}
+
+#[cfg(test)]
+#[path = "./handler_tests.rs"]
+mod handler_tests;
@@ -0,0 +1,522 @@
+#[cfg(test)]
+mod handler_tests {
+    use std::{
+        collections::BTreeMap,
+        sync::atomic::{AtomicBool, Ordering},
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    };
+
+    use async_nats::{HeaderMap, Message};
+    use bytes::Bytes;
+    use chat_proto::runtiva::nats::v1 as proto_nats;
+    use prost::Message as _;
+
+    use crate::{
+        error::{BadRequest, ErrorModel, ErrorReason, MetaKeys, Status},
+        request::{Converter, NatsEnvelope, DEADLINE_HEADER, REQUESTOR_HEADER},
+        response::{NatsResponse, ERROR_DETAILS_HEADER, ERROR_REPLY_HEADER},
+        server::{receiver::test_message, serde::NatsMessageSerde},
+    };
+
+    use super::super::{error_reply, process_json, process_prost};
+    use super::{ChatGroupCreate, ChatGroupError, CreatedChatGroup};
+
+    async fn create_json(
+        request: NatsEnvelope<ChatGroupCreate>,
+    ) -> Result<CreatedChatGroup, ChatGroupError> {
+        if request.data.title.is_empty() {
+            return Err(ChatGroupError::TitleEmpty);
+        }
+
+        Ok(CreatedChatGroup {
+            title: request.data.title,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_json_handler_replies_with_data() {
+        let payload = serde_json::to_vec(&ChatGroupCreate {
+            title: "Hello".to_string(),
+        })
+        .unwrap();
+
+        let reply = process_json(
+            &create_json,
+            test_message("chat.chatgroup.command.create", Some("_INBOX.1"), payload),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(reply.subject, "_INBOX.1");
+        assert!(reply.headers.is_none());
+
+        let response: NatsResponse<CreatedChatGroup, ErrorReason> =
+            serde_json::from_slice(&reply.payload).unwrap();
+        assert_eq!(response.data.unwrap().title, "Hello".to_string());
+        assert!(response.error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_json_handler_replies_with_error_model() {
+        let payload = serde_json::to_vec(&ChatGroupCreate {
+            title: "".to_string(),
+        })
+        .unwrap();
+
+        let reply = process_json(
+            &create_json,
+            test_message("chat.chatgroup.command.create", Some("_INBOX.1"), payload),
+        )
+        .await
+        .unwrap();
+
+        let response: NatsResponse<CreatedChatGroup, ErrorReason> =
+            serde_json::from_slice(&reply.payload).unwrap();
+        let error = response.error.unwrap();
+
+        assert!(response.data.is_none());
+        assert_eq!(error.status, Status::InvalidArgument);
+        assert_eq!(
+            error.details[0].metadata.get(&MetaKeys::Request).unwrap(),
+            "chat.chatgroup.command.create"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_json_handler_replies_with_internal_error_on_unserializable_response() {
+        async fn unserializable(
+            _request: NatsEnvelope<ChatGroupCreate>,
+        ) -> Result<BTreeMap<(u8, u8), String>, ChatGroupError> {
+            Ok(BTreeMap::from([((1, 2), "Hello".to_string())]))
+        }
+
+        let payload = serde_json::to_vec(&ChatGroupCreate {
+            title: "Hello".to_string(),
+        })
+        .unwrap();
+
+        let reply = process_json(
+            &unserializable,
+            test_message("chat.chatgroup.command.create", Some("_INBOX.1"), payload),
+        )
+        .await
+        .unwrap();
+
+        let response: NatsResponse<CreatedChatGroup, ErrorReason> =
+            serde_json::from_slice(&reply.payload).unwrap();
+
+        assert!(response.data.is_none());
+        assert_eq!(response.error.unwrap().status, Status::Internal);
+    }
+
+    #[tokio::test]
+    async fn test_json_handler_rejects_invalid_payload() {
+        let reply = process_json(
+            &create_json,
+            test_message(
+                "chat.chatgroup.command.create",
+                Some("_INBOX.1"),
+                Bytes::from_static(b"not json"),
+            ),
+        )
+        .await
+        .unwrap();
+
+        let response: NatsResponse<CreatedChatGroup, ErrorReason> =
+            serde_json::from_slice(&reply.payload).unwrap();
+
+        assert_eq!(response.error.unwrap().status, Status::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_json_handler_lifts_native_headers() {
+        let payload = serde_json::to_vec(&ChatGroupCreate {
+            title: "".to_string(),
+        })
+        .unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(REQUESTOR_HEADER, "42");
+        let mut message = test_message("chat.chatgroup.command.create", Some("_INBOX.1"), payload);
+        message.headers = Some(headers);
+
+        let reply = process_json(&create_json, message).await.unwrap();
+
+        let response: NatsResponse<CreatedChatGroup, ErrorReason> =
+            serde_json::from_slice(&reply.payload).unwrap();
+        let error = response.error.unwrap();
+
+        assert_eq!(
+            error.details[0].metadata.get(&MetaKeys::Requestor).unwrap(),
+            "42"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_json_handler_without_reply_subject() {
+        let payload = serde_json::to_vec(&ChatGroupCreate {
+            title: "Hello".to_string(),
+        })
+        .unwrap();
+
+        let reply = process_json(
+            &create_json,
+            test_message("chat.chatgroup.command.create", None, payload),
+        )
+        .await;
+
+        assert!(reply.is_none());
+    }
+
+    fn with_deadline(mut message: Message, deadline: SystemTime) -> Message {
+        let millis = deadline.duration_since(UNIX_EPOCH).unwrap().as_millis();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(DEADLINE_HEADER, millis.to_string().as_str());
+        message.headers = Some(headers);
+        message
+    }
+
+    #[tokio::test]
+    async fn test_json_handler_skips_expired_deadline() {
+        let payload = serde_json::to_vec(&ChatGroupCreate {
+            title: "Hello".to_string(),
+        })
+        .unwrap();
+        let called = AtomicBool::new(false);
+        let handler = |request| {
+            called.store(true, Ordering::SeqCst);
+            create_json(request)
+        };
+
+        let deadline = SystemTime::now() - Duration::from_secs(1);
+        let message = with_deadline(
+            test_message("chat.chatgroup.command.create", Some("_INBOX.1"), payload),
+            deadline,
+        );
+        let reply = process_json(&handler, message).await.unwrap();
+
+        let response: NatsResponse<CreatedChatGroup, ErrorReason> =
+            serde_json::from_slice(&reply.payload).unwrap();
+        let error = response.error.unwrap();
+
+        assert!(!called.load(Ordering::SeqCst));
+        assert_eq!(error.status, Status::DeadlineExceeded);
+        assert_eq!(error.code, 504);
+    }
+
+    #[tokio::test]
+    async fn test_json_handler_cancelled_at_deadline() {
+        async fn slow(
+            request: NatsEnvelope<ChatGroupCreate>,
+        ) -> Result<CreatedChatGroup, ChatGroupError> {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            create_json(request).await
+        }
+
+        let payload = serde_json::to_vec(&ChatGroupCreate {
+            title: "Hello".to_string(),
+        })
+        .unwrap();
+
+        let deadline = SystemTime::now() + Duration::from_millis(20);
+        let message = with_deadline(
+            test_message("chat.chatgroup.command.create", Some("_INBOX.1"), payload),
+            deadline,
+        );
+        let reply = process_json(&slow, message).await.unwrap();
+
+        let response: NatsResponse<CreatedChatGroup, ErrorReason> =
+            serde_json::from_slice(&reply.payload).unwrap();
+        assert_eq!(response.error.unwrap().status, Status::DeadlineExceeded);
+    }
+
+    #[tokio::test]
+    async fn test_json_handler_within_deadline() {
+        let payload = serde_json::to_vec(&ChatGroupCreate {
+            title: "Hello".to_string(),
+        })
+        .unwrap();
+
+        let deadline = SystemTime::now() + Duration::from_secs(30);
+        let message = with_deadline(
+            test_message("chat.chatgroup.command.create", Some("_INBOX.1"), payload),
+            deadline,
+        );
+        let reply = process_json(&create_json, message).await.unwrap();
+
+        let response: NatsResponse<CreatedChatGroup, ErrorReason> =
+            serde_json::from_slice(&reply.payload).unwrap();
+        assert_eq!(response.data.unwrap().title, "Hello".to_string());
+    }
+
+    async fn create_prost(
+        request: NatsEnvelope<ChatGroupCreate>,
+    ) -> Result<proto_nats::CreateChatGroupRequest, ChatGroupError> {
+        if request.data.title.is_empty() {
+            return Err(ChatGroupError::TitleEmpty);
+        }
+
+        Ok(proto_nats::CreateChatGroupRequest {
+            title: request.data.title,
+            ..Default::default()
+        })
+    }
+
+    fn prost_payload(title: &str) -> Bytes {
+        proto_nats::NatsChatGroupCreateRequest {
+            headers: vec![proto_nats::MetadataMap {
+                key: "x-requestor".to_string(),
+                value: vec!["42".to_string()],
+            }],
+            data: Some(proto_nats::CreateChatGroupRequest {
+                title: title.to_string(),
+                ..Default::default()
+            }),
+        }
+        .encode_to_vec()
+        .into()
+    }
+
+    #[tokio::test]
+    async fn test_prost_handler_replies_with_message() {
+        let converter =
+            Converter::new(NatsMessageSerde::<proto_nats::NatsChatGroupCreateRequest>::default());
+
+        let reply = process_prost::<_, _, _, _, ErrorReason, _, _, _>(
+            &converter,
+            &create_prost,
+            test_message(
+                "chat.chatgroup.command.create",
+                Some("_INBOX.1"),
+                prost_payload("Hello"),
+            ),
+        )
+        .await
+        .unwrap();
+
+        assert!(reply.headers.is_none());
+        let response = proto_nats::CreateChatGroupRequest::decode(reply.payload).unwrap();
+        assert_eq!(response.title, "Hello".to_string());
+    }
+
+    #[tokio::test]
+    async fn test_prost_handler_replies_with_error_reply() {
+        let converter =
+            Converter::new(NatsMessageSerde::<proto_nats::NatsChatGroupCreateRequest>::default());
+
+        let reply = process_prost::<_, _, _, _, ErrorReason, _, _, _>(
+            &converter,
+            &create_prost,
+            test_message(
+                "chat.chatgroup.command.create",
+                Some("_INBOX.1"),
+                prost_payload(""),
+            ),
+        )
+        .await
+        .unwrap();
+
+        assert!(reply.headers.unwrap().get(ERROR_REPLY_HEADER).is_some());
+
+        let error = proto_nats::ErrorReply::decode(reply.payload).unwrap();
+        assert_eq!(error.code, 400);
+        assert_eq!(error.status, Status::InvalidArgument as i32);
+        assert_eq!(error.details[0].reason, "UNSUPPORTED_REQUEST".to_string());
+
+        let requestor = error.details[0]
+            .metadata
+            .iter()
+            .find(|entry| entry.key == "requestor")
+            .unwrap();
+        assert_eq!(requestor.value, "42".to_string());
+    }
+
+    #[tokio::test]
+    async fn test_prost_handler_rejects_invalid_embedded_header() {
+        let converter =
+            Converter::new(NatsMessageSerde::<proto_nats::NatsChatGroupCreateRequest>::default());
+
+        let payload = proto_nats::NatsChatGroupCreateRequest {
+            headers: vec![proto_nats::MetadataMap {
+                key: "x requestor".to_string(),
+                value: vec!["42".to_string()],
+            }],
+            data: None,
+        }
+        .encode_to_vec();
+
+        let reply = process_prost::<_, _, _, _, ErrorReason, _, _, _>(
+            &converter,
+            &create_prost,
+            test_message("chat.chatgroup.command.create", Some("_INBOX.1"), payload),
+        )
+        .await
+        .unwrap();
+
+        let error = proto_nats::ErrorReply::decode(reply.payload).unwrap();
+        assert_eq!(error.code, 400);
+        assert_eq!(error.status, Status::InvalidArgument as i32);
+    }
+
+    #[test]
+    fn test_error_reply_carries_typed_details() {
+        let model = ErrorModel::<ErrorReason>::new(
+            Status::InvalidArgument,
+            400,
+            "Invalid chat group".to_string(),
+        )
+        .with_typed_detail(BadRequest::new().field_violation("title", "must not be empty"));
+
+        let (headers, payload) = error_reply(model.clone());
+
+        assert!(headers.get(ERROR_REPLY_HEADER).is_some());
+        let details = headers.get(ERROR_DETAILS_HEADER).unwrap();
+
+        let error = proto_nats::ErrorReply::decode(payload).unwrap();
+        let received = ErrorModel::<ErrorReason>::try_from(error)
+            .unwrap()
+            .with_typed_details_header(details.as_str())
+            .unwrap();
+        assert_eq!(received.typed_details, model.typed_details);
+    }
+
+    #[tokio::test]
+    async fn test_prost_handler_skips_expired_deadline() {
+        let converter =
+            Converter::new(NatsMessageSerde::<proto_nats::NatsChatGroupCreateRequest>::default());
+
+        let deadline = SystemTime::now() - Duration::from_secs(1);
+        let reply = process_prost::<_, _, _, _, ErrorReason, _, _, _>(
+            &converter,
+            &create_prost,
+            with_deadline(
+                test_message(
+                    "chat.chatgroup.command.create",
+                    Some("_INBOX.1"),
+                    prost_payload("Hello"),
+                ),
+                deadline,
+            ),
+        )
+        .await
+        .unwrap();
+
+        assert!(reply.headers.unwrap().get(ERROR_REPLY_HEADER).is_some());
+
+        let error = proto_nats::ErrorReply::decode(reply.payload).unwrap();
+        assert_eq!(error.code, 504);
+        assert_eq!(error.status, Status::DeadlineExceeded as i32);
+    }
+
+    #[tokio::test]
+    async fn test_prost_handler_merges_native_headers() {
+        let converter =
+            Converter::new(NatsMessageSerde::<proto_nats::NatsChatGroupCreateRequest>::default());
+
+        async fn echo_headers(
+            request: NatsEnvelope<ChatGroupCreate>,
+        ) -> Result<proto_nats::CreateChatGroupRequest, ChatGroupError> {
+            let trace = request.headers.0.get("traceparent").unwrap();
+
+            Ok(proto_nats::CreateChatGroupRequest {
+                title: format!(
+                    "{}:{}",
+                    request.headers.requestor().unwrap(),
+                    trace.to_str().unwrap()
+                ),
+                ..Default::default()
+            })
+        }
+
+        let mut headers = HeaderMap::new();
+        headers.insert(REQUESTOR_HEADER, "7");
+        headers.insert("traceparent", "00-abc-def-01");
+        let mut message = test_message(
+            "chat.chatgroup.command.create",
+            Some("_INBOX.1"),
+            prost_payload("Hello"),
+        );
+        message.headers = Some(headers);
+
+        let reply =
+            process_prost::<_, _, _, _, ErrorReason, _, _, _>(&converter, &echo_headers, message)
+                .await
+                .unwrap();
+
+        // the requestor embedded in the protobuf request wins over the native header
+        let response = proto_nats::CreateChatGroupRequest::decode(reply.payload).unwrap();
+        assert_eq!(response.title, "42:00-abc-def-01".to_string());
+    }
+}
+
+use chat_proto::runtiva::nats::v1 as proto_nats;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{ErrorModel, ErrorReason, MetaKeys, Status, ToErrorModel},
+    request::{RequestHeaders, TryFromNatsRequest},
+    server::NatsTransportError,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ChatGroupCreate {
+    pub title: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CreatedChatGroup {
+    pub title: String,
+}
+
+impl TryFromNatsRequest<proto_nats::NatsChatGroupCreateRequest> for ChatGroupCreate {
+    type Error = NatsTransportError;
+
+    fn try_from(
+        value: proto_nats::NatsChatGroupCreateRequest,
+    ) -> Result<(Self, RequestHeaders), Self::Error> {
+        let headers: RequestHeaders = value.headers.try_into()?;
+        let title = value.data.map(|data| data.title).unwrap_or_default();
+
+        Ok((ChatGroupCreate { title }, headers))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+enum ChatGroupError {
+    #[error("No chat title provided.")]
+    TitleEmpty,
+}
+
+impl ToErrorModel<ErrorReason> for ChatGroupError {
+    fn to_error_model(
+        &self,
+        requestor: Option<i64>,
+        request: Option<String>,
+    ) -> ErrorModel<ErrorReason> {
+        let mut model = ErrorModel::new(self.status(), self.error_code(), self.msg())
+            .with_details(ErrorReason::UnsupportedRequest, "runtiva.com".to_string());
+
+        if let Some(request) = request {
+            model = model.append_metadata(MetaKeys::Request, request);
+        }
+
+        if let Some(requestor) = requestor {
+            model = model.append_metadata(MetaKeys::Requestor, requestor.to_string());
+        }
+
+        model
+    }
+
+    fn msg(&self) -> String {
+        self.to_string()
+    }
+
+    fn error_code(&self) -> i32 {
+        400
+    }
+
+    fn status(&self) -> Status {
+        Status::InvalidArgument
+    }
+}
@@ -0,0 +1,359 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_nats::jetstream::{
+    self,
+    consumer::{self, pull, push, AckPolicy, IntoConsumerConfig},
+};
+use async_trait::async_trait;
+use futures::{stream::BoxStream, StreamExt};
+use tokio::sync::watch;
+
+use crate::server::{shutdown::ShutdownListener, NatsServer, NatsTransportError};
+
+use super::{
+    ack::{ack_on_completion, acking, DEFAULT_ACK_WAIT},
+    dispatcher::{Dispatch, Dispatcher},
+    service::{ready, Readiness},
+    subscription_handle::SubscriptionControl,
+    ConsumerMode, DurableConsumerConfig, MessageHandler, MessageService, OutcomeHandler, Subscribe,
+    SubscribeOptions, SubscriptionHandle,
+};
+
+/// [Subscribe] implementation receiving messages from a durable JetStream consumer.
+///
+/// Handlers with the core subscription signature are acknowledged once they complete. They
+/// receive the message without its reply subject (the ack subject of the message), so
+/// [json_handler](super::json_handler), [prost_handler](super::prost_handler),
+/// [json_service](super::json_service) and [Router](super::Router) do not reply: their errors
+/// are redelivered when transient (e.g. `Unavailable`) and terminated otherwise (e.g.
+/// `InvalidArgument`). Handlers registered with [Subscribe::subscribe_with_outcome] decide how each
+/// message is acknowledged by returning an [AckOutcome](super::AckOutcome).
+///
+/// Transient errors of the consumer (e.g. missed idle heartbeats) are logged and the
+/// subscription keeps receiving. Once the consumer can no longer deliver messages (e.g. it was
+/// deleted), the subscription stops and the error is returned by [SubscriptionHandle::join].
+///
+/// The durable consumer filters a single subject: an existing consumer is updated when its
+/// `max_deliver`, `ack_wait` or deliver group changed, but subscribing to another subject than
+/// the one it filters fails with [NatsTransportError::ConsumerMismatch].
+pub struct JetStreamReceiver {
+    nats_server: Arc<NatsServer>,
+    consumer: DurableConsumerConfig,
+    subject: Mutex<Option<String>>,
+}
+
+impl JetStreamReceiver {
+    pub fn new(nats_server: Arc<NatsServer>, consumer: DurableConsumerConfig) -> JetStreamReceiver {
+        JetStreamReceiver {
+            nats_server,
+            consumer,
+            subject: Mutex::new(None),
+        }
+    }
+
+    /// Ack wait of the consumer, the server default when not configured
+    fn ack_wait(&self) -> Duration {
+        // a zero ack wait is replaced by the server default
+        self.consumer
+            .ack_wait_duration()
+            .filter(|ack_wait| !ack_wait.is_zero())
+            .unwrap_or(DEFAULT_ACK_WAIT)
+    }
+
+    /// Creates the consumer (if needed) and starts receiving its messages
+    async fn messages(
+        &self,
+        subject: &str,
+        options: &SubscribeOptions,
+    ) -> Result<ConsumerMessages<jetstream::Message>, NatsTransportError> {
+        let consumer = &self.consumer;
+        let stream = self
+            .nats_server
+            .jetstream()
+            .get_stream(&consumer.stream)
+            .await
+            .map_err(|err| NatsTransportError::NatsConsumerError(err.into()))?;
+
+        let max_deliver = consumer.max_deliver.unwrap_or(-1);
+        let ack_wait = self.ack_wait();
+
+        let messages = match consumer.mode {
+            ConsumerMode::Pull => {
+                let config = pull::Config {
+                    durable_name: Some(consumer.durable_name.clone()),
+                    filter_subject: subject.to_string(),
+                    ack_policy: AckPolicy::Explicit,
+                    max_deliver,
+                    ack_wait,
+                    ..Default::default()
+                };
+
+                let mut existing: consumer::Consumer<pull::Config> = stream
+                    .get_or_create_consumer(&consumer.durable_name, config.clone())
+                    .await
+                    .map_err(|err| NatsTransportError::NatsConsumerError(err.into()))?;
+
+                if needs_update(
+                    &existing.cached_info().config,
+                    &config.clone().into_consumer_config(),
+                )? {
+                    existing = stream
+                        .create_consumer(config)
+                        .await
+                        .map_err(|err| NatsTransportError::NatsConsumerError(err.into()))?;
+                }
+
+                existing
+                    .messages()
+                    .await
+                    .map_err(|err| NatsTransportError::NatsConsumerError(err.into()))?
+                    .map(|message| message.map_err(ConsumerStreamError::from))
+                    .boxed()
+            }
+            ConsumerMode::Push => {
+                let mut config = push::Config {
+                    durable_name: Some(consumer.durable_name.clone()),
+                    deliver_subject: self.nats_server.client().new_inbox(),
+                    deliver_group: options.queue_group.clone(),
+                    filter_subject: subject.to_string(),
+                    ack_policy: AckPolicy::Explicit,
+                    max_deliver,
+                    ack_wait,
+                    ..Default::default()
+                };
+
+                let mut existing: consumer::Consumer<push::Config> = stream
+                    .get_or_create_consumer(&consumer.durable_name, config.clone())
+                    .await
+                    .map_err(|err| NatsTransportError::NatsConsumerError(err.into()))?;
+
+                let current = &existing.cached_info().config;
+                if needs_update(current, &config.clone().into_consumer_config())? {
+                    // the replicas sharing the consumer keep receiving on its deliver subject
+                    if let Some(deliver_subject) = &current.deliver_subject {
+                        config.deliver_subject = deliver_subject.clone();
+                    }
+                    existing = stream
+                        .create_consumer(config)
+                        .await
+                        .map_err(|err| NatsTransportError::NatsConsumerError(err.into()))?;
+                }
+
+                existing
+                    .messages()
+                    .await
+                    .map_err(|err| NatsTransportError::NatsConsumerError(err.into()))?
+                    .map(|message| message.map_err(ConsumerStreamError::from))
+                    .boxed()
+            }
+        };
+
+        Ok(messages)
+    }
+}
+
+#[async_trait]
+impl Subscribe for JetStreamReceiver {
+    async fn subscribe_with_options(
+        &self,
+        subject: String,
+        options: SubscribeOptions,
+        handler: MessageHandler,
+    ) -> Result<SubscriptionHandle, NatsTransportError> {
+        self.subscribe_with_outcome(subject, options, ack_on_completion(handler))
+            .await
+    }
+
+    async fn subscribe_service(
+        &self,
+        subject: String,
+        options: SubscribeOptions,
+        service: MessageService,
+    ) -> Result<SubscriptionHandle, NatsTransportError> {
+        let handler = ack_on_completion(service.handler);
+        self.subscribe_ready(subject, options, handler, Some(service.ready))
+            .await
+    }
+
+    /// Binds the durable consumer to the subject and calls the handler for each message,
+    /// applying the returned outcome to the message.
+    /// The queue group of the [SubscribeOptions] is used as the deliver group of push consumers.
+    async fn subscribe_with_outcome(
+        &self,
+        subject: String,
+        options: SubscribeOptions,
+        handler: OutcomeHandler,
+    ) -> Result<SubscriptionHandle, NatsTransportError> {
+        self.subscribe_ready(subject, options, handler, None).await
+    }
+}
+
+impl JetStreamReceiver {
+    /// Binds the consumer and spawns the receive loop, which waits for the readiness (if any)
+    /// before taking each message
+    async fn subscribe_ready(
+        &self,
+        subject: String,
+        options: SubscribeOptions,
+        handler: OutcomeHandler,
+        readiness: Option<Readiness>,
+    ) -> Result<SubscriptionHandle, NatsTransportError> {
+        let shutdown = self.nats_server.shutdown_listener()?;
+        bind_subject(&self.subject, &self.consumer.durable_name, &subject)?;
+        let messages = self.messages(&subject, &options).await?;
+
+        let dispatcher = Dispatcher::new(&options, acking(handler, self.ack_wait()));
+        let (control_tx, control_rx) = watch::channel(SubscriptionControl::Active);
+        let task = tokio::task::spawn(async move {
+            receive(messages, dispatcher, readiness, control_rx, shutdown).await
+        });
+
+        Ok(SubscriptionHandle::new(subject, control_tx, task))
+    }
+}
+
+/// Binds the receiver's consumer to the subject, failing when it is bound to another subject
+pub(crate) fn bind_subject(
+    bound: &Mutex<Option<String>>,
+    durable_name: &str,
+    subject: &str,
+) -> Result<(), NatsTransportError> {
+    let mut bound = bound.lock().expect("bound subject is never poisoned");
+
+    match bound.as_deref() {
+        Some(bound) if bound != subject => Err(NatsTransportError::ConsumerMismatch(format!(
+            "consumer {} filters {}, not {}",
+            durable_name, bound, subject
+        ))),
+        _ => {
+            *bound = Some(subject.to_string());
+            Ok(())
+        }
+    }
+}
+
+/// Whether the existing consumer must be updated to the desired configuration. Fails when it
+/// filters another subject or uses another ack policy, which are not updated.
+pub(crate) fn needs_update(
+    existing: &consumer::Config,
+    desired: &consumer::Config,
+) -> Result<bool, NatsTransportError> {
+    let name = desired.durable_name.as_deref().unwrap_or_default();
+
+    if existing.filter_subject != desired.filter_subject {
+        return Err(NatsTransportError::ConsumerMismatch(format!(
+            "consumer {} filters {}, not {}",
+            name, existing.filter_subject, desired.filter_subject
+        )));
+    }
+
+    if existing.ack_policy != desired.ack_policy {
+        return Err(NatsTransportError::ConsumerMismatch(format!(
+            "consumer {} uses the {:?} ack policy, not {:?}",
+            name, existing.ack_policy, desired.ack_policy
+        )));
+    }
+
+    Ok(existing.max_deliver != desired.max_deliver
+        || existing.ack_wait != desired.ack_wait
+        || existing.deliver_group != desired.deliver_group)
+}
+
+/// Messages delivered by a consumer
+pub(crate) type ConsumerMessages<M> = BoxStream<'static, Result<M, ConsumerStreamError>>;
+
+/// Error yielded by the message stream of a consumer
+#[derive(Debug)]
+pub(crate) struct ConsumerStreamError {
+    /// The consumer no longer delivers messages, e.g. it was deleted
+    pub terminal: bool,
+    pub source: Box<dyn std::error::Error + Send + Sync + 'static>,
+}
+
+impl From<pull::MessagesError> for ConsumerStreamError {
+    fn from(err: pull::MessagesError) -> Self {
+        let terminal = matches!(
+            err.kind(),
+            pull::MessagesErrorKind::ConsumerDeleted | pull::MessagesErrorKind::PushBasedConsumer
+        );
+
+        Self {
+            terminal,
+            source: err.into(),
+        }
+    }
+}
+
+impl From<push::MessagesError> for ConsumerStreamError {
+    fn from(err: push::MessagesError) -> Self {
+        let terminal = matches!(
+            err.kind(),
+            push::MessagesErrorKind::ConsumerDeleted | push::MessagesErrorKind::PullBasedConsumer
+        );
+
+        Self {
+            terminal,
+            source: err.into(),
+        }
+    }
+}
+
+/// Dispatches the consumer's messages until it is stopped through its [SubscriptionHandle],
+/// the server shuts down or the consumer fails, then waits for in-flight messages to be processed
+pub(crate) async fn receive<M: Dispatch>(
+    mut messages: ConsumerMessages<M>,
+    mut dispatcher: Dispatcher<M>,
+    readiness: Option<Readiness>,
+    mut control: watch::Receiver<SubscriptionControl>,
+    mut shutdown: ShutdownListener,
+) -> Result<(), NatsTransportError> {
+    // a dropped handle detaches the subscription, which then runs until the server shuts down
+    let mut attached = true;
+
+    let result = loop {
+        tokio::select! {
+            changed = control.changed(), if attached => {
+                if changed.is_err() {
+                    attached = false;
+                    continue;
+                }
+
+                if *control.borrow_and_update() != SubscriptionControl::Active {
+                    break Ok(());
+                }
+            }
+            _ = shutdown.requested() => break Ok(()),
+            message = async {
+                ready(readiness.as_ref()).await;
+                messages.next().await
+            } => match message {
+                Some(Ok(message)) => dispatcher.dispatch(message).await,
+                Some(Err(err)) if err.terminal => {
+                    tracing::error!(
+                        error = %err.source,
+                        "JetStream consumer stopped delivering messages"
+                    );
+                    break Err(NatsTransportError::NatsConsumerError(err.source));
+                }
+                // e.g. missed idle heartbeats: the consumer keeps delivering messages after them
+                Some(Err(err)) => {
+                    tracing::warn!(error = %err.source, "JetStream consumer error");
+                }
+                None => break Ok(()),
+            },
+        }
+    };
+
+    // messages that were delivered but not dispatched are not acknowledged, so the
+    // server redelivers them after the ack wait: draining only waits for in-flight messages
+    drop(messages);
+    dispatcher.finish().await;
+    result
+}
+
+#[cfg(test)]
+#[path = "./jetstream_receiver_tests.rs"]
+mod jetstream_receiver_tests;
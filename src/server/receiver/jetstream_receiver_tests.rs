@@ -0,0 +1,307 @@
+#[cfg(test)]
+mod jetstream_receiver_tests {
+    use std::{
+        convert::Infallible,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        task::{Context, Poll},
+        time::Duration,
+    };
+
+    use async_nats::{
+        jetstream::{
+            consumer::{self, pull, push, AckPolicy},
+            stream,
+        },
+        Message,
+    };
+    use bytes::Bytes;
+    use futures::{
+        future::{self, BoxFuture},
+        FutureExt, StreamExt,
+    };
+    use tokio::sync::{mpsc, watch, OwnedSemaphorePermit, Semaphore};
+    use tower::Service;
+
+    use crate::server::{
+        receiver::{
+            dispatcher::Dispatcher,
+            jetstream_receiver::{
+                bind_subject, needs_update, receive, ConsumerMessages, ConsumerStreamError,
+            },
+            message_handler, outcome_handler,
+            subscription_handle::SubscriptionControl,
+            test_message, AckOutcome, DurableConsumerConfig, JetStreamReceiver, MessageService,
+            Subscribe, SubscribeOptions,
+        },
+        shutdown::ShutdownCoordinator,
+        NatsServer, NatsTransportError,
+    };
+
+    /// Runs the receive loop over the given stream items, returning its result and the
+    /// payloads of the processed messages
+    async fn run(
+        items: Vec<Result<Message, ConsumerStreamError>>,
+    ) -> (Result<(), NatsTransportError>, Vec<String>) {
+        let processed = Arc::new(Mutex::new(Vec::new()));
+        let handler = {
+            let processed = processed.clone();
+            message_handler(move |message: Message| {
+                let processed = processed.clone();
+                async move {
+                    let payload = String::from_utf8(message.payload.to_vec()).unwrap();
+                    processed.lock().unwrap().push(payload);
+                }
+            })
+        };
+
+        let messages: ConsumerMessages<Message> = futures::stream::iter(items).boxed();
+        let dispatcher = Dispatcher::new(&SubscribeOptions::new(), handler);
+        let (_control_tx, control_rx) = watch::channel(SubscriptionControl::Active);
+        let coordinator = ShutdownCoordinator::new();
+
+        let result = receive(
+            messages,
+            dispatcher,
+            None,
+            control_rx,
+            coordinator.listener().unwrap(),
+        )
+        .await;
+
+        let processed = processed.lock().unwrap().clone();
+        (result, processed)
+    }
+
+    /// Service ready once per permit added to its semaphore, recording the processed payloads
+    struct Permits {
+        semaphore: Arc<Semaphore>,
+        acquire: Option<BoxFuture<'static, OwnedSemaphorePermit>>,
+        permit: Option<OwnedSemaphorePermit>,
+        processed: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Service<Message> for Permits {
+        type Response = ();
+        type Error = Infallible;
+        type Future = future::Ready<Result<(), Infallible>>;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            if self.permit.is_none() {
+                let semaphore = self.semaphore.clone();
+                let acquire = self.acquire.get_or_insert_with(|| {
+                    async move { semaphore.acquire_owned().await.unwrap() }.boxed()
+                });
+                let Poll::Ready(permit) = acquire.poll_unpin(cx) else {
+                    return Poll::Pending;
+                };
+                self.acquire = None;
+                self.permit = Some(permit);
+            }
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, message: Message) -> Self::Future {
+            // the permit is used up by the call
+            self.permit.take().expect("called when ready").forget();
+            let payload = String::from_utf8(message.payload.to_vec()).unwrap();
+            self.processed.lock().unwrap().push(payload);
+            future::ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_service_readiness_holds_messages_back() {
+        let semaphore = Arc::new(Semaphore::new(1));
+        let processed = Arc::new(Mutex::new(Vec::new()));
+        let service = MessageService::new(Permits {
+            semaphore: semaphore.clone(),
+            acquire: None,
+            permit: None,
+            processed: processed.clone(),
+        });
+
+        let pulled = Arc::new(AtomicUsize::new(0));
+        let messages: ConsumerMessages<Message> = {
+            let pulled = pulled.clone();
+            futures::stream::iter(["1", "2", "3"])
+                .map(move |payload| {
+                    pulled.fetch_add(1, Ordering::SeqCst);
+                    Ok(test_message("chat.chatgroup.event.created", None, payload))
+                })
+                .boxed()
+        };
+
+        let dispatcher = Dispatcher::new(&SubscribeOptions::new().concurrency(4), service.handler);
+        let (_control_tx, control_rx) = watch::channel(SubscriptionControl::Active);
+        let coordinator = ShutdownCoordinator::new();
+        let task = tokio::spawn(receive(
+            messages,
+            dispatcher,
+            Some(service.ready),
+            control_rx,
+            coordinator.listener().unwrap(),
+        ));
+
+        // the next message is not taken while the service is not ready
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(pulled.load(Ordering::SeqCst), 1);
+        assert_eq!(*processed.lock().unwrap(), ["1"]);
+
+        // the end of the stream is only seen once the service is ready again
+        semaphore.add_permits(3);
+        tokio::time::timeout(Duration::from_secs(1), task)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(pulled.load(Ordering::SeqCst), 3);
+        assert_eq!(*processed.lock().unwrap(), ["1", "2", "3"]);
+    }
+
+    #[tokio::test]
+    async fn test_transient_error_keeps_receiving() {
+        let (result, processed) = run(vec![
+            Ok(test_message("chat.chatgroup.event.created", None, "1")),
+            Err(pull::MessagesError::from(pull::MessagesErrorKind::MissingHeartbeat).into()),
+            Ok(test_message("chat.chatgroup.event.created", None, "2")),
+        ])
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(processed, ["1", "2"]);
+    }
+
+    #[tokio::test]
+    async fn test_terminal_error_stops_receiving() {
+        let (result, processed) = run(vec![
+            Ok(test_message("chat.chatgroup.event.created", None, "1")),
+            Err(push::MessagesError::from(push::MessagesErrorKind::ConsumerDeleted).into()),
+            Ok(test_message("chat.chatgroup.event.created", None, "2")),
+        ])
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(NatsTransportError::NatsConsumerError(_))
+        ));
+        assert_eq!(processed, ["1"]);
+    }
+
+    #[test]
+    fn test_terminal_errors() {
+        let pull_error = |kind| ConsumerStreamError::from(pull::MessagesError::from(kind));
+        let push_error = |kind| ConsumerStreamError::from(push::MessagesError::from(kind));
+
+        assert!(pull_error(pull::MessagesErrorKind::ConsumerDeleted).terminal);
+        assert!(pull_error(pull::MessagesErrorKind::PushBasedConsumer).terminal);
+        assert!(!pull_error(pull::MessagesErrorKind::MissingHeartbeat).terminal);
+        assert!(!pull_error(pull::MessagesErrorKind::Pull).terminal);
+
+        assert!(push_error(push::MessagesErrorKind::ConsumerDeleted).terminal);
+        assert!(push_error(push::MessagesErrorKind::PullBasedConsumer).terminal);
+        assert!(!push_error(push::MessagesErrorKind::MissingHeartbeat).terminal);
+    }
+
+    #[test]
+    fn test_consumer_updates() {
+        let desired = consumer::Config {
+            durable_name: Some("receiver-test".to_string()),
+            filter_subject: "chat.receivertest.>".to_string(),
+            ack_policy: AckPolicy::Explicit,
+            ack_wait: Duration::from_secs(30),
+            max_deliver: -1,
+            ..Default::default()
+        };
+
+        assert!(!needs_update(&desired, &desired).unwrap());
+        for existing in [
+            consumer::Config {
+                max_deliver: 5,
+                ..desired.clone()
+            },
+            consumer::Config {
+                ack_wait: Duration::from_secs(10),
+                ..desired.clone()
+            },
+            consumer::Config {
+                deliver_group: Some("receivers".to_string()),
+                ..desired.clone()
+            },
+        ] {
+            assert!(needs_update(&existing, &desired).unwrap());
+        }
+
+        for existing in [
+            consumer::Config {
+                filter_subject: "chat.othertest.>".to_string(),
+                ..desired.clone()
+            },
+            consumer::Config {
+                ack_policy: AckPolicy::All,
+                ..desired.clone()
+            },
+        ] {
+            assert!(matches!(
+                needs_update(&existing, &desired),
+                Err(NatsTransportError::ConsumerMismatch(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_single_subject_per_consumer() {
+        let bound = Mutex::new(None);
+
+        assert!(bind_subject(&bound, "receiver-test", "chat.receivertest.>").is_ok());
+        assert!(bind_subject(&bound, "receiver-test", "chat.receivertest.>").is_ok());
+        assert!(matches!(
+            bind_subject(&bound, "receiver-test", "chat.othertest.>"),
+            Err(NatsTransportError::ConsumerMismatch(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_jetstream_receiver() -> Result<(), NatsTransportError> {
+        let nats = Arc::new(NatsServer::initialize("").await?);
+        nats.jetstream()
+            .get_or_create_stream(stream::Config {
+                name: "CHAT_RECEIVER_TEST".to_string(),
+                subjects: vec!["chat.receivertest.>".to_string()],
+                ..Default::default()
+            })
+            .await
+            .map_err(|err| NatsTransportError::NatsStreamError(err.into()))?;
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let receiver = JetStreamReceiver::new(
+            nats.clone(),
+            DurableConsumerConfig::new("CHAT_RECEIVER_TEST", "receiver-test"),
+        );
+        let handle = receiver
+            .subscribe_with_outcome(
+                "chat.receivertest.>".to_string(),
+                SubscribeOptions::new(),
+                outcome_handler(move |message: Message| {
+                    let tx = tx.clone();
+                    async move {
+                        let _ = tx.send(message.payload);
+                        AckOutcome::Ack
+                    }
+                }),
+            )
+            .await?;
+
+        nats.jetstream()
+            .publish("chat.receivertest.created".to_string(), "abc".into())
+            .await
+            .map_err(|err| NatsTransportError::JetStreamPublishError(err.into()))?;
+
+        let payload = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await;
+        assert_eq!(payload.unwrap().unwrap(), Bytes::from_static(b"abc"));
+
+        handle.unsubscribe().await
+    }
+}
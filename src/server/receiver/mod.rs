@@ -1,5 +1,49 @@
 mod subscribe;
-pub use subscribe::Subscribe;
+pub use subscribe::{message_handler, MessageHandler, Subscribe, SubscribeExt};
 
 mod nats_receiver;
 pub use nats_receiver::NatsReceiver;
+
+mod jetstream_receiver;
+pub use jetstream_receiver::JetStreamReceiver;
+
+mod consumer_config;
+pub use consumer_config::{ConsumerMode, Delivery, DurableConsumerConfig};
+
+mod ack;
+pub use ack::{outcome_handler, AckOutcome, OutcomeHandler};
+
+mod subscribe_options;
+pub use subscribe_options::{OrderingKey, SubscribeOptions, MAX_CONCURRENCY};
+
+mod dispatcher;
+
+mod subscription_handle;
+pub use subscription_handle::SubscriptionHandle;
+
+mod handler;
+pub use handler::{json_handler, prost_handler};
+
+mod service;
+pub use service::{json_service, prost_service, MessageService};
+
+mod router;
+pub use router::{ReplyFormat, Router, DEFAULT_ERROR_DOMAIN};
+
+/// Builds a core NATS message for the receiver tests
+#[cfg(test)]
+pub(crate) fn test_message(
+    subject: &str,
+    reply: Option<&str>,
+    payload: impl Into<bytes::Bytes>,
+) -> async_nats::Message {
+    async_nats::Message {
+        subject: subject.into(),
+        reply: reply.map(Into::into),
+        payload: payload.into(),
+        headers: None,
+        status: None,
+        description: None,
+        length: 0,
+    }
+}
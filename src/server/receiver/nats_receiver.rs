@@ -1,61 +1,158 @@
 //#![feature(async_closure)]
 use std::sync::Arc;
 
-use async_nats::Message;
+use async_nats::Subscriber;
 use async_trait::async_trait;
-use futures::future::BoxFuture;
-use futures::Future;
 use futures::StreamExt;
+use tokio::sync::watch;
 
-use crate::server::NatsServer;
+use crate::server::{shutdown::ShutdownListener, NatsServer, NatsTransportError};
 
-use super::Subscribe;
+use super::{
+    dispatcher::Dispatcher,
+    service::{ready, Readiness},
+    subscription_handle::SubscriptionControl,
+    MessageHandler, MessageService, Subscribe, SubscribeOptions, SubscriptionHandle,
+};
 
-pub struct NatsReceiver {}
-
-impl Default for NatsReceiver {
-    fn default() -> Self {
-        Self::new()
-    }
+/// [Subscribe] implementation receiving messages from core NATS subscriptions.
+///
+/// The receiver owns the [NatsServer] it subscribes on, so it no longer implements [Default]:
+/// `NatsReceiver::default()` becomes `NatsReceiver::new(nats_server)`, and the server is no
+/// longer passed to each [Subscribe] call.
+pub struct NatsReceiver {
+    nats_server: Arc<NatsServer>,
 }
 
 impl NatsReceiver {
-    pub fn new() -> NatsReceiver {
-        NatsReceiver {}
+    pub fn new(nats_server: Arc<NatsServer>) -> NatsReceiver {
+        NatsReceiver { nats_server }
+    }
+
+    /// Subscribes and spawns the receive loop, which waits for the readiness (if any) before
+    /// taking each message
+    async fn subscribe_ready(
+        &self,
+        subject: String,
+        options: SubscribeOptions,
+        handler: MessageHandler,
+        readiness: Option<Readiness>,
+    ) -> Result<SubscriptionHandle, NatsTransportError> {
+        let shutdown = self.nats_server.shutdown_listener()?;
+
+        let client = self.nats_server.client();
+        let subscription = match &options.queue_group {
+            Some(queue_group) => {
+                client
+                    .queue_subscribe(subject.clone(), queue_group.clone())
+                    .await
+            }
+            None => client.subscribe(subject.clone()).await,
+        }
+        .map_err(|err| NatsTransportError::NatsSubscribeError(err.into()))?;
+
+        let dispatcher = Dispatcher::new(&options, handler);
+        let (control_tx, control_rx) = watch::channel(SubscriptionControl::Active);
+        let task = tokio::task::spawn(async move {
+            receive(subscription, dispatcher, readiness, control_rx, shutdown).await
+        });
+
+        Ok(SubscriptionHandle::new(subject, control_tx, task))
     }
 }
 
-#[async_trait(?Send)]
+#[async_trait]
 impl Subscribe for NatsReceiver {
-    async fn subscribe<F, Fut>(
+    async fn subscribe_with_options(
         &self,
-        nats_server: Arc<NatsServer>,
         subject: String,
-        proc: F,
-    ) -> Result<(), BoxFuture<dyn std::error::Error + Send + Sync>>
-    where
-        F: Fn(Message) -> Fut + Send + Sync + 'static,
-        Fut: Future<Output = ()> + Send + Sync,
-    {
-        tokio::task::spawn(async move {
-            let result = nats_server.client().subscribe(subject).await?;
-
-            // TODO: How to properly handle this error?
-            // if let Err(e) = result {
-            //     panic!("Unable to initialize NATS subscription for : {}", e);
-            // }
-
-            // let mut subscription = result.unwrap();
-            let mut subscription = result;
-            while let Some(message) = subscription.next().await {
-                proc(message).await;
+        options: SubscribeOptions,
+        handler: MessageHandler,
+    ) -> Result<SubscriptionHandle, NatsTransportError> {
+        self.subscribe_ready(subject, options, handler, None).await
+    }
+
+    async fn subscribe_service(
+        &self,
+        subject: String,
+        options: SubscribeOptions,
+        service: MessageService,
+    ) -> Result<SubscriptionHandle, NatsTransportError> {
+        self.subscribe_ready(subject, options, service.handler, Some(service.ready))
+            .await
+    }
+}
+
+/// Dispatches the messages of the subscription until it is closed, stopped through
+/// its [SubscriptionHandle] or the server shuts down, then waits for in-flight messages
+/// to be processed
+async fn receive(
+    mut subscription: Subscriber,
+    mut dispatcher: Dispatcher,
+    readiness: Option<Readiness>,
+    mut control: watch::Receiver<SubscriptionControl>,
+    mut shutdown: ShutdownListener,
+) -> Result<(), NatsTransportError> {
+    // a dropped handle detaches the subscription, which then runs until it closes
+    // or the server shuts down
+    let mut attached = true;
+
+    let result = loop {
+        tokio::select! {
+            changed = control.changed(), if attached => {
+                if changed.is_err() {
+                    attached = false;
+                    continue;
+                }
+
+                let signal = *control.borrow_and_update();
+                match signal {
+                    SubscriptionControl::Active => continue,
+                    SubscriptionControl::Unsubscribe => {
+                        break unsubscribe(&mut subscription).await;
+                    }
+                    SubscriptionControl::Drain => {
+                        break drain(&mut subscription, &mut dispatcher).await;
+                    }
+                }
+            }
+            _ = shutdown.requested() => {
+                break drain(&mut subscription, &mut dispatcher).await;
             }
+            message = async {
+                ready(readiness.as_ref()).await;
+                subscription.next().await
+            } => match message {
+                Some(message) => dispatcher.dispatch(message).await,
+                None => break Ok(()),
+            },
+        }
+    };
 
-            Ok::<(), Box<dyn std::error::Error + Send + Sync>>(())
-        });
+    dispatcher.finish().await;
+    result
+}
+
+/// Unsubscribes, then dispatches the messages that were already received
+async fn drain(
+    subscription: &mut Subscriber,
+    dispatcher: &mut Dispatcher,
+) -> Result<(), NatsTransportError> {
+    unsubscribe(subscription).await?;
 
-        Ok(())
+    // the subscription yields the already received messages, then closes
+    while let Some(message) = subscription.next().await {
+        dispatcher.dispatch(message).await;
     }
+
+    Ok(())
+}
+
+async fn unsubscribe(subscription: &mut Subscriber) -> Result<(), NatsTransportError> {
+    subscription
+        .unsubscribe()
+        .await
+        .map_err(|err| NatsTransportError::NatsUnsubscribeError(err.into()))
 }
 
 #[cfg(test)]
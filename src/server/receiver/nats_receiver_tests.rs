@@ -5,7 +5,7 @@ mod nats_receiver_tests {
     use async_nats::Message;
 
     use crate::server::{
-        receiver::{NatsReceiver, Subscribe},
+        receiver::{NatsReceiver, SubscribeExt},
         NatsServer, NatsTransportError,
     };
 
@@ -13,14 +13,42 @@ mod nats_receiver_tests {
     async fn test_receiver() -> Result<(), NatsTransportError> {
         let nats = NatsServer::initialize("".into()).await?;
 
-        let receiver = NatsReceiver::new();
+        let receiver = NatsReceiver::new(Arc::new(nats));
         let subject = "chat.topic.>".to_string();
-        let _ = receiver.subscribe(Arc::new(nats), subject, processor).await;
+        let _ = receiver.subscribe(subject, processor).await;
 
         assert!(true); // successfully created subscription
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_queue_receiver() -> Result<(), NatsTransportError> {
+        let nats = NatsServer::initialize("").await?;
+
+        let receiver = NatsReceiver::new(Arc::new(nats));
+        let subject = "chat.*.command.*".to_string();
+        let queue_group = "chat-persist".to_string();
+        let result = receiver
+            .queue_subscribe(subject, queue_group, processor)
+            .await;
+
+        assert!(result.is_ok()); // successfully created queue subscription
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_receiver_unsubscribe() -> Result<(), NatsTransportError> {
+        let nats = NatsServer::initialize("").await?;
+
+        let receiver = NatsReceiver::new(Arc::new(nats));
+        let subject = "chat.topic.>".to_string();
+        let handle = receiver.subscribe(subject, processor).await?;
+
+        assert_eq!(handle.subject(), "chat.topic.>");
+        handle.unsubscribe().await?;
+        Ok(())
+    }
+
     async fn processor(msg: Message) {
         assert_eq!("abc".to_string(), msg.subject);
     }
@@ -0,0 +1,281 @@
+use std::sync::Arc;
+
+use async_nats::Message;
+use futures::{Future, FutureExt};
+
+use crate::{
+    error::{ErrorModel, ErrorReason, MetaKeys, Status},
+    request::RequestHeaders,
+    response::NatsResponse,
+    server::{
+        serde::{NatsJson, Serializer},
+        NatsServer, NatsTransportError,
+    },
+};
+
+use super::{
+    handler::{error_reply, reply_subject, Reply},
+    message_handler, MessageHandler, Subscribe, SubscribeOptions, SubscriptionHandle,
+};
+
+/// Error domain of the replies to requests without a matching route
+pub const DEFAULT_ERROR_DOMAIN: &str = "runtiva.com";
+
+/// Encoding of the error replies sent for requests without a matching route
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReplyFormat {
+    /// [NatsResponse] in JSON, as sent by [json_handler](super::json_handler)
+    #[default]
+    Json,
+
+    /// `proto_nats::ErrorReply` flagged with the [ERROR_REPLY_HEADER] header, as sent by
+    /// [prost_handler](super::prost_handler)
+    Prost,
+}
+
+/// Dispatches messages to handlers registered against subject patterns, replacing
+/// `match message.subject.as_str()` blocks.
+///
+/// Each message is dispatched to the most specific matching route: tokens are compared from
+/// left to right, and a literal token is more specific than `*`, which is more specific
+/// than `>`. Requests without a matching route are answered with an
+/// [ErrorReason::UnsupportedRequest] error.
+///
+/// ```ignore
+/// let router = Router::new(nats.clone())
+///     .route("chat.chatgroup.command.create", json_handler(nats.clone(), create_chat_group))
+///     .route("chat.chatgroup.command.*", json_handler(nats.clone(), other_command))
+///     .route("chat.*.event.>", audit_event);
+///
+/// let receiver = NatsReceiver::new(nats);
+/// let handles = router.subscribe(&receiver, SubscribeOptions::new()).await?;
+/// ```
+pub struct Router {
+    nats_server: Arc<NatsServer>,
+    routes: Routes,
+}
+
+/// Routing table of a [Router]
+pub(crate) struct Routes {
+    routes: Vec<Route>,
+    reply_format: ReplyFormat,
+    error_domain: String,
+}
+
+pub(crate) struct Route {
+    pub pattern: String,
+    pub handler: MessageHandler,
+}
+
+impl Router {
+    pub fn new(nats_server: Arc<NatsServer>) -> Self {
+        Self {
+            nats_server,
+            routes: Routes::new(),
+        }
+    }
+
+    /// Registers the handler for the subject pattern, replacing the handler previously
+    /// registered for the same pattern
+    pub fn route<F, Fut>(mut self, pattern: impl Into<String>, handler: F) -> Self
+    where
+        F: Fn(Message) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.routes.insert(pattern.into(), message_handler(handler));
+        self
+    }
+
+    pub fn reply_format(mut self, reply_format: ReplyFormat) -> Self {
+        self.routes.reply_format = reply_format;
+        self
+    }
+
+    /// Error domain of the [ErrorReason::UnsupportedRequest] replies, [DEFAULT_ERROR_DOMAIN] by default
+    pub fn error_domain(mut self, error_domain: impl Into<String>) -> Self {
+        self.routes.error_domain = error_domain.into();
+        self
+    }
+
+    /// Subscribes once per route pattern. A message matching several patterns is received
+    /// by each of their subscriptions, but only handled by the most specific one.
+    pub async fn subscribe(
+        self,
+        receiver: &dyn Subscribe,
+        options: SubscribeOptions,
+    ) -> Result<Vec<SubscriptionHandle>, NatsTransportError> {
+        let patterns: Vec<String> = self
+            .routes
+            .routes
+            .iter()
+            .map(|route| route.pattern.clone())
+            .collect();
+        let routes = Arc::new(self.routes);
+
+        let mut handles = Vec::with_capacity(patterns.len());
+        for pattern in patterns {
+            let handler = dispatch(
+                self.nats_server.clone(),
+                routes.clone(),
+                Some(pattern.clone()),
+            );
+            let handle = receiver
+                .subscribe_with_options(pattern, options.clone(), handler)
+                .await?;
+            handles.push(handle);
+        }
+
+        Ok(handles)
+    }
+
+    /// Subscribes once to the wildcard subject (e.g. `chat.>`), dispatching every message
+    /// it receives
+    pub async fn subscribe_wildcard(
+        self,
+        receiver: &dyn Subscribe,
+        subject: String,
+        options: SubscribeOptions,
+    ) -> Result<SubscriptionHandle, NatsTransportError> {
+        let handler = dispatch(self.nats_server, Arc::new(self.routes), None);
+        receiver
+            .subscribe_with_options(subject, options, handler)
+            .await
+    }
+}
+
+impl Routes {
+    pub fn new() -> Self {
+        Self {
+            routes: vec![],
+            reply_format: ReplyFormat::default(),
+            error_domain: DEFAULT_ERROR_DOMAIN.to_string(),
+        }
+    }
+
+    pub fn insert(&mut self, pattern: String, handler: MessageHandler) {
+        match self
+            .routes
+            .iter_mut()
+            .find(|route| route.pattern == pattern)
+        {
+            Some(route) => route.handler = handler,
+            None => self.routes.push(Route { pattern, handler }),
+        }
+    }
+
+    /// Most specific route matching the subject
+    pub fn route_for(&self, subject: &str) -> Option<&Route> {
+        let subject: Vec<&str> = subject.split('.').collect();
+
+        self.routes
+            .iter()
+            .filter_map(|route| specificity(&route.pattern, &subject).map(|rank| (rank, route)))
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, route)| route)
+    }
+
+    /// Error reply for a request without a matching route (None if the message is not a request,
+    /// whose error is logged instead)
+    pub fn unsupported_request(&self, message: Message) -> Option<Reply> {
+        let request = message.subject.to_string();
+        let requestor = message
+            .headers
+            .as_ref()
+            .map(RequestHeaders::from)
+            .and_then(|headers| headers.requestor());
+
+        let mut model = ErrorModel::new(
+            Status::Unimplemented,
+            501,
+            format!("No handler for subject: {}", request),
+        )
+        .with_details(ErrorReason::UnsupportedRequest, self.error_domain.clone())
+        .append_metadata(MetaKeys::Request, request);
+
+        if let Some(requestor) = requestor {
+            model = model.append_metadata(MetaKeys::Requestor, requestor.to_string());
+        }
+
+        let reply = reply_subject(&message.subject, message.reply, Some(&model))?;
+
+        let (headers, payload) = match self.reply_format {
+            ReplyFormat::Json => {
+                let response: NatsResponse<(), ErrorReason> = NatsResponse {
+                    error: Some(model),
+                    data: None,
+                };
+                let payload = NatsJson::<NatsResponse<(), ErrorReason>>::default()
+                    .serialize(response)
+                    .ok()?;
+                (None, payload)
+            }
+            ReplyFormat::Prost => {
+                let (headers, payload) = error_reply(model);
+                (Some(headers), payload)
+            }
+        };
+
+        Some(Reply {
+            subject: reply,
+            headers,
+            payload,
+        })
+    }
+}
+
+/// Subscription handler dispatching the messages it receives. A handler subscribed to a
+/// route pattern skips the messages routed to a more specific pattern.
+fn dispatch(
+    nats_server: Arc<NatsServer>,
+    routes: Arc<Routes>,
+    subscription: Option<String>,
+) -> MessageHandler {
+    Arc::new(
+        move |message: Message| match routes.route_for(message.subject.as_ref()) {
+            Some(route)
+                if subscription.is_none() || subscription.as_ref() == Some(&route.pattern) =>
+            {
+                (route.handler)(message)
+            }
+            Some(_) => futures::future::ready(()).boxed(),
+            None => {
+                let reply = routes.unsupported_request(message);
+                let nats_server = nats_server.clone();
+
+                async move {
+                    if let Some(reply) = reply {
+                        reply.send(&nats_server).await;
+                    }
+                }
+                .boxed()
+            }
+        },
+    )
+}
+
+/// Ranks how specifically the pattern matches the subject tokens, None if it does not match.
+/// Ranks compare token by token: literal > `*` > `>`.
+fn specificity(pattern: &str, subject: &[&str]) -> Option<Vec<u8>> {
+    let mut rank = Vec::with_capacity(subject.len());
+
+    for (position, token) in pattern.split('.').enumerate() {
+        match token {
+            ">" => {
+                return (position < subject.len()).then(|| {
+                    rank.push(0);
+                    rank
+                })
+            }
+            _ if position >= subject.len() => return None,
+            "*" => rank.push(1),
+            token if token == subject[position] => rank.push(2),
+            _ => return None,
+        }
+    }
+
+    (rank.len() == subject.len()).then_some(rank)
+}
+
+#[cfg(test)]
+#[path = "./router_tests.rs"]
+mod router_tests;
@@ -0,0 +1,164 @@
+#[cfg(test)]
+mod router_tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use bytes::Bytes;
+    use chat_proto::runtiva::nats::v1 as proto_nats;
+    use prost::Message as _;
+
+    use crate::{
+        error::{ErrorReason, MetaKeys, Status},
+        response::{NatsResponse, ERROR_REPLY_HEADER},
+        server::receiver::{message_handler, test_message},
+    };
+
+    use super::super::{specificity, ReplyFormat, Routes};
+
+    fn routes(patterns: &[&str]) -> Routes {
+        let mut routes = Routes::new();
+        for pattern in patterns {
+            routes.insert(pattern.to_string(), message_handler(|_| async {}));
+        }
+        routes
+    }
+
+    fn routed(routes: &Routes, subject: &str) -> Option<String> {
+        routes.route_for(subject).map(|route| route.pattern.clone())
+    }
+
+    #[test]
+    fn test_specificity() {
+        let subject = ["chat", "chatgroup", "event", "created"];
+
+        assert_eq!(
+            specificity("chat.chatgroup.event.created", &subject),
+            Some(vec![2, 2, 2, 2])
+        );
+        assert_eq!(
+            specificity("chat.*.event.>", &subject),
+            Some(vec![2, 1, 2, 0])
+        );
+        assert_eq!(specificity("chat.>", &subject), Some(vec![2, 0]));
+        assert_eq!(specificity("chat.chatgroup.event", &subject), None);
+        assert_eq!(
+            specificity("chat.chatgroup.event.created.>", &subject),
+            None
+        );
+        assert_eq!(specificity("chat.user.>", &subject), None);
+    }
+
+    #[test]
+    fn test_most_specific_route() {
+        let routes = routes(&[
+            "chat.>",
+            "chat.*.event.>",
+            "chat.chatgroup.command.*",
+            "chat.chatgroup.command.create",
+        ]);
+
+        assert_eq!(
+            routed(&routes, "chat.chatgroup.command.create").unwrap(),
+            "chat.chatgroup.command.create"
+        );
+        assert_eq!(
+            routed(&routes, "chat.chatgroup.command.rename").unwrap(),
+            "chat.chatgroup.command.*"
+        );
+        assert_eq!(
+            routed(&routes, "chat.user.event.created.1234").unwrap(),
+            "chat.*.event.>"
+        );
+        assert_eq!(routed(&routes, "chat.user.query.get").unwrap(), "chat.>");
+        assert_eq!(routed(&routes, "billing.invoice.event.paid"), None);
+    }
+
+    #[tokio::test]
+    async fn test_route_replaces_handler() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut routes = routes(&["chat.chatgroup.command.create"]);
+
+        let counter = calls.clone();
+        routes.insert(
+            "chat.chatgroup.command.create".to_string(),
+            message_handler(move |_| {
+                let counter = counter.clone();
+                async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                }
+            }),
+        );
+
+        let route = routes.route_for("chat.chatgroup.command.create").unwrap();
+        (route.handler)(test_message(
+            "chat.chatgroup.command.create",
+            None,
+            Bytes::new(),
+        ))
+        .await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_unsupported_request_json_reply() {
+        let routes = routes(&["chat.chatgroup.command.create"]);
+
+        let reply = routes
+            .unsupported_request(test_message(
+                "chat.chatgroup.command.delete",
+                Some("_INBOX.1"),
+                Bytes::new(),
+            ))
+            .unwrap();
+
+        assert_eq!(reply.subject, "_INBOX.1");
+        assert!(reply.headers.is_none());
+
+        let response: NatsResponse<(), ErrorReason> =
+            serde_json::from_slice(&reply.payload).unwrap();
+        let error = response.error.unwrap();
+
+        assert_eq!(error.status, Status::Unimplemented);
+        assert_eq!(error.details[0].reason, ErrorReason::UnsupportedRequest);
+        assert_eq!(
+            error.details[0].metadata.get(&MetaKeys::Request).unwrap(),
+            "chat.chatgroup.command.delete"
+        );
+    }
+
+    #[test]
+    fn test_unsupported_request_prost_reply() {
+        let mut routes = routes(&[]);
+        routes.reply_format = ReplyFormat::Prost;
+
+        let reply = routes
+            .unsupported_request(test_message(
+                "chat.chatgroup.command.delete",
+                Some("_INBOX.1"),
+                Bytes::new(),
+            ))
+            .unwrap();
+
+        assert!(reply.headers.unwrap().get(ERROR_REPLY_HEADER).is_some());
+
+        let error = proto_nats::ErrorReply::decode(reply.payload).unwrap();
+        assert_eq!(error.status, Status::Unimplemented as i32);
+        assert_eq!(error.details[0].reason, "UNSUPPORTED_REQUEST".to_string());
+    }
+
+    #[test]
+    fn test_unmatched_publish_is_not_answered() {
+        let routes = routes(&[]);
+
+        assert!(routes
+            .unsupported_request(test_message(
+                "chat.chatgroup.event.created",
+                None,
+                Bytes::new()
+            ))
+            .is_none());
+    }
+}
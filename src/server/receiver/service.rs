@@ -0,0 +1,372 @@
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use async_nats::Message;
+use futures::{
+    future::{self, BoxFuture},
+    task::noop_waker_ref,
+    FutureExt,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use tower::{load_shed::error::Overloaded, timeout::error::Elapsed, BoxError, Service};
+
+use crate::{
+    error::{ErrorModel, Status},
+    request::{Converter, NatsEnvelope, TryFromNatsRequest},
+    response::NatsResponse,
+    server::{
+        serde::{Deserializer, Serde},
+        NatsServer,
+    },
+};
+
+use super::{
+    handler::{
+        deadline_exceeded, json_reply, json_request, prost_reply, prost_request, reply_subject,
+        within_deadline, Reply,
+    },
+    MessageHandler,
+};
+
+/// Polls the readiness of the service of a subscription
+pub(crate) type Readiness = Arc<dyn Fn(&mut Context<'_>) -> Poll<()> + Send + Sync>;
+
+/// Subscription callback driven by a [tower::Service], see
+/// [Subscribe::subscribe_service](super::Subscribe::subscribe_service).
+///
+/// The receiver waits for the service to be ready before taking the next message off the
+/// subscription, then calls it right away: a service that is not ready (e.g. a saturated
+/// concurrency limit) leaves the messages on the server instead of buffering them.
+pub struct MessageService {
+    pub(crate) ready: Readiness,
+    pub(crate) handler: MessageHandler,
+}
+
+impl MessageService {
+    /// Drives a service processing the raw messages. Service errors are logged.
+    pub fn new<S>(service: S) -> Self
+    where
+        S: Service<Message, Response = ()> + Send + 'static,
+        S::Future: Send + 'static,
+        S::Error: Into<BoxError>,
+    {
+        let service = Arc::new(Mutex::new(service));
+
+        MessageService {
+            ready: readiness(&service),
+            handler: Arc::new(move |message: Message| {
+                let subject = message.subject.to_string();
+                let response = call(&service, message);
+
+                async move {
+                    if let Err(err) = response.await {
+                        tracing::warn!(subject, error = %err, "failed to process message");
+                    }
+                }
+                .boxed()
+            }),
+        }
+    }
+}
+
+/// Serves JSON requests through a [tower::Service], so the tower middleware (timeouts,
+/// concurrency limits, load shedding, ...) can be layered around the handler.
+///
+/// The response is published back to the reply subject as with [json_handler](super::json_handler).
+/// Service errors are replied as an [ErrorModel]: `DeadlineExceeded` for a timeout,
+/// `Unavailable` for a shed load and `Internal` otherwise. As with
+/// [json_handler](super::json_handler), requests whose deadline passed are replied
+/// `DeadlineExceeded` without calling the service.
+///
+/// ```ignore
+/// let service = ServiceBuilder::new()
+///     .concurrency_limit(16)
+///     .timeout(Duration::from_secs(5))
+///     .service_fn(create_chat_group);
+///
+/// receiver
+///     .subscribe_service(
+///         "chat.chatgroup.command.create".to_string(),
+///         SubscribeOptions::new().concurrency(16),
+///         json_service(nats.clone(), service),
+///     )
+///     .await?;
+///
+/// async fn create_chat_group(
+///     request: NatsEnvelope<CreateChatGroupRequest>,
+/// ) -> Result<StandardNatsResponse<ChatGroup>, Infallible> {
+///     ...
+/// }
+/// ```
+pub fn json_service<Req, Resp, R, S>(nats_server: Arc<NatsServer>, service: S) -> MessageService
+where
+    Req: DeserializeOwned + Send + 'static,
+    Resp: Serialize + Send + 'static,
+    R: Serialize + Send + 'static,
+    S: Service<NatsEnvelope<Req>, Response = NatsResponse<Resp, R>> + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<BoxError>,
+{
+    let service = Arc::new(Mutex::new(service));
+
+    MessageService {
+        ready: readiness(&service),
+        handler: Arc::new(move |message: Message| {
+            let nats_server = nats_server.clone();
+            let reply = serve_json(&service, message);
+
+            async move {
+                if let Some(reply) = reply.await {
+                    reply.send(&nats_server).await;
+                }
+            }
+            .boxed()
+        }),
+    }
+}
+
+/// Serves protobuf requests through a [tower::Service], see [json_service].
+///
+/// The request is decoded through the [Converter], and the response is published back as with
+/// [prost_handler](super::prost_handler): the data of the [NatsResponse] on success, or its
+/// error as a `proto_nats::ErrorReply`.
+pub fn prost_service<Msg, OutMsg, S, Resp, R, Svc>(
+    nats_server: Arc<NatsServer>,
+    converter: Converter<Msg, OutMsg, S>,
+    service: Svc,
+) -> MessageService
+where
+    Msg: TryFromNatsRequest<OutMsg> + Debug + Send + Sync + 'static,
+    <Msg as TryFromNatsRequest<OutMsg>>::Error: std::error::Error + Send + Sync + 'static,
+    OutMsg: prost::Message + Send + Sync + 'static,
+    S: Serde<OutMsg> + Send + Sync + 'static,
+    <S as Deserializer<OutMsg>>::Error: std::error::Error + Send + Sync + 'static,
+    Resp: prost::Message + Default + Send + 'static,
+    R: ToString + Send + 'static,
+    Svc: Service<NatsEnvelope<Msg>, Response = NatsResponse<Resp, R>> + Send + 'static,
+    Svc::Future: Send + 'static,
+    Svc::Error: Into<BoxError>,
+{
+    let service = Arc::new(Mutex::new(service));
+
+    MessageService {
+        ready: readiness(&service),
+        handler: Arc::new(move |message: Message| {
+            let nats_server = nats_server.clone();
+            let reply = serve_prost(&converter, &service, message);
+
+            async move {
+                if let Some(reply) = reply.await {
+                    reply.send(&nats_server).await;
+                }
+            }
+            .boxed()
+        }),
+    }
+}
+
+/// Waits for the service of a subscription to be ready, when it is driven by one
+pub(crate) async fn ready(readiness: Option<&Readiness>) {
+    if let Some(readiness) = readiness {
+        future::poll_fn(|cx| readiness(cx)).await;
+    }
+}
+
+/// Readiness of the shared service. A readiness error resolves it as well, the error is then
+/// returned by the next call.
+fn readiness<Req, S>(service: &Arc<Mutex<S>>) -> Readiness
+where
+    S: Service<Req> + Send + 'static,
+{
+    let service = service.clone();
+
+    Arc::new(move |cx| {
+        let mut service = service.lock().expect("service is never poisoned");
+        service.poll_ready(cx).map(|_| ())
+    })
+}
+
+/// Decodes the message and calls the service, then builds the JSON reply (if the message
+/// expects one)
+pub(crate) fn serve_json<Req, Resp, R, S>(
+    service: &Arc<Mutex<S>>,
+    message: Message,
+) -> BoxFuture<'static, Option<Reply>>
+where
+    Req: DeserializeOwned + Send + 'static,
+    Resp: Serialize + Send + 'static,
+    R: Serialize + Send + 'static,
+    S: Service<NatsEnvelope<Req>, Response = NatsResponse<Resp, R>> + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<BoxError>,
+{
+    let Message {
+        subject,
+        reply,
+        headers,
+        payload,
+        ..
+    } = message;
+
+    let response = match json_request(headers.as_ref(), payload) {
+        Ok(envelope) => call_within_deadline(service, envelope),
+        Err(model) => future::ready(Err(model)).boxed(),
+    };
+
+    async move {
+        let response = response.await.unwrap_or_else(|model| NatsResponse {
+            error: Some(model),
+            data: None,
+        });
+
+        let reply = reply_subject(&subject, reply, response.error.as_ref())?;
+        json_reply(reply, response)
+    }
+    .boxed()
+}
+
+/// Decodes the message and calls the service, then builds the protobuf reply (if the message
+/// expects one)
+pub(crate) fn serve_prost<Msg, OutMsg, S, Resp, R, Svc>(
+    converter: &Converter<Msg, OutMsg, S>,
+    service: &Arc<Mutex<Svc>>,
+    message: Message,
+) -> BoxFuture<'static, Option<Reply>>
+where
+    Msg: TryFromNatsRequest<OutMsg> + Debug + Send + 'static,
+    <Msg as TryFromNatsRequest<OutMsg>>::Error: std::error::Error + Send + Sync + 'static,
+    OutMsg: prost::Message,
+    S: Serde<OutMsg>,
+    <S as Deserializer<OutMsg>>::Error: std::error::Error + Send + Sync + 'static,
+    Resp: prost::Message + Default + Send + 'static,
+    R: ToString + Send + 'static,
+    Svc: Service<NatsEnvelope<Msg>, Response = NatsResponse<Resp, R>> + Send + 'static,
+    Svc::Future: Send + 'static,
+    Svc::Error: Into<BoxError>,
+{
+    let Message {
+        subject,
+        reply,
+        headers,
+        payload,
+        ..
+    } = message;
+
+    let response = match prost_request(converter, headers.as_ref(), payload) {
+        Ok(envelope) => call_within_deadline(service, envelope),
+        Err(model) => future::ready(Err(model)).boxed(),
+    };
+
+    async move {
+        let result = match response.await {
+            Ok(NatsResponse {
+                error: Some(model), ..
+            }) => Err(model),
+            Ok(NatsResponse { data, .. }) => Ok(data.unwrap_or_default()),
+            Err(model) => Err(model),
+        };
+
+        let reply = reply_subject(&subject, reply, result.as_ref().err())?;
+        Some(prost_reply(reply, result))
+    }
+    .boxed()
+}
+
+/// Calls the service, unless the request deadline has passed, replying `DeadlineExceeded`
+/// instead when it passes before the service responded
+fn call_within_deadline<Req, Resp, R, S>(
+    service: &Arc<Mutex<S>>,
+    request: NatsEnvelope<Req>,
+) -> BoxFuture<'static, Result<NatsResponse<Resp, R>, ErrorModel<R>>>
+where
+    Req: Send + 'static,
+    Resp: Send + 'static,
+    R: Send + 'static,
+    S: Service<NatsEnvelope<Req>, Response = NatsResponse<Resp, R>> + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<BoxError>,
+{
+    match request.remaining() {
+        Some(remaining) if remaining.is_zero() => future::ready(Err(deadline_exceeded())).boxed(),
+        remaining => {
+            let response = call(service, request);
+
+            async move {
+                within_deadline(remaining, || response)
+                    .await?
+                    .map_err(service_error)
+            }
+            .boxed()
+        }
+    }
+}
+
+/// Calls the service right away when it is ready, as it is once the receiver waited for its
+/// readiness before taking the message, and otherwise once it becomes ready. The service is
+/// only locked while it is polled and called, so the responses of several requests can be
+/// awaited concurrently.
+fn call<Req, S>(
+    service: &Arc<Mutex<S>>,
+    request: Req,
+) -> BoxFuture<'static, Result<S::Response, BoxError>>
+where
+    Req: Send + 'static,
+    S: Service<Req> + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<BoxError>,
+{
+    let mut request = Some(request);
+    let mut ready_call = {
+        let service = service.clone();
+
+        move |cx: &mut Context<'_>| {
+            let mut service = service.lock().expect("service is never poisoned");
+            match service.poll_ready(cx) {
+                Poll::Ready(Ok(())) => {
+                    let request = request.take().expect("the service is called once");
+                    Poll::Ready(Ok(service.call(request)))
+                }
+                Poll::Ready(Err(err)) => Poll::Ready(Err(err.into())),
+                Poll::Pending => Poll::Pending,
+            }
+        }
+    };
+
+    let called = ready_call(&mut Context::from_waker(noop_waker_ref()));
+
+    async move {
+        let response = match called {
+            Poll::Ready(response) => response,
+            Poll::Pending => future::poll_fn(ready_call).await,
+        };
+
+        response?.await.map_err(Into::into)
+    }
+    .boxed()
+}
+
+/// ErrorModel returned when the service fails instead of returning a response
+pub(crate) fn service_error<R>(err: BoxError) -> ErrorModel<R> {
+    if err.is::<Elapsed>() {
+        ErrorModel::new(
+            Status::DeadlineExceeded,
+            504,
+            format!("Request timed out: {}", err),
+        )
+    } else if err.is::<Overloaded>() {
+        ErrorModel::new(
+            Status::Unavailable,
+            503,
+            format!("Service overloaded: {}", err),
+        )
+    } else {
+        ErrorModel::new(Status::Internal, 500, format!("Service failed: {}", err))
+    }
+}
+
+#[cfg(test)]
+#[path = "./service_tests.rs"]
+mod service_tests;
@@ -0,0 +1,233 @@
+#[cfg(test)]
+mod service_tests {
+    use std::{
+        convert::Infallible,
+        sync::{Arc, Mutex},
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    };
+
+    use async_nats::HeaderMap;
+    use bytes::Bytes;
+    use chat_proto::runtiva::nats::v1 as proto_nats;
+    use prost::Message as _;
+    use tower::{load_shed::LoadShed, service_fn, timeout::Timeout};
+
+    use crate::{
+        error::{ErrorReason, Status},
+        request::{Converter, NatsEnvelope, DEADLINE_HEADER},
+        response::{NatsResponse, StandardNatsResponse, ERROR_REPLY_HEADER},
+        server::{receiver::test_message, serde::NatsMessageSerde},
+    };
+
+    use super::super::{serve_json, serve_prost};
+    use super::{ChatGroupCreate, NeverReady};
+
+    fn json_payload(title: &str) -> Bytes {
+        serde_json::to_vec(&ChatGroupCreate {
+            title: title.to_string(),
+        })
+        .unwrap()
+        .into()
+    }
+
+    async fn echo(
+        request: NatsEnvelope<ChatGroupCreate>,
+    ) -> Result<StandardNatsResponse<String>, Infallible> {
+        Ok(NatsResponse::new(request.data.title))
+    }
+
+    #[tokio::test]
+    async fn test_json_service_replies_with_response() {
+        let service = Arc::new(Mutex::new(service_fn(echo)));
+
+        let reply = serve_json(
+            &service,
+            test_message(
+                "chat.chatgroup.command.create",
+                Some("_INBOX.1"),
+                json_payload("Hello"),
+            ),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(reply.subject, "_INBOX.1");
+        let response: StandardNatsResponse<String> =
+            serde_json::from_slice(&reply.payload).unwrap();
+        assert_eq!(response.data.unwrap(), "Hello".to_string());
+    }
+
+    #[tokio::test]
+    async fn test_json_service_timeout() {
+        let slow = service_fn(|request: NatsEnvelope<ChatGroupCreate>| async move {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            echo(request).await
+        });
+        let service = Arc::new(Mutex::new(Timeout::new(slow, Duration::from_millis(10))));
+
+        let reply = serve_json(
+            &service,
+            test_message(
+                "chat.chatgroup.command.create",
+                Some("_INBOX.1"),
+                json_payload("Hello"),
+            ),
+        )
+        .await
+        .unwrap();
+
+        let response: StandardNatsResponse<String> =
+            serde_json::from_slice(&reply.payload).unwrap();
+        assert_eq!(response.error.unwrap().status, Status::DeadlineExceeded);
+    }
+
+    #[tokio::test]
+    async fn test_json_service_sheds_load_when_not_ready() {
+        let service = Arc::new(Mutex::new(LoadShed::new(NeverReady)));
+
+        let reply = serve_json::<ChatGroupCreate, String, ErrorReason, _>(
+            &service,
+            test_message(
+                "chat.chatgroup.command.create",
+                Some("_INBOX.1"),
+                json_payload("Hello"),
+            ),
+        )
+        .await
+        .unwrap();
+
+        let response: StandardNatsResponse<String> =
+            serde_json::from_slice(&reply.payload).unwrap();
+        assert_eq!(response.error.unwrap().status, Status::Unavailable);
+    }
+
+    #[tokio::test]
+    async fn test_json_service_skips_expired_deadline() {
+        // without the deadline, the request would wait for the service forever
+        let service = Arc::new(Mutex::new(NeverReady));
+
+        let deadline = SystemTime::now() - Duration::from_secs(1);
+        let millis = deadline.duration_since(UNIX_EPOCH).unwrap().as_millis();
+        let mut headers = HeaderMap::new();
+        headers.insert(DEADLINE_HEADER, millis.to_string().as_str());
+        let mut message = test_message(
+            "chat.chatgroup.command.create",
+            Some("_INBOX.1"),
+            json_payload("Hello"),
+        );
+        message.headers = Some(headers);
+
+        let reply = serve_json::<ChatGroupCreate, String, ErrorReason, _>(&service, message)
+            .await
+            .unwrap();
+
+        let response: StandardNatsResponse<String> =
+            serde_json::from_slice(&reply.payload).unwrap();
+        assert_eq!(response.error.unwrap().status, Status::DeadlineExceeded);
+    }
+
+    #[tokio::test]
+    async fn test_json_service_rejects_invalid_payload() {
+        let service = Arc::new(Mutex::new(service_fn(echo)));
+
+        let reply = serve_json(
+            &service,
+            test_message(
+                "chat.chatgroup.command.create",
+                Some("_INBOX.1"),
+                Bytes::from_static(b"not json"),
+            ),
+        )
+        .await
+        .unwrap();
+
+        let response: StandardNatsResponse<String> =
+            serde_json::from_slice(&reply.payload).unwrap();
+        assert_eq!(response.error.unwrap().status, Status::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_prost_service_replies_with_error_reply() {
+        let converter =
+            Converter::new(NatsMessageSerde::<proto_nats::NatsChatGroupCreateRequest>::default());
+        let service = Arc::new(Mutex::new(service_fn(
+            |_: NatsEnvelope<ChatGroupCreate>| async {
+                Err::<NatsResponse<proto_nats::CreateChatGroupRequest, ErrorReason>, _>(
+                    "database unavailable",
+                )
+            },
+        )));
+
+        let payload = proto_nats::NatsChatGroupCreateRequest {
+            headers: vec![],
+            data: Some(proto_nats::CreateChatGroupRequest {
+                title: "Hello".to_string(),
+                ..Default::default()
+            }),
+        }
+        .encode_to_vec();
+
+        let reply = serve_prost(
+            &converter,
+            &service,
+            test_message("chat.chatgroup.command.create", Some("_INBOX.1"), payload),
+        )
+        .await
+        .unwrap();
+
+        assert!(reply.headers.unwrap().get(ERROR_REPLY_HEADER).is_some());
+        let error = proto_nats::ErrorReply::decode(reply.payload).unwrap();
+        assert_eq!(error.status, Status::Internal as i32);
+    }
+}
+
+use std::{
+    convert::Infallible,
+    task::{Context, Poll},
+};
+
+use chat_proto::runtiva::nats::v1 as proto_nats;
+use futures::future::Ready;
+use serde::{Deserialize, Serialize};
+use tower::Service;
+
+use crate::{
+    request::{NatsEnvelope, RequestHeaders, TryFromNatsRequest},
+    response::StandardNatsResponse,
+    server::NatsTransportError,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ChatGroupCreate {
+    pub title: String,
+}
+
+impl TryFromNatsRequest<proto_nats::NatsChatGroupCreateRequest> for ChatGroupCreate {
+    type Error = NatsTransportError;
+
+    fn try_from(
+        value: proto_nats::NatsChatGroupCreateRequest,
+    ) -> Result<(Self, RequestHeaders), Self::Error> {
+        let headers: RequestHeaders = value.headers.try_into()?;
+        let title = value.data.map(|data| data.title).unwrap_or_default();
+
+        Ok((ChatGroupCreate { title }, headers))
+    }
+}
+
+/// Service whose readiness never resolves, e.g. a saturated concurrency limit
+struct NeverReady;
+
+impl Service<NatsEnvelope<ChatGroupCreate>> for NeverReady {
+    type Response = StandardNatsResponse<String>;
+    type Error = Infallible;
+    type Future = Ready<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Pending
+    }
+
+    fn call(&mut self, _request: NatsEnvelope<ChatGroupCreate>) -> Self::Future {
+        unreachable!("the service is never ready")
+    }
+}
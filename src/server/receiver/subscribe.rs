@@ -2,22 +2,118 @@ use std::sync::Arc;
 
 use async_nats::Message;
 use async_trait::async_trait;
-use futures::{future::BoxFuture, Future};
+use futures::{future::BoxFuture, Future, FutureExt};
 
-use crate::server::NatsServer;
+use crate::server::NatsTransportError;
+
+use super::{
+    ack::ignore_outcome, MessageService, OutcomeHandler, SubscribeOptions, SubscriptionHandle,
+};
+
+/// Type-erased subscription callback, called for each received message
+pub type MessageHandler = Arc<dyn Fn(Message) -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// Wraps an async callback into a [MessageHandler]
+pub fn message_handler<F, Fut>(proc: F) -> MessageHandler
+where
+    F: Fn(Message) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    Arc::new(move |message| proc(message).boxed())
+}
 
 /// Trait for subscribing to a NATs subject.
 /// examples of subject: `name.abc`, `name.abc.>`, `name.abc.*`, `name.abc.*.def`
+///
+/// The subscription is created before returning, so an invalid subject or a missing
+/// permission is returned as an error. The returned [SubscriptionHandle] is used to
+/// unsubscribe, drain or await the subscription.
+///
+/// The trait is object safe, so a receiver can be shared as an `Arc<dyn Subscribe>`
+/// (e.g. to inject a mock receiver in tests). The generic callback shapes are provided
+/// by [SubscribeExt].
+#[async_trait]
+pub trait Subscribe: Send + Sync {
+    /// Subscribes to a NATs subject with the given [SubscribeOptions]
+    /// (queue group, concurrency limit and per-key ordering).
+    /// The handler is called for each message received.
+    async fn subscribe_with_options(
+        &self,
+        subject: String,
+        options: SubscribeOptions,
+        handler: MessageHandler,
+    ) -> Result<SubscriptionHandle, NatsTransportError>;
+
+    /// Subscribes with a handler deciding how each message is acknowledged, see
+    /// [AckOutcome](super::AckOutcome). Receivers without acknowledgements (e.g. core NATS
+    /// subscriptions) ignore the outcome.
+    async fn subscribe_with_outcome(
+        &self,
+        subject: String,
+        options: SubscribeOptions,
+        handler: OutcomeHandler,
+    ) -> Result<SubscriptionHandle, NatsTransportError> {
+        self.subscribe_with_options(subject, options, ignore_outcome(handler))
+            .await
+    }
+
+    /// Subscribes with a [MessageService] (see [json_service](super::json_service)): the
+    /// service is polled for readiness before each message is taken off the subscription and
+    /// called right away, so its middleware (e.g. a concurrency limit) holds the messages back.
+    /// The concurrency of the [SubscribeOptions] still bounds the responses awaited at once.
+    ///
+    /// Receivers without a receive loop (e.g. test doubles) call the handler of the service,
+    /// which waits for the readiness itself.
+    async fn subscribe_service(
+        &self,
+        subject: String,
+        options: SubscribeOptions,
+        service: MessageService,
+    ) -> Result<SubscriptionHandle, NatsTransportError> {
+        self.subscribe_with_options(subject, options, service.handler)
+            .await
+    }
+}
+
+/// Convenience methods accepting any async callback, for all [Subscribe] implementations
+/// (including `dyn Subscribe`).
 /// F is the callback function that will be called when a message is received.
-#[async_trait(?Send)]
-pub trait Subscribe {
+#[async_trait]
+pub trait SubscribeExt: Subscribe {
     async fn subscribe<F, Fut>(
         &self,
-        nats_server: Arc<NatsServer>,
         subject: String,
         proc: F,
-    ) -> Result<(), BoxFuture<dyn std::error::Error + Send + Sync>>
+    ) -> Result<SubscriptionHandle, NatsTransportError>
     where
         F: Fn(Message) -> Fut + Send + Sync + 'static,
-        Fut: Future<Output = ()> + Send + Sync;
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.subscribe_with_options(subject, SubscribeOptions::new(), message_handler(proc))
+            .await
+    }
+
+    /// Subscribes to a NATs subject as a member of a queue group.
+    /// Each message is delivered to only one subscriber of the queue group, which load-balances
+    /// processing (e.g. commands on `chat.*.command.*`) across service replicas.
+    async fn queue_subscribe<F, Fut>(
+        &self,
+        subject: String,
+        queue_group: String,
+        proc: F,
+    ) -> Result<SubscriptionHandle, NatsTransportError>
+    where
+        F: Fn(Message) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let options = SubscribeOptions::new().queue_group(queue_group);
+        self.subscribe_with_options(subject, options, message_handler(proc))
+            .await
+    }
 }
+
+impl<S> SubscribeExt for S where S: Subscribe + ?Sized {}
+
+#[cfg(test)]
+#[path = "./subscribe_tests.rs"]
+mod subscribe_tests;
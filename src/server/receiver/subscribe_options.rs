@@ -0,0 +1,73 @@
+/// Highest [SubscribeOptions::concurrency] limit
+pub const MAX_CONCURRENCY: usize = 4096;
+
+/// Options for a subscription created with [Subscribe::subscribe_with_options](super::Subscribe::subscribe_with_options)
+///
+/// By default messages are processed one at a time, in the order they are received.
+///
+/// ```
+/// use nats_transport::server::receiver::SubscribeOptions;
+///
+/// // process up to 16 messages of `chat.topic.>` in parallel,
+/// // while keeping the messages of each chat (`chat.topic.<chat_id>`) in order
+/// let options = SubscribeOptions::new()
+///     .concurrency(16)
+///     .ordered_by_subject_token(2);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscribeOptions {
+    pub(crate) queue_group: Option<String>,
+    pub(crate) concurrency: usize,
+    pub(crate) ordering: Option<OrderingKey>,
+}
+
+/// Part of the message used to keep related messages in order when processing concurrently
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderingKey {
+    /// Zero based index of a subject token, e.g. `2` for the chat id in `chat.topic.<chat_id>.>`
+    SubjectToken(usize),
+}
+
+impl Default for SubscribeOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SubscribeOptions {
+    pub fn new() -> Self {
+        Self {
+            queue_group: None,
+            concurrency: 1,
+            ordering: None,
+        }
+    }
+
+    /// Joins the given queue group, so each message is delivered to a single member of the group
+    pub fn queue_group(mut self, queue_group: impl Into<String>) -> Self {
+        self.queue_group = Some(queue_group.into());
+        self
+    }
+
+    /// Maximum number of messages processed at the same time (from 1 to [MAX_CONCURRENCY])
+    pub fn concurrency(mut self, limit: usize) -> Self {
+        self.concurrency = limit.clamp(1, MAX_CONCURRENCY);
+        self
+    }
+
+    /// Processes messages with the same value of the given subject token in order,
+    /// one at a time, while other keys are processed concurrently
+    pub fn ordered_by_subject_token(mut self, index: usize) -> Self {
+        self.ordering = Some(OrderingKey::SubjectToken(index));
+        self
+    }
+}
+
+impl OrderingKey {
+    /// Extracts the key from the subject. Subjects without the token share the empty key.
+    pub(crate) fn key<'a>(&self, subject: &'a str) -> &'a str {
+        match self {
+            OrderingKey::SubjectToken(index) => subject.split('.').nth(*index).unwrap_or_default(),
+        }
+    }
+}
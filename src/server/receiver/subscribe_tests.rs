@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod subscribe_tests {
+    use std::sync::{Arc, Mutex};
+
+    use async_nats::Message;
+    use async_trait::async_trait;
+    use bytes::Bytes;
+
+    use crate::server::{
+        receiver::{
+            test_message, MessageHandler, Subscribe, SubscribeExt, SubscribeOptions,
+            SubscriptionHandle,
+        },
+        NatsTransportError,
+    };
+
+    /// Records the subscriptions and delivers a single message to each handler
+    #[derive(Default)]
+    struct MockReceiver {
+        subscriptions: Mutex<Vec<(String, SubscribeOptions)>>,
+    }
+
+    #[async_trait]
+    impl Subscribe for MockReceiver {
+        async fn subscribe_with_options(
+            &self,
+            subject: String,
+            options: SubscribeOptions,
+            handler: MessageHandler,
+        ) -> Result<SubscriptionHandle, NatsTransportError> {
+            self.subscriptions
+                .lock()
+                .unwrap()
+                .push((subject.clone(), options));
+
+            let message = test_message(&subject, None, Bytes::new());
+
+            let task = tokio::spawn(async move {
+                handler(message).await;
+                Ok(())
+            });
+
+            Ok(SubscriptionHandle::from_task(subject, task))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_through_trait_object() -> Result<(), NatsTransportError> {
+        let mock = Arc::new(MockReceiver::default());
+        let receiver: Arc<dyn Subscribe> = mock.clone();
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+
+        // the trait object can be moved into a spawned task
+        let handle = tokio::spawn(async move {
+            receiver
+                .queue_subscribe(
+                    "chat.*.command.*".to_string(),
+                    "chat-persist".to_string(),
+                    move |msg: Message| {
+                        let sink = sink.clone();
+                        async move { sink.lock().unwrap().push(msg.subject.to_string()) }
+                    },
+                )
+                .await
+        })
+        .await
+        .unwrap()?;
+
+        assert_eq!(handle.subject(), "chat.*.command.*");
+        handle.join().await?;
+
+        let subscriptions = mock.subscriptions.lock().unwrap().clone();
+        assert_eq!(
+            subscriptions,
+            vec![(
+                "chat.*.command.*".to_string(),
+                SubscribeOptions::new().queue_group("chat-persist")
+            )]
+        );
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec!["chat.*.command.*".to_string()]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use tokio::{sync::watch, task::JoinHandle};
+
+use crate::server::NatsTransportError;
+
+/// Control signal sent from a [SubscriptionHandle] to its receiver task
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SubscriptionControl {
+    Active,
+    Unsubscribe,
+    Drain,
+}
+
+/// Handle to a running subscription created by [Subscribe](super::Subscribe).
+///
+/// Dropping the handle detaches the subscription, which keeps processing messages
+/// until the connection is closed.
+#[derive(Debug)]
+pub struct SubscriptionHandle {
+    subject: String,
+    control: watch::Sender<SubscriptionControl>,
+    task: JoinHandle<Result<(), NatsTransportError>>,
+}
+
+impl SubscriptionHandle {
+    pub(crate) fn new(
+        subject: String,
+        control: watch::Sender<SubscriptionControl>,
+        task: JoinHandle<Result<(), NatsTransportError>>,
+    ) -> Self {
+        Self {
+            subject,
+            control,
+            task,
+        }
+    }
+
+    /// Creates a handle for a receiver task spawned outside of this crate, e.g. by a mock
+    /// [Subscribe](super::Subscribe) implementation. Unsubscribe and drain signals are not
+    /// delivered to the task: they only wait for it to stop.
+    pub fn from_task(
+        subject: impl Into<String>,
+        task: JoinHandle<Result<(), NatsTransportError>>,
+    ) -> Self {
+        let (control, _) = watch::channel(SubscriptionControl::Active);
+        Self::new(subject.into(), control, task)
+    }
+
+    /// The subject (or subject pattern) of the subscription
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+
+    /// Returns true once the receiver task has stopped
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Unsubscribes immediately. Messages that were received but not yet processed are dropped.
+    pub async fn unsubscribe(self) -> Result<(), NatsTransportError> {
+        // the task may already have stopped, in which case there is nothing to signal
+        let _ = self.control.send(SubscriptionControl::Unsubscribe);
+        self.join().await
+    }
+
+    /// Unsubscribes and waits for the messages that were already received to be processed.
+    /// The receiver task is aborted if draining takes longer than `timeout`.
+    pub async fn drain(mut self, timeout: Duration) -> Result<(), NatsTransportError> {
+        let _ = self.control.send(SubscriptionControl::Drain);
+
+        match tokio::time::timeout(timeout, &mut self.task).await {
+            Ok(result) => result?,
+            Err(_) => {
+                self.task.abort();
+                Err(NatsTransportError::DrainTimeout(self.subject))
+            }
+        }
+    }
+
+    /// Waits for the receiver task to stop, returning its error (if any)
+    pub async fn join(self) -> Result<(), NatsTransportError> {
+        self.task.await?
+    }
+}
+
+#[cfg(test)]
+#[path = "./subscription_handle_tests.rs"]
+mod subscription_handle_tests;
@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod subscription_handle_tests {
+    use std::time::Duration;
+
+    use tokio::sync::watch;
+
+    use crate::server::{
+        receiver::{subscription_handle::SubscriptionControl, SubscriptionHandle},
+        NatsTransportError,
+    };
+
+    // Spawns a fake receiver task that stops once it is signalled
+    fn handle(stop_on: SubscriptionControl) -> SubscriptionHandle {
+        let (control_tx, mut control_rx) = watch::channel(SubscriptionControl::Active);
+
+        let task = tokio::spawn(async move {
+            while control_rx.changed().await.is_ok() {
+                if *control_rx.borrow() == stop_on {
+                    return Ok(());
+                }
+            }
+            Ok(())
+        });
+
+        SubscriptionHandle::new("chat.topic.>".to_string(), control_tx, task)
+    }
+
+    #[tokio::test]
+    async fn test_unsubscribe() {
+        let handle = handle(SubscriptionControl::Unsubscribe);
+
+        assert!(!handle.is_finished());
+        assert!(handle.unsubscribe().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_drain() {
+        let handle = handle(SubscriptionControl::Drain);
+
+        assert!(handle.drain(Duration::from_secs(1)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_drain_timeout() {
+        // never stops on a drain signal
+        let handle = handle(SubscriptionControl::Unsubscribe);
+
+        let result = handle.drain(Duration::from_millis(10)).await;
+
+        assert!(matches!(
+            result,
+            Err(NatsTransportError::DrainTimeout(subject)) if subject == "chat.topic.>"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_join_returns_task_error() {
+        let (control_tx, _) = watch::channel(SubscriptionControl::Active);
+        let task = tokio::spawn(async { Err(NatsTransportError::EmptyReply) });
+
+        let handle = SubscriptionHandle::new("chat.topic.>".to_string(), control_tx, task);
+
+        assert!(matches!(
+            handle.join().await,
+            Err(NatsTransportError::EmptyReply)
+        ));
+    }
+}
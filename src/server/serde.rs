@@ -6,14 +6,18 @@ use bytes::Bytes;
 pub use json::NatsJson;
 
 pub trait Serializer<T> {
-    fn serialize(&self, value: T) -> Bytes;
+    type Error;
+
+    fn serialize(&self, value: T) -> Result<Bytes, Self::Error>;
 }
 
-impl<T, F> Serializer<T> for F
+impl<T, Err, F> Serializer<T> for F
 where
-    F: Fn(T) -> Bytes,
+    F: Fn(T) -> Result<Bytes, Err>,
 {
-    fn serialize(&self, value: T) -> Bytes {
+    type Error = Err;
+
+    fn serialize(&self, value: T) -> Result<Bytes, Self::Error> {
         self(value)
     }
 }
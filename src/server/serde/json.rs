@@ -18,10 +18,10 @@ impl<T> Serializer<T> for NatsJson<T>
 where
     T: Serialize,
 {
-    fn serialize(&self, value: T) -> Bytes {
-        serde_json::to_vec(&value)
-            .expect("json serialization should not fail")
-            .into()
+    type Error = serde_json::Error;
+
+    fn serialize(&self, value: T) -> Result<Bytes, Self::Error> {
+        serde_json::to_vec(&value).map(Bytes::from)
     }
 }
 
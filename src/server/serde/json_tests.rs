@@ -1,5 +1,7 @@
 #[cfg(test)]
 mod json_tests {
+    use std::collections::BTreeMap;
+
     use serde::{Deserialize, Serialize};
 
     use crate::server::serde::{json::NatsJson, Deserializer, Serializer};
@@ -14,13 +16,21 @@ mod json_tests {
             credit: 23.23,
         };
 
-        let serialized_test_msg = serde.serialize(test_msg.clone());
+        let serialized_test_msg = serde.serialize(test_msg.clone()).unwrap();
 
         let deserized_test_msg = serde.deserialize(serialized_test_msg.into()).unwrap();
 
         assert_eq!(deserized_test_msg, test_msg);
     }
 
+    #[test]
+    fn test_json_serialize_error() {
+        let serde = NatsJson::<BTreeMap<(u8, u8), String>>::default();
+        let value = BTreeMap::from([((1, 2), "tuple keys are not valid json keys".to_string())]);
+
+        assert!(serde.serialize(value).is_err());
+    }
+
     #[derive(Debug, Serialize, Clone, Deserialize, PartialEq)]
     struct SerializableUser {
         pub id: String,
@@ -1,4 +1,4 @@
-use std::marker::PhantomData;
+use std::{convert::Infallible, marker::PhantomData};
 
 use prost::{bytes::Bytes, Message};
 
@@ -13,8 +13,11 @@ impl<T> Serializer<T> for NatsMessageSerde<T>
 where
     T: Message,
 {
-    fn serialize(&self, value: T) -> Bytes {
-        value.encode_to_vec().into()
+    /// Encoding into a growable buffer cannot fail
+    type Error = Infallible;
+
+    fn serialize(&self, value: T) -> Result<Bytes, Self::Error> {
+        Ok(value.encode_to_vec().into())
     }
 }
 
@@ -17,7 +17,7 @@ mod prost_tests {
             credit: 23.23,
         };
 
-        let serialized_test_msg = serde.serialize(test_msg.clone());
+        let serialized_test_msg = serde.serialize(test_msg.clone()).unwrap();
 
         //assert_eq!(vec![1], serialized_test_msg);
         // [10, 4, 49, 50, 51, 52, 18, 4, 116, 101, 115, 116, 25, 123, 20, 174, 71, 225, 58, 55, 64]
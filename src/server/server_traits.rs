@@ -1,8 +1,11 @@
+use std::{str::FromStr, time::Duration};
+
+use async_nats::{jetstream::publish::PublishAck, HeaderMap};
 use async_trait::async_trait;
 use prost::Message;
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Serialize};
 
-use crate::server::NatsTransportError;
+use crate::server::{ClientError, JetStreamPublishOptions, NatsTransportError};
 
 /// Publish is a [NatsServer] trait used to publish a NATS message using a gRPC protocol buffer
 #[async_trait]
@@ -13,6 +16,18 @@ where
     /// Loads an Aggregate Root instance from the data store,
     /// referenced by its unique identifier.
     async fn publish(&self, subject: String, msg: T) -> Result<(), NatsTransportError>;
+
+    /// Publishes the message with native NATS headers,
+    /// e.g. from [RequestHeaders](crate::request::RequestHeaders) with `headers.into()`.
+    /// The default implementation drops the headers and calls [publish](Self::publish).
+    async fn publish_with_headers(
+        &self,
+        subject: String,
+        _headers: HeaderMap,
+        msg: T,
+    ) -> Result<(), NatsTransportError> {
+        self.publish(subject, msg).await
+    }
 }
 
 /// Publish is a [NatsServer] trait used to publish a NATS message using JSON serialization
@@ -24,6 +39,57 @@ where
     /// Loads an Aggregate Root instance from the data store,
     /// referenced by its unique identifier.
     async fn publish(&self, subject: String, msg: T) -> Result<(), NatsTransportError>;
+
+    /// Publishes the message with native NATS headers,
+    /// e.g. from [RequestHeaders](crate::request::RequestHeaders) with `headers.into()`.
+    /// The default implementation drops the headers and calls [publish](Self::publish).
+    async fn publish_with_headers(
+        &self,
+        subject: String,
+        _headers: HeaderMap,
+        msg: T,
+    ) -> Result<(), NatsTransportError>
+    where
+        T: Send,
+    {
+        self.publish(subject, msg).await
+    }
+}
+
+/// JetStreamPublish is a [NatsServer] trait used to durably publish a NATS message to a JetStream stream
+/// using a gRPC protocol buffer
+#[async_trait]
+pub trait JetStreamPublishProst<T>: Send + Sync
+where
+    T: Message + Default + 'static,
+{
+    /// Publishes the message and waits for the stream to acknowledge that it was stored.
+    /// The [JetStreamPublishOptions] set the deduplication id and the expected last sequence checks.
+    async fn jetstream_publish(
+        &self,
+        subject: String,
+        headers: Option<HeaderMap>,
+        msg: T,
+        options: JetStreamPublishOptions,
+    ) -> Result<PublishAck, NatsTransportError>;
+}
+
+/// JetStreamPublish is a [NatsServer] trait used to durably publish a NATS message to a JetStream stream
+/// using JSON serialization
+#[async_trait]
+pub trait JetStreamPublishJson<T>: Send + Sync
+where
+    T: Serialize + 'static,
+{
+    /// Publishes the message and waits for the stream to acknowledge that it was stored.
+    /// The [JetStreamPublishOptions] set the deduplication id and the expected last sequence checks.
+    async fn jetstream_publish(
+        &self,
+        subject: String,
+        headers: Option<HeaderMap>,
+        msg: T,
+        options: JetStreamPublishOptions,
+    ) -> Result<PublishAck, NatsTransportError>;
 }
 
 /// Request is a [NatsServer] trait used to perform a request/reply NATS message using a gRPC protocol buffer
@@ -39,6 +105,40 @@ where
         subject: String,
         msg: T,
     ) -> Result<async_nats::Message, NatsTransportError>;
+
+    /// Sends the request with native NATS headers,
+    /// e.g. from [RequestHeaders](crate::request::RequestHeaders) with `headers.into()`.
+    /// The default implementation drops the headers and calls [request](Self::request).
+    async fn request_with_headers(
+        &self,
+        subject: String,
+        _headers: HeaderMap,
+        msg: T,
+    ) -> Result<async_nats::Message, NatsTransportError> {
+        self.request(subject, msg).await
+    }
+
+    /// Sends the request with native NATS headers, failing with [NatsTransportError::RequestTimeout]
+    /// when no reply arrived within the timeout. The deadline is sent in the
+    /// [DEADLINE_HEADER](crate::request::DEADLINE_HEADER) header, so the handler is cancelled
+    /// once it passed; use [NatsEnvelope::remaining](crate::request::NatsEnvelope::remaining)
+    /// as the timeout to propagate the deadline of the request being handled.
+    /// The default implementation only bounds [request_with_headers](Self::request_with_headers)
+    /// by the timeout, without sending the deadline.
+    async fn request_with_timeout(
+        &self,
+        subject: String,
+        headers: HeaderMap,
+        msg: T,
+        timeout: Duration,
+    ) -> Result<async_nats::Message, NatsTransportError> {
+        tokio::time::timeout(
+            timeout,
+            self.request_with_headers(subject.clone(), headers, msg),
+        )
+        .await
+        .map_err(|_| NatsTransportError::RequestTimeout(subject))?
+    }
 }
 
 /// Request is a [NatsServer] trait used to perform a request/reply NATS message using JSON serialization
@@ -54,4 +154,67 @@ where
         subject: String,
         msg: T,
     ) -> Result<async_nats::Message, NatsTransportError>;
+
+    /// Sends the request with native NATS headers,
+    /// e.g. from [RequestHeaders](crate::request::RequestHeaders) with `headers.into()`.
+    /// The default implementation drops the headers and calls [request](Self::request).
+    async fn request_with_headers(
+        &self,
+        subject: String,
+        _headers: HeaderMap,
+        msg: T,
+    ) -> Result<async_nats::Message, NatsTransportError> {
+        self.request(subject, msg).await
+    }
+
+    /// Sends the request with native NATS headers, failing with [NatsTransportError::RequestTimeout]
+    /// when no reply arrived within the timeout. The deadline is sent in the
+    /// [DEADLINE_HEADER](crate::request::DEADLINE_HEADER) header, so the handler is cancelled
+    /// once it passed; use [NatsEnvelope::remaining](crate::request::NatsEnvelope::remaining)
+    /// as the timeout to propagate the deadline of the request being handled.
+    /// The default implementation only bounds [request_with_headers](Self::request_with_headers)
+    /// by the timeout, without sending the deadline.
+    async fn request_with_timeout(
+        &self,
+        subject: String,
+        headers: HeaderMap,
+        msg: T,
+        timeout: Duration,
+    ) -> Result<async_nats::Message, NatsTransportError> {
+        tokio::time::timeout(
+            timeout,
+            self.request_with_headers(subject.clone(), headers, msg),
+        )
+        .await
+        .map_err(|_| NatsTransportError::RequestTimeout(subject))?
+    }
+}
+
+/// RequestReply is a [NatsServer] trait used to perform a request/reply NATS message using JSON serialization,
+/// decoding the reply as a [NatsResponse](crate::response::NatsResponse)
+#[async_trait]
+pub trait RequestReplyJson<T, Resp, R>: Send + Sync
+where
+    T: Serialize + Send + Sync + 'static,
+    Resp: DeserializeOwned + Send + 'static,
+    R: DeserializeOwned + Send + 'static,
+{
+    /// Sends the request and returns the reply data, or the [ErrorModel](crate::error::ErrorModel)
+    /// returned by the remote service.
+    async fn request_reply(&self, subject: String, msg: T) -> Result<Resp, ClientError<R>>;
+}
+
+/// RequestReply is a [NatsServer] trait used to perform a request/reply NATS message using a gRPC protocol buffer,
+/// decoding the reply into the response message
+#[async_trait]
+pub trait RequestReplyProst<T, Resp, R>: Send + Sync
+where
+    T: Message + Default + Send + Sync + 'static,
+    Resp: Message + Default + Send + 'static,
+    R: FromStr + Send + 'static,
+{
+    /// Sends the request and returns the decoded reply message, or the error reply
+    /// returned by the remote service (flagged by the [ERROR_REPLY_HEADER](crate::response::ERROR_REPLY_HEADER) header)
+    /// converted back into an [ErrorModel](crate::error::ErrorModel).
+    async fn request_reply(&self, subject: String, msg: T) -> Result<Resp, ClientError<R>>;
 }
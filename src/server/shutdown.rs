@@ -0,0 +1,116 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::sync::{mpsc, watch};
+
+use crate::server::NatsTransportError;
+
+/// Coordinates the graceful shutdown of a [NatsServer](crate::server::NatsServer) and
+/// the subscriptions registered against it.
+///
+/// Each subscription holds a [ShutdownListener]. Shutdown signals every listener and then
+/// waits until all of them have been dropped, i.e. until every receiver task has drained
+/// its subscription and finished its in-flight messages.
+#[derive(Debug)]
+pub(crate) struct ShutdownCoordinator {
+    signal: watch::Sender<bool>,
+    // Cloned into every listener. The receiver completes once the coordinator's
+    // sender has been taken and all listeners are dropped.
+    active_tx: Mutex<Option<mpsc::Sender<()>>>,
+    active_rx: tokio::sync::Mutex<mpsc::Receiver<()>>,
+}
+
+/// Held by a receiver task for as long as it is running
+#[derive(Debug)]
+pub(crate) struct ShutdownListener {
+    signal: watch::Receiver<bool>,
+    _active: mpsc::Sender<()>,
+}
+
+impl ShutdownCoordinator {
+    pub fn new() -> Self {
+        let (signal, _) = watch::channel(false);
+        let (active_tx, active_rx) = mpsc::channel(1);
+
+        Self {
+            signal,
+            active_tx: Mutex::new(Some(active_tx)),
+            active_rx: tokio::sync::Mutex::new(active_rx),
+        }
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        *self.signal.borrow()
+    }
+
+    /// Registers a new listener, or fails if the shutdown has already started
+    pub fn listener(&self) -> Result<ShutdownListener, NatsTransportError> {
+        let active = self
+            .active_tx
+            .lock()
+            .expect("shutdown lock poisoned")
+            .clone()
+            .ok_or(NatsTransportError::ShuttingDown)?;
+
+        Ok(ShutdownListener {
+            signal: self.signal.subscribe(),
+            _active: active,
+        })
+    }
+
+    /// Signals all listeners and waits (up to the deadline) for them to be dropped
+    pub async fn shutdown(&self, deadline: Duration) -> Result<(), NatsTransportError> {
+        self.signal.send_replace(true);
+        self.active_tx
+            .lock()
+            .expect("shutdown lock poisoned")
+            .take();
+
+        let mut active_rx = self.active_rx.lock().await;
+
+        // listeners never send, so this only returns once every sender has been dropped
+        tokio::time::timeout(deadline, active_rx.recv())
+            .await
+            .map(|_| ())
+            .map_err(|_| NatsTransportError::ShutdownTimeout)
+    }
+}
+
+impl ShutdownListener {
+    /// Completes once shutdown has been requested
+    pub async fn requested(&mut self) {
+        while !*self.signal.borrow_and_update() {
+            if self.signal.changed().await.is_err() {
+                // the server was dropped without shutting down
+                futures::future::pending::<()>().await;
+            }
+        }
+    }
+}
+
+/// Completes when the process receives SIGTERM or SIGINT (Ctrl-C)
+pub(crate) async fn termination_signal() -> Result<(), NatsTransportError> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut sigterm =
+            signal(SignalKind::terminate()).map_err(NatsTransportError::SignalError)?;
+
+        tokio::select! {
+            _ = sigterm.recv() => Ok(()),
+            result = tokio::signal::ctrl_c() => result.map_err(NatsTransportError::SignalError),
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c()
+            .await
+            .map_err(NatsTransportError::SignalError)
+    }
+}
+
+#[cfg(test)]
+#[path = "./shutdown_tests.rs"]
+mod shutdown_tests;
@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod shutdown_tests {
+    use std::time::Duration;
+
+    use crate::server::{shutdown::ShutdownCoordinator, NatsTransportError};
+
+    #[tokio::test]
+    async fn test_shutdown_waits_for_listeners() {
+        let coordinator = ShutdownCoordinator::new();
+        let mut listener = coordinator.listener().unwrap();
+
+        // simulated receiver task: stops once shutdown is requested
+        let task = tokio::spawn(async move {
+            listener.requested().await;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        });
+
+        assert!(!coordinator.is_shutting_down());
+        coordinator.shutdown(Duration::from_secs(1)).await.unwrap();
+
+        assert!(coordinator.is_shutting_down());
+        assert!(task.is_finished());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_timeout() {
+        let coordinator = ShutdownCoordinator::new();
+        let _listener = coordinator.listener().unwrap();
+
+        let result = coordinator.shutdown(Duration::from_millis(10)).await;
+
+        assert!(matches!(result, Err(NatsTransportError::ShutdownTimeout)));
+    }
+
+    #[tokio::test]
+    async fn test_no_listeners_after_shutdown() {
+        let coordinator = ShutdownCoordinator::new();
+
+        coordinator.shutdown(Duration::from_secs(1)).await.unwrap();
+
+        assert!(matches!(
+            coordinator.listener(),
+            Err(NatsTransportError::ShuttingDown)
+        ));
+    }
+}
@@ -1,7 +1,353 @@
+use std::{fmt, str::FromStr};
+
+use crate::server::NatsTransportError;
+
+/// First token of every subject of the chat services
+pub const SUBJECT_PREFIX: &str = "chat";
+
+const ANY_TOKEN: &str = "*";
+const REST_TOKEN: &str = ">";
+
+#[deprecated(note = "use Subject::event, or SubjectPattern for wildcard subjects")]
 pub struct SubjectName {}
 
+#[allow(deprecated)]
 impl SubjectName {
     pub fn chat_event(domain: &str, evt_name: &str) -> String {
         format!("chat.{}.event.{}", domain, evt_name)
     }
 }
+
+/// Kind of message sent on a subject
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SubjectKind {
+    Command,
+    Event,
+    Query,
+}
+
+/// Subject of a chat message: `chat.<domain>.<kind>.<name>[.<id>]`, e.g.
+/// `chat.channel.command.create` or `chat.chatgroup.event.renamed.<chatgroup_id>`.
+///
+/// Tokens are validated when the subject is built or parsed: they may not be empty,
+/// nor contain spaces, dots or wildcards.
+///
+/// ```
+/// use nats_transport::{Subject, SubjectKind};
+///
+/// let subject = Subject::command("channel", "create")?.with_id("1234")?;
+/// assert_eq!(subject.to_string(), "chat.channel.command.create.1234");
+///
+/// // e.g. from `message.subject` in a handler
+/// let parsed: Subject = "chat.channel.command.create.1234".parse()?;
+/// assert_eq!(parsed.kind(), SubjectKind::Command);
+/// assert_eq!(parsed.id(), Some("1234"));
+/// # Ok::<(), nats_transport::server::NatsTransportError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Subject {
+    domain: String,
+    kind: SubjectKind,
+    name: String,
+    id: Option<String>,
+}
+
+/// Pattern matching [Subject]s, for subscriptions and stream or consumer filters.
+///
+/// Segments that are not set match any token (`*`). A segment set to `*` matches any token
+/// as well, and a segment set to `>` matches all remaining tokens. The id segment is only part
+/// of the pattern when set.
+///
+/// ```
+/// use nats_transport::{SubjectKind, SubjectPattern};
+///
+/// let pattern = SubjectPattern::new().domain("chatgroup").kind(SubjectKind::Event).name(">");
+/// assert_eq!(pattern.build()?, "chat.chatgroup.event.>");
+///
+/// let pattern = SubjectPattern::new().kind(SubjectKind::Command).name("create");
+/// assert_eq!(pattern.build()?, "chat.*.command.create");
+/// # Ok::<(), nats_transport::server::NatsTransportError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SubjectPattern {
+    domain: Option<String>,
+    kind: Option<SubjectKind>,
+    name: Option<String>,
+    id: Option<String>,
+}
+
+impl SubjectKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubjectKind::Command => "command",
+            SubjectKind::Event => "event",
+            SubjectKind::Query => "query",
+        }
+    }
+}
+
+impl fmt::Display for SubjectKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SubjectKind {
+    type Err = NatsTransportError;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind {
+            "command" => Ok(SubjectKind::Command),
+            "event" => Ok(SubjectKind::Event),
+            "query" => Ok(SubjectKind::Query),
+            kind => Err(NatsTransportError::InvalidSubject(format!(
+                "unknown subject kind: {}",
+                kind
+            ))),
+        }
+    }
+}
+
+impl Subject {
+    pub fn new(
+        domain: impl Into<String>,
+        kind: SubjectKind,
+        name: impl Into<String>,
+    ) -> Result<Self, NatsTransportError> {
+        Ok(Self {
+            domain: literal(domain.into())?,
+            kind,
+            name: literal(name.into())?,
+            id: None,
+        })
+    }
+
+    pub fn command(
+        domain: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Result<Self, NatsTransportError> {
+        Self::new(domain, SubjectKind::Command, name)
+    }
+
+    pub fn event(
+        domain: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Result<Self, NatsTransportError> {
+        Self::new(domain, SubjectKind::Event, name)
+    }
+
+    pub fn query(
+        domain: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Result<Self, NatsTransportError> {
+        Self::new(domain, SubjectKind::Query, name)
+    }
+
+    /// Appends the id of the entity the message applies to
+    pub fn with_id(mut self, id: impl Into<String>) -> Result<Self, NatsTransportError> {
+        self.id = Some(literal(id.into())?);
+        Ok(self)
+    }
+
+    /// Parses a subject received from NATS, e.g. `message.subject`
+    pub fn parse(subject: &str) -> Result<Self, NatsTransportError> {
+        let invalid = || {
+            NatsTransportError::InvalidSubject(format!(
+                "expected chat.<domain>.<kind>.<name>[.<id>]: {}",
+                subject
+            ))
+        };
+
+        let tokens: Vec<&str> = subject.split('.').collect();
+        let (domain, kind, name, id) = match tokens.as_slice() {
+            [SUBJECT_PREFIX, domain, kind, name] => (domain, kind, name, None),
+            [SUBJECT_PREFIX, domain, kind, name, id] => (domain, kind, name, Some(id)),
+            _ => return Err(invalid()),
+        };
+
+        let subject = Self::new(*domain, kind.parse()?, *name)?;
+        match id {
+            Some(id) => subject.with_id(*id),
+            None => Ok(subject),
+        }
+    }
+
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    pub fn kind(&self) -> SubjectKind {
+        self.kind
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+}
+
+impl fmt::Display for Subject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{}.{}.{}",
+            SUBJECT_PREFIX, self.domain, self.kind, self.name
+        )?;
+
+        if let Some(id) = &self.id {
+            write!(f, ".{}", id)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Subject {
+    type Err = NatsTransportError;
+
+    fn from_str(subject: &str) -> Result<Self, Self::Err> {
+        Self::parse(subject)
+    }
+}
+
+impl From<Subject> for String {
+    fn from(subject: Subject) -> Self {
+        subject.to_string()
+    }
+}
+
+impl SubjectPattern {
+    /// Pattern matching all chat subjects without an id: `chat.*.*.*`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn domain(mut self, domain: impl Into<String>) -> Self {
+        self.domain = Some(domain.into());
+        self
+    }
+
+    pub fn kind(mut self, kind: SubjectKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Validates the literal tokens and returns the pattern as a NATS subject
+    pub fn build(&self) -> Result<String, NatsTransportError> {
+        let tokens = self.tokens();
+
+        for (position, token) in tokens.iter().enumerate() {
+            match *token {
+                REST_TOKEN if position != tokens.len() - 1 => {
+                    return Err(NatsTransportError::InvalidSubject(format!(
+                        "'>' must be the last token: {}",
+                        self
+                    )))
+                }
+                ANY_TOKEN | REST_TOKEN => {}
+                token => validate(token)?,
+            }
+        }
+
+        Ok(self.to_string())
+    }
+
+    /// Returns true if the subject matches the pattern
+    pub fn matches(&self, subject: &Subject) -> bool {
+        let kind = subject.kind.as_str();
+        let subject_tokens = [
+            subject.domain.as_str(),
+            kind,
+            subject.name.as_str(),
+            subject.id.as_deref().unwrap_or_default(),
+        ];
+        let subject_len = if subject.id.is_some() { 4 } else { 3 };
+
+        let tokens = self.tokens();
+        for (position, token) in tokens.iter().enumerate() {
+            match *token {
+                REST_TOKEN => return position < subject_len,
+                _ if position >= subject_len => return false,
+                ANY_TOKEN => continue,
+                token if token != subject_tokens[position] => return false,
+                _ => continue,
+            }
+        }
+
+        tokens.len() == subject_len
+    }
+
+    /// Tokens following the `chat` prefix, up to the first `>`
+    fn tokens(&self) -> Vec<&str> {
+        let segments = [
+            self.domain.as_deref(),
+            self.kind.as_ref().map(SubjectKind::as_str),
+            self.name.as_deref(),
+        ];
+
+        let mut tokens: Vec<&str> = segments
+            .into_iter()
+            .map(|segment| segment.unwrap_or(ANY_TOKEN))
+            .collect();
+        tokens.extend(self.id.as_deref());
+
+        match tokens.iter().position(|token| *token == REST_TOKEN) {
+            // tokens set after the first '>' are reported by build
+            Some(rest) if tokens[rest + 1..].iter().all(|token| *token == ANY_TOKEN) => {
+                tokens.truncate(rest + 1);
+                tokens
+            }
+            _ => tokens,
+        }
+    }
+}
+
+impl fmt::Display for SubjectPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", SUBJECT_PREFIX)?;
+        for token in self.tokens() {
+            write!(f, ".{}", token)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn literal(token: String) -> Result<String, NatsTransportError> {
+    validate(&token)?;
+    Ok(token)
+}
+
+/// Literal tokens may not be empty, nor contain whitespace, dots or wildcards
+fn validate(token: &str) -> Result<(), NatsTransportError> {
+    let invalid = token.is_empty()
+        || token
+            .chars()
+            .any(|c| c.is_whitespace() || c == '.' || c == '*' || c == '>');
+
+    if invalid {
+        return Err(NatsTransportError::InvalidSubject(format!(
+            "invalid subject token: '{}'",
+            token
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "./subject_tests.rs"]
+mod subject_tests;
@@ -0,0 +1,127 @@
+#[cfg(test)]
+mod subject_tests {
+    use crate::{server::NatsTransportError, Subject, SubjectKind, SubjectPattern};
+
+    #[test]
+    fn test_build_subject() {
+        let subject = Subject::event("chatgroup", "renamed").unwrap();
+        assert_eq!(subject.to_string(), "chat.chatgroup.event.renamed");
+
+        let subject = Subject::query("user", "get")
+            .unwrap()
+            .with_id("42")
+            .unwrap();
+        assert_eq!(subject.to_string(), "chat.user.query.get.42");
+    }
+
+    #[test]
+    fn test_parse_subject() {
+        let subject: Subject = "chat.channel.command.create.[channel_id]".parse().unwrap();
+
+        assert_eq!(subject.domain(), "channel");
+        assert_eq!(subject.kind(), SubjectKind::Command);
+        assert_eq!(subject.name(), "create");
+        assert_eq!(subject.id(), Some("[channel_id]"));
+        assert_eq!(
+            subject.to_string(),
+            "chat.channel.command.create.[channel_id]"
+        );
+
+        let subject = Subject::parse("chat.chatgroup.event.created").unwrap();
+        assert_eq!(subject.id(), None);
+    }
+
+    #[test]
+    fn test_parse_invalid_subject() {
+        for subject in [
+            "chat.chatgroup.event",
+            "chat.chatgroup.event.created.123.456",
+            "billing.invoice.event.paid",
+            "chat.chatgroup.notification.created",
+            "chat.chatgroup.event.*",
+            "chat..event.created",
+        ] {
+            assert!(
+                matches!(
+                    Subject::parse(subject),
+                    Err(NatsTransportError::InvalidSubject(_))
+                ),
+                "{}",
+                subject
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_tokens() {
+        assert!(Subject::event("chat group", "created").is_err());
+        assert!(Subject::event("chatgroup", "created.v2").is_err());
+        assert!(Subject::event("chatgroup", ">").is_err());
+        assert!(Subject::event("", "created").is_err());
+        assert!(Subject::event("chatgroup", "created")
+            .unwrap()
+            .with_id("*")
+            .is_err());
+    }
+
+    #[test]
+    fn test_patterns() {
+        assert_eq!(SubjectPattern::new().build().unwrap(), "chat.*.*.*");
+        assert_eq!(SubjectPattern::new().domain(">").build().unwrap(), "chat.>");
+        assert_eq!(
+            SubjectPattern::new()
+                .domain("chatgroup")
+                .kind(SubjectKind::Event)
+                .name("*")
+                .build()
+                .unwrap(),
+            "chat.chatgroup.event.*"
+        );
+        assert_eq!(
+            SubjectPattern::new()
+                .kind(SubjectKind::Command)
+                .name("create")
+                .id("*")
+                .build()
+                .unwrap(),
+            "chat.*.command.create.*"
+        );
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        assert!(SubjectPattern::new().domain("chat group").build().is_err());
+        assert!(SubjectPattern::new()
+            .domain(">")
+            .kind(SubjectKind::Event)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_pattern_matches() {
+        let created = Subject::event("chatgroup", "created").unwrap();
+        let renamed = Subject::event("chatgroup", "renamed")
+            .unwrap()
+            .with_id("123")
+            .unwrap();
+
+        let all_events = SubjectPattern::new()
+            .domain("chatgroup")
+            .kind(SubjectKind::Event)
+            .name(">");
+        assert!(all_events.matches(&created));
+        assert!(all_events.matches(&renamed));
+
+        let without_id = SubjectPattern::new().domain("chatgroup");
+        assert!(without_id.matches(&created));
+        assert!(!without_id.matches(&renamed));
+
+        let with_id = SubjectPattern::new().name("renamed").id("*");
+        assert!(!with_id.matches(&created));
+        assert!(with_id.matches(&renamed));
+
+        let commands = SubjectPattern::new().kind(SubjectKind::Command).name(">");
+        assert!(!commands.matches(&created));
+    }
+}